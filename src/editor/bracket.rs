@@ -0,0 +1,340 @@
+//! An index of brackets for matching them quickly.
+//!
+//! `BracketIndex` records every `()`, `[]` and `{}` of a buffer sorted by
+//! offset, together with its partner and the pair enclosing it, so that
+//! matching and enclosing-pair queries take a binary search. Brackets can
+//! be excluded, e.g. ones inside strings or comments. After an edit, only
+//! the edited characters are read, and brackets are paired again from them
+//! on until the unclosed open brackets are the same as before the edit.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::bracket::BracketIndex;
+//!
+//! let buffer: Vec<char> = "f(a[0], \")\")".chars().collect();
+//! let index = BracketIndex::new(&buffer, |i| (8..11).contains(&i));
+//! assert_eq!(index.matching(1), Some(11));
+//! assert_eq!(index.enclosing(6), Some((1, 11)));
+//! ```
+
+use std::ops::Range;
+
+/// Returns the partner of a bracket character, and whether it opens a pair.
+pub fn bracket_kind(ch: char) -> Option<(char, bool)> {
+    match ch {
+        '(' => Some((')', true)),
+        '[' => Some((']', true)),
+        '{' => Some(('}', true)),
+        ')' => Some(('(', false)),
+        ']' => Some(('[', false)),
+        '}' => Some(('{', false)),
+        _ => None,
+    }
+}
+
+/// An index of a bracket which was removed by an edit.
+const REMOVED: usize = usize::MAX;
+
+/// Returns the innermost unclosed open bracket after the bracket `b` at
+/// index `i`.
+fn top_after(b: &Bracket, i: usize) -> Option<usize> {
+    if b.open {
+        Some(i)
+    } else {
+        b.parent
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Bracket {
+    offset: usize,
+    open: bool,
+    /// The index of the partner.
+    partner: Option<usize>,
+    /// The index of the open bracket which was innermost when this one was read.
+    parent: Option<usize>,
+}
+
+/// Brackets of a buffer.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BracketIndex {
+    brackets: Vec<Bracket>,
+}
+
+impl BracketIndex {
+    /// Indexes brackets of a buffer, excluding ones at offsets for which `skip` returns `true`.
+    pub fn new<F>(buffer: &[char], skip: F) -> BracketIndex
+    where
+        F: Fn(usize) -> bool,
+    {
+        let mut index = BracketIndex::default();
+        index.reindex(buffer, 0..buffer.len(), skip);
+        index
+    }
+
+    /// Reads brackets of `buffer` again in `range`, e.g. after brackets to
+    /// skip there changed, excluding ones for which `skip` returns `true`.
+    pub fn reindex<F>(&mut self, buffer: &[char], range: Range<usize>, skip: F)
+    where
+        F: Fn(usize) -> bool,
+    {
+        self.splice(buffer, range.clone(), range, skip);
+    }
+
+    /// Replaces brackets in the `old` range of offsets with the ones of
+    /// `buffer` in the `new` range, shifting the brackets after it, and pairs
+    /// brackets again from there on until the pairs are the same as before.
+    fn splice<F>(&mut self, buffer: &[char], old: Range<usize>, new: Range<usize>, skip: F)
+    where
+        F: Fn(usize) -> bool,
+    {
+        if old.is_empty() && new.is_empty() {
+            return;
+        }
+        let first = self.position(old.start);
+        let last = self.position(old.end);
+        let read: Vec<Bracket> = new.clone()
+            .filter(|&offset| !skip(offset))
+            .filter_map(|offset| {
+                bracket_kind(buffer[offset]).map(|(_, open)| Bracket {
+                    offset,
+                    open,
+                    partner: None,
+                    parent: None,
+                })
+            })
+            .collect();
+        let resume = first + read.len();
+        let moved = |i: usize| {
+            if i < first {
+                i
+            } else if i >= last {
+                i - last + resume
+            } else {
+                REMOVED
+            }
+        };
+        for b in &mut self.brackets[last..] {
+            b.offset = b.offset - old.len() + new.len();
+            b.partner = b.partner.map(moved);
+            b.parent = b.parent.map(moved);
+        }
+        let mut top = first.checked_sub(1).and_then(|i| top_after(&self.brackets[i], i));
+        while let Some(t) = top {
+            self.brackets[t].partner = self.brackets[t].partner.map(moved);
+            top = self.brackets[t].parent;
+        }
+        let old_top = last.checked_sub(1).and_then(|i| top_after(&self.brackets[i], i)).map(moved);
+        self.brackets.splice(first..last, read);
+        self.pair(buffer, first, resume, old_top);
+    }
+
+    /// Returns the index of the first bracket at or after `offset`.
+    fn position(&self, offset: usize) -> usize {
+        self.brackets
+            .binary_search_by_key(&offset, |b| b.offset)
+            .unwrap_or_else(|i| i)
+    }
+
+    /// Pairs brackets from index `first` on, keeping pairs before it.
+    ///
+    /// Brackets from index `resume` on were paired before, where `old_top`
+    /// was the innermost unclosed open bracket just before them. Pairing
+    /// stops once the unclosed open brackets are the same as before, from
+    /// where the pairs are the same too.
+    fn pair(&mut self, buffer: &[char], first: usize, resume: usize, mut old_top: Option<usize>) {
+        // Open brackets which are still unclosed form a chain of parents
+        // from the innermost one after the last kept bracket.
+        let mut stack: Vec<usize> = Vec::new();
+        let mut top = first.checked_sub(1).and_then(|i| top_after(&self.brackets[i], i));
+        while let Some(t) = top {
+            stack.push(t);
+            top = self.brackets[t].parent;
+        }
+        stack.reverse();
+        // Their partners are found again, unless pairing stops.
+        let kept: Vec<Option<usize>> = stack.iter().map(|&t| self.brackets[t].partner.take()).collect();
+        for i in first..self.brackets.len() {
+            if i >= resume {
+                // The stacks below the same innermost bracket differ if it
+                // was pushed again here, as pairing would have stopped then,
+                // so only stacks of brackets before `first` can be the same.
+                let top = stack.last().cloned();
+                if top == old_top && top.is_none_or(|t| t < first) {
+                    for (k, &t) in stack.iter().enumerate() {
+                        self.brackets[t].partner = kept[k];
+                    }
+                    return;
+                }
+                old_top = top_after(&self.brackets[i], i);
+            }
+            let (partner, open) = bracket_kind(buffer[self.brackets[i].offset]).unwrap();
+            self.brackets[i].partner = None;
+            self.brackets[i].parent = stack.last().cloned();
+            // A close bracket pairs with the innermost open one of its kind,
+            // leaving open brackets inside unmatched.
+            let pair = if open {
+                None
+            } else {
+                stack.iter().rposition(|&t| buffer[self.brackets[t].offset] == partner)
+            };
+            if open {
+                stack.push(i);
+            } else if let Some(depth) = pair {
+                let t = stack[depth];
+                stack.truncate(depth);
+                self.brackets[t].partner = Some(i);
+                self.brackets[i].partner = Some(t);
+                self.brackets[i].parent = self.brackets[t].parent;
+            }
+        }
+    }
+
+    fn find(&self, offset: usize) -> Option<usize> {
+        self.brackets.binary_search_by_key(&offset, |b| b.offset).ok()
+    }
+
+    /// Returns the offset of the partner of the bracket at `offset`.
+    pub fn matching(&self, offset: usize) -> Option<usize> {
+        let i = self.find(offset)?;
+        self.brackets[i].partner.map(|p| self.brackets[p].offset)
+    }
+
+    /// Returns offsets of the innermost matched pair which contains `offset`,
+    /// where a bracket belongs to its own pair.
+    pub fn enclosing(&self, offset: usize) -> Option<(usize, usize)> {
+        let n = match self.brackets.binary_search_by_key(&offset, |b| b.offset) {
+            Ok(i) => i,
+            Err(0) => return None,
+            Err(i) => i - 1,
+        };
+        let b = &self.brackets[n];
+        let mut open = match b.partner {
+            Some(_) if b.open => Some(n),
+            Some(p) if b.offset == offset => Some(p),
+            _ => b.parent,
+        };
+        while let Some(i) = open {
+            if let Some(p) = self.brackets[i].partner {
+                return Some((self.brackets[i].offset, self.brackets[p].offset));
+            }
+            open = self.brackets[i].parent;
+        }
+        None
+    }
+
+    /// Returns offsets of brackets which have no partner.
+    pub fn unmatched(&self) -> Vec<usize> {
+        self.brackets
+            .iter()
+            .filter(|b| b.partner.is_none())
+            .map(|b| b.offset)
+            .collect()
+    }
+
+    /// Updates brackets for `removed` characters at `offset` replaced with
+    /// `inserted` ones, where `buffer` is the edited buffer and `skip`
+    /// excludes inserted brackets.
+    pub fn edit<F>(&mut self, buffer: &[char], offset: usize, removed: usize, inserted: usize, skip: F)
+    where
+        F: Fn(usize) -> bool,
+    {
+        self.splice(buffer, offset..offset + removed, offset..offset + inserted, skip);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use editor::testing::simulate;
+
+    fn build(s: &str) -> BracketIndex {
+        let buffer: Vec<char> = s.chars().collect();
+        BracketIndex::new(&buffer, |_| false)
+    }
+
+    #[test]
+    fn test_matching() {
+        let index = build("a(b[c]{d})e");
+        assert_eq!(index.matching(1), Some(9));
+        assert_eq!(index.matching(9), Some(1));
+        assert_eq!(index.matching(3), Some(5));
+        assert_eq!(index.matching(6), Some(8));
+        assert_eq!(index.matching(0), None);
+        assert!(index.unmatched().is_empty());
+    }
+
+    #[test]
+    fn test_enclosing() {
+        let index = build("(a [b] c) d (e");
+        assert_eq!(index.enclosing(1), Some((0, 8)));
+        assert_eq!(index.enclosing(4), Some((3, 5)));
+        assert_eq!(index.enclosing(5), Some((3, 5)));
+        assert_eq!(index.enclosing(7), Some((0, 8)));
+        assert_eq!(index.enclosing(0), Some((0, 8)));
+        assert_eq!(index.enclosing(10), None);
+        assert_eq!(index.enclosing(13), None);
+
+        // An unmatched open bracket is skipped over.
+        let index = build("{ ( }");
+        assert_eq!(index.enclosing(3), Some((0, 4)));
+    }
+
+    #[test]
+    fn test_unmatched() {
+        let index = build("(]) ) {");
+        assert_eq!(index.unmatched(), vec![1, 4, 6]);
+        assert_eq!(index.matching(0), Some(2));
+    }
+
+    #[test]
+    fn test_edit() {
+        let edit = |index: &mut BracketIndex, s: &str, offset, removed, inserted| {
+            let buffer: Vec<char> = s.chars().collect();
+            index.edit(&buffer, offset, removed, inserted, |_| false);
+            assert_eq!(*index, build(s));
+        };
+        let mut index = build("a (b) c");
+        edit(&mut index, "a (xy) c", 3, 1, 2);
+        assert_eq!(index.matching(2), Some(5));
+        edit(&mut index, "za (xy) c", 0, 0, 1);
+        assert_eq!(index.matching(3), Some(6));
+        edit(&mut index, "(za (xy) c", 0, 0, 1);
+        assert_eq!(index.matching(4), Some(7));
+        assert_eq!(index.unmatched(), vec![0]);
+        edit(&mut index, "(za (xy) c]", 10, 0, 1);
+        edit(&mut index, "(za (xy]) c]", 7, 0, 1);
+        assert_eq!(index.matching(4), Some(8));
+        edit(&mut index, "(za (x c]", 6, 3, 0);
+        assert_eq!(index.unmatched(), vec![0, 4, 8]);
+    }
+
+    #[test]
+    fn test_random_edits() {
+        simulate(|rng| {
+            let mut buffer: Vec<char> = Vec::new();
+            let mut index = BracketIndex::default();
+            for _ in 0..40 {
+                let offset = rng.below(buffer.len() + 1);
+                let removed = rng.below(buffer.len() - offset + 1).min(3);
+                let inserted: Vec<char> = (0..rng.below(4))
+                    .map(|_| ['(', ')', '{', '}', '[', ']', 'a'][rng.below(7)])
+                    .collect();
+                buffer.splice(offset..offset + removed, inserted.iter().cloned());
+                index.edit(&buffer, offset, removed, inserted.len(), |_| false);
+                assert_eq!(index, BracketIndex::new(&buffer, |_| false), "{:?}", buffer);
+            }
+        });
+    }
+
+    #[test]
+    fn test_reindex() {
+        let buffer: Vec<char> = "f(\")\", [1])".chars().collect();
+        let mut index = BracketIndex::new(&buffer, |_| false);
+        assert_eq!(index.matching(1), Some(3));
+        index.reindex(&buffer, 2..5, |i| (2..5).contains(&i));
+        assert_eq!(index, BracketIndex::new(&buffer, |i| (2..5).contains(&i)));
+        assert_eq!(index.matching(1), Some(10));
+    }
+}
@@ -0,0 +1,168 @@
+//! Case conversion of text.
+//!
+//! Characters are mapped with Unicode case mapping, so a character may
+//! become several, like `ß` to `SS`. Identifier cases split each run of
+//! alphanumerics, `_` and `-` into words at separators and case changes,
+//! and join the words again, keeping leading and trailing separators.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::case::{convert_case, Case};
+//!
+//! assert_eq!(convert_case("straße", Case::Upper), "STRASSE");
+//! assert_eq!(convert_case("parseHTTPResponse", Case::Snake), "parse_http_response");
+//! assert_eq!(convert_case("__max-value2", Case::Pascal), "__MaxValue2");
+//! ```
+
+/// A case to convert text to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Case {
+    /// `UPPER CASE`.
+    Upper,
+    /// `lower case`.
+    Lower,
+    /// Swaps uppercase and lowercase letters.
+    Toggle,
+    /// `Title Case`, capitalizing each whitespace-separated word.
+    Title,
+    /// `snake_case`.
+    Snake,
+    /// `camelCase`.
+    Camel,
+    /// `PascalCase`.
+    Pascal,
+    /// `kebab-case`.
+    Kebab,
+    /// `SCREAMING_CASE`.
+    Screaming,
+}
+
+/// Returns `s` with its first character uppercase and the others lowercase.
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(ch) => ch.to_uppercase().chain(chars.as_str().to_lowercase().chars()).collect(),
+        None => String::new(),
+    }
+}
+
+/// Splits an identifier into words.
+fn words(s: &[char]) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (i, &ch) in s.iter().enumerate() {
+        let boundary = i > 0 && ch.is_uppercase() && {
+            let prev = s[i - 1];
+            let next_lower = s.get(i + 1).is_some_and(|c| c.is_lowercase());
+            prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_lower)
+        };
+        if (boundary || !ch.is_alphanumeric()) && !word.is_empty() {
+            words.push(::std::mem::take(&mut word));
+        }
+        if ch.is_alphanumeric() {
+            word.push(ch);
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// Converts an identifier, keeping its leading and trailing separators.
+fn convert_identifier(s: &[char], case: Case) -> String {
+    let lead = s.iter().take_while(|ch| !ch.is_alphanumeric()).count();
+    if lead == s.len() {
+        return s.iter().collect();
+    }
+    let trail = s.iter().rev().take_while(|ch| !ch.is_alphanumeric()).count();
+    let words = words(&s[lead..s.len() - trail]);
+    let body = match case {
+        Case::Snake => words.iter().map(|w| w.to_lowercase()).collect::<Vec<_>>().join("_"),
+        Case::Kebab => words.iter().map(|w| w.to_lowercase()).collect::<Vec<_>>().join("-"),
+        Case::Screaming => words.iter().map(|w| w.to_uppercase()).collect::<Vec<_>>().join("_"),
+        Case::Pascal => words.iter().map(|w| capitalize(w)).collect(),
+        Case::Camel => words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| if i == 0 { w.to_lowercase() } else { capitalize(w) })
+            .collect(),
+        _ => unreachable!("not an identifier case: {:?}", case),
+    };
+    let mut result: String = s[..lead].iter().collect();
+    result += &body;
+    result.extend(&s[s.len() - trail..]);
+    result
+}
+
+/// Converts the case of text.
+pub fn convert_case(s: &str, case: Case) -> String {
+    match case {
+        Case::Upper => s.to_uppercase(),
+        Case::Lower => s.to_lowercase(),
+        Case::Toggle => s.chars()
+            .map(|ch| if ch.is_uppercase() {
+                ch.to_lowercase().collect::<String>()
+            } else {
+                ch.to_uppercase().collect()
+            })
+            .collect(),
+        Case::Title => {
+            let mut result = String::new();
+            let mut word = String::new();
+            for ch in s.chars() {
+                if ch.is_whitespace() {
+                    result += &capitalize(&word);
+                    word.clear();
+                    result.push(ch);
+                } else {
+                    word.push(ch);
+                }
+            }
+            result + &capitalize(&word)
+        }
+        _ => {
+            let chars: Vec<char> = s.chars().collect();
+            let mut result = String::new();
+            let mut i = 0;
+            while i < chars.len() {
+                let ident = |ch: &char| ch.is_alphanumeric() || *ch == '_' || *ch == '-';
+                let n = chars[i..].iter().take_while(|ch| ident(ch)).count();
+                if n == 0 {
+                    result.push(chars[i]);
+                    i += 1;
+                } else {
+                    result += &convert_identifier(&chars[i..i + n], case);
+                    i += n;
+                }
+            }
+            result
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_character_cases() {
+        assert_eq!(convert_case("Hello, Wörld ß", Case::Toggle), "hELLO, wÖRLD SS");
+        assert_eq!(convert_case("ΣΊΣΥΦΟΣ", Case::Lower), "σίσυφος");
+        assert_eq!(convert_case("the  qUICK\tfox", Case::Title), "The  Quick\tFox");
+    }
+
+    #[test]
+    fn test_identifier_cases() {
+        let s = "fooBar baz_qux-quux HTML5Parser _a -";
+        assert_eq!(convert_case(s, Case::Snake), "foo_bar baz_qux_quux html5_parser _a -");
+        assert_eq!(convert_case(s, Case::Camel), "fooBar bazQuxQuux html5Parser _a -");
+        assert_eq!(convert_case(s, Case::Pascal), "FooBar BazQuxQuux Html5Parser _A -");
+        assert_eq!(convert_case(s, Case::Kebab), "foo-bar baz-qux-quux html5-parser _a -");
+        assert_eq!(
+            convert_case(s, Case::Screaming),
+            "FOO_BAR BAZ_QUX_QUUX HTML5_PARSER _A -"
+        );
+    }
+}
@@ -0,0 +1,294 @@
+//! Comment leaders, the markers which begin each line of a comment, and
+//! toggling comments by the comment syntax of a language.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::comment::{comment_leader, CommentSyntax};
+//!
+//! let leaders = CommentSyntax::for_language("rust").unwrap().leaders;
+//! let line: Vec<char> = "    /// docs".chars().collect();
+//! assert_eq!(comment_leader(&line, &leaders), Some(4..7));
+//! let line: Vec<char> = " * docs".chars().collect();
+//! assert_eq!(comment_leader(&line, &leaders), Some(1..2));
+//! let line: Vec<char> = "*p = 1;".chars().collect();
+//! assert_eq!(comment_leader(&line, &leaders), None);
+//! ```
+//!
+//! ```
+//! use edit::editor::comment::{toggle_line_comments, CommentSyntax};
+//!
+//! let syntax = CommentSyntax::for_language("python").unwrap();
+//! let text = ["if x:", "    y()"];
+//! let lines: Vec<Vec<char>> = text.iter().map(|l| l.chars().collect()).collect();
+//! let lines: Vec<&[char]> = lines.iter().map(|l| &l[..]).collect();
+//! assert_eq!(
+//!     toggle_line_comments(&lines, &syntax, 4),
+//!     Some(vec!["# if x:".to_string(), "#     y()".to_string()])
+//! );
+//! ```
+
+use std::ops::Range;
+
+use editor::indent::indent_width;
+use editor::prose::is_blank;
+
+/// Comment leaders recognized by default, those of `//` comments.
+///
+/// Markers like `#` or `--` begin other text in many languages, such as
+/// `#include` or `--flag`, so they are not recognized by default.
+pub const DEFAULT_LEADERS: &[&str] = &["///", "//!", "//"];
+
+/// Returns `DEFAULT_LEADERS` as strings.
+pub fn default_leaders() -> Vec<String> {
+    DEFAULT_LEADERS.iter().map(|s| s.to_string()).collect()
+}
+
+/// Returns the range of the longest of `leaders` which begins a line after
+/// its indentation.
+///
+/// A leader ending with a space, like `* `, needs whitespace or the end of
+/// the line after it, which is not part of the leader.
+pub fn comment_leader(line: &[char], leaders: &[String]) -> Option<Range<usize>> {
+    let indent = line.iter().take_while(|ch| ch.is_whitespace()).count();
+    let rest = &line[indent..];
+    leaders
+        .iter()
+        .filter_map(|l| {
+            let spaced = l.ends_with(' ');
+            let l: Vec<char> = l.trim_end_matches(' ').chars().collect();
+            let ok = !l.is_empty() && rest.starts_with(&l) &&
+                (!spaced || rest.get(l.len()).is_none_or(|ch| ch.is_whitespace()));
+            ok.then(|| indent..indent + l.len())
+        })
+        .max_by_key(|r| r.len())
+}
+
+/// The comment syntax of a language.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommentSyntax {
+    /// The marker of line comments, like `//`.
+    pub line: Option<String>,
+    /// The delimiters of block comments, like `/*` and `*/`.
+    pub block: Option<(String, String)>,
+    /// The comment leaders, which `Editor` uses for joining and reflowing.
+    /// See `comment_leader`.
+    pub leaders: Vec<String>,
+}
+
+impl Default for CommentSyntax {
+    /// Returns the comment syntax used without a language, `//` line comments.
+    ///
+    /// Markers like `#` or `--` begin other text in many languages, such as
+    /// `#include` or `--flag`, so they are only used for languages known to
+    /// have them.
+    fn default() -> CommentSyntax {
+        CommentSyntax {
+            line: Some("//".to_string()),
+            block: None,
+            leaders: default_leaders(),
+        }
+    }
+}
+
+impl CommentSyntax {
+    /// Returns the comment syntax of a language by the name of its grammar,
+    /// like `rust`, or `None` if the language is unknown.
+    ///
+    /// Besides the line comment marker, the leaders include the doc comment
+    /// markers of languages which have them, like `///` and `//!` in Rust,
+    /// and `* ` if block comments open with `*`, which continues them on each
+    /// line. Markers like `#` and `--` need whitespace after them, so that a
+    /// shebang `#!` or an arrow `-->` is not taken for a comment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use edit::editor::comment::CommentSyntax;
+    ///
+    /// let syntax = CommentSyntax::for_language("python").unwrap();
+    /// assert_eq!(syntax.line, Some("#".to_string()));
+    /// assert_eq!(syntax.leaders, ["# "]);
+    /// let syntax = CommentSyntax::for_language("css").unwrap();
+    /// assert_eq!(syntax.leaders, ["* "]);
+    /// ```
+    pub fn for_language(name: &str) -> Option<CommentSyntax> {
+        let (line, block, leaders): (_, _, &[&str]) = match name {
+            "rust" => (Some("//"), Some(("/*", "*/")), &["//", "///", "//!", "* "]),
+            "c" | "cpp" | "go" | "java" | "javascript" | "kotlin" | "swift" | "typescript" => {
+                (Some("//"), Some(("/*", "*/")), &["//", "* "])
+            }
+            "make" | "perl" | "python" | "ruby" | "shell" | "toml" | "yaml" => {
+                (Some("#"), None, &["# "])
+            }
+            "haskell" => (Some("--"), Some(("{-", "-}")), &["-- "]),
+            "lua" => (Some("--"), Some(("--[[", "]]")), &["-- "]),
+            "sql" => (Some("--"), Some(("/*", "*/")), &["-- ", "* "]),
+            "clojure" | "lisp" | "scheme" => (Some(";"), None, &[";", ";;", ";;;"]),
+            "css" => (None, Some(("/*", "*/")), &["* "]),
+            "html" | "markdown" | "xml" => (None, Some(("<!--", "-->")), &[]),
+            "json" => (None, None, &[]),
+            _ => return None,
+        };
+        Some(CommentSyntax {
+            line: line.map(|l| l.to_string()),
+            block: block.map(|(open, close)| (open.to_string(), close.to_string())),
+            leaders: leaders.iter().map(|l| l.to_string()).collect(),
+        })
+    }
+}
+
+/// Returns the range of a line without whitespace around it.
+fn trimmed(line: &[char]) -> Range<usize> {
+    let start = line.iter().take_while(|ch| ch.is_whitespace()).count();
+    let end = line.len() - line[start..].iter().rev().take_while(|ch| ch.is_whitespace()).count();
+    start..end
+}
+
+/// Returns whether text begins with `open` and ends with `close`, which do
+/// not overlap.
+fn is_wrapped(s: &[char], open: &[char], close: &[char]) -> bool {
+    s.len() >= open.len() + close.len() && s.starts_with(open) && s.ends_with(close)
+}
+
+/// Removes `open` and `close` from around text, with a space inside each.
+fn unwrap(s: &[char], open: &[char], close: &[char]) -> Vec<char> {
+    let mut inner = &s[open.len()..s.len() - close.len()];
+    if inner.first() == Some(&' ') {
+        inner = &inner[1..];
+    }
+    if !close.is_empty() && inner.last() == Some(&' ') {
+        inner = &inner[..inner.len() - 1];
+    }
+    inner.to_vec()
+}
+
+/// Puts `open` and `close` around text, with a space inside each.
+fn wrap(s: &[char], open: &[char], close: &[char]) -> Vec<char> {
+    let mut result = open.to_vec();
+    result.push(' ');
+    result.extend(s);
+    if !close.is_empty() {
+        result.push(' ');
+        result.extend(close);
+    }
+    result
+}
+
+/// Toggles line comments of lines, returning the new lines.
+///
+/// If every non-blank line is commented, the markers are removed with a
+/// space after each. Otherwise, markers followed by a space are inserted at
+/// the minimum indentation of the non-blank lines. Without a line comment
+/// marker, each line is wrapped in block comment delimiters instead. Blank
+/// lines are kept.
+///
+/// Returns `None` if the syntax has no comments or every line is blank.
+pub fn toggle_line_comments(
+    lines: &[&[char]],
+    syntax: &CommentSyntax,
+    tabstop: usize,
+) -> Option<Vec<String>> {
+    let (open, close): (Vec<char>, Vec<char>) = match (&syntax.line, &syntax.block) {
+        (Some(line), _) => (line.chars().collect(), Vec::new()),
+        (None, Some((open, close))) => (open.chars().collect(), close.chars().collect()),
+        (None, None) => return None,
+    };
+    let non_blank = || lines.iter().filter(|l| !is_blank(l));
+    let indent = non_blank().map(|l| indent_width(l, tabstop)).min()?;
+    let commented = non_blank().all(|l| is_wrapped(&l[trimmed(l)], &open, &close));
+    let toggled = lines.iter().map(|&l| {
+        if is_blank(l) {
+            return l.iter().collect();
+        }
+        let r = trimmed(l);
+        let (start, text) = if commented {
+            (r.start, unwrap(&l[r.clone()], &open, &close))
+        } else {
+            let start = (0..=r.start).find(|&i| indent_width(&l[..i], tabstop) >= indent).unwrap();
+            (start, wrap(&l[start..r.end], &open, &close))
+        };
+        l[..start].iter().chain(&text).chain(&l[r.end..]).collect()
+    });
+    Some(toggled.collect())
+}
+
+/// Toggles a block comment around text, keeping whitespace around it.
+///
+/// If the text is already in a block comment, the delimiters are removed
+/// with a space inside each. Otherwise, they are inserted with a space.
+pub fn toggle_block_comment(s: &[char], open: &str, close: &str) -> String {
+    let (open, close): (Vec<char>, Vec<char>) = (open.chars().collect(), close.chars().collect());
+    let r = trimmed(s);
+    if r.is_empty() {
+        return s.iter().collect();
+    }
+    let text = if is_wrapped(&s[r.clone()], &open, &close) {
+        unwrap(&s[r.clone()], &open, &close)
+    } else {
+        wrap(&s[r.clone()], &open, &close)
+    };
+    s[..r.start].iter().chain(&text).chain(&s[r.end..]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comment_leader() {
+        let leaders = default_leaders();
+        let leader = |s: &str| {
+            let line: Vec<char> = s.chars().collect();
+            comment_leader(&line, &leaders)
+        };
+        assert_eq!(leader("// a"), Some(0..2));
+        assert_eq!(leader("\t//! a"), Some(1..4));
+        assert_eq!(leader("//"), Some(0..2));
+        assert_eq!(leader("#include"), None);
+        assert_eq!(leader("  a // b"), None);
+        assert_eq!(leader(""), None);
+
+        let leaders = CommentSyntax::for_language("rust").unwrap().leaders;
+        let leader = |s: &str| {
+            let line: Vec<char> = s.chars().collect();
+            comment_leader(&line, &leaders)
+        };
+        assert_eq!(leader("  * a"), Some(2..3));
+        assert_eq!(leader("  *"), Some(2..3));
+        assert_eq!(leader("*a"), None);
+    }
+
+    fn toggle(text: &[&str], syntax: &CommentSyntax) -> Option<Vec<String>> {
+        let lines: Vec<Vec<char>> = text.iter().map(|l| l.chars().collect()).collect();
+        let lines: Vec<&[char]> = lines.iter().map(|l| &l[..]).collect();
+        toggle_line_comments(&lines, syntax, 4)
+    }
+
+    #[test]
+    fn test_toggle_line_comments() {
+        let rust = CommentSyntax::for_language("rust").unwrap();
+        let text = ["    a", "", "\tb", "    //c"];
+        let commented = toggle(&text, &rust).unwrap();
+        assert_eq!(commented, ["    // a", "", "\t// b", "    // //c"]);
+        let commented: Vec<&str> = commented.iter().map(|s| &s[..]).collect();
+        assert_eq!(toggle(&commented, &rust).unwrap(), text);
+        assert_eq!(toggle(&["  //c", "//d "], &rust).unwrap(), ["  c", "d "]);
+        assert_eq!(toggle(&["", "  "], &rust), None);
+
+        let css = CommentSyntax::for_language("css").unwrap();
+        assert_eq!(toggle(&["a {", "}"], &css).unwrap(), ["/* a { */", "/* } */"]);
+        assert_eq!(toggle(&["/*a*/", " /* b */ "], &css).unwrap(), ["a", " b "]);
+        assert_eq!(toggle(&["a"], &CommentSyntax::for_language("json").unwrap()), None);
+    }
+
+    #[test]
+    fn test_toggle_block_comment() {
+        let s: Vec<char> = "  a\n  b ".chars().collect();
+        let commented = toggle_block_comment(&s, "/*", "*/");
+        assert_eq!(commented, "  /* a\n  b */ ");
+        let commented: Vec<char> = commented.chars().collect();
+        assert_eq!(toggle_block_comment(&commented, "/*", "*/"), "  a\n  b ");
+        assert_eq!(toggle_block_comment(&[' '], "/*", "*/"), " ");
+    }
+}
@@ -0,0 +1,151 @@
+//! Completion from the contents of a buffer, like vim's `Ctrl-N` and `Ctrl-X Ctrl-L`.
+//!
+//! Candidates are collected from the buffer and ordered by their distance
+//! from the cursor, the closest first.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::complete::keyword_candidates;
+//! use edit::editor::keyword::KeywordClass;
+//!
+//! let s: Vec<char> = "foo_bar fizz f foo_bar food".chars().collect();
+//! let class = KeywordClass::default();
+//! assert_eq!(keyword_candidates(&s, 14, &class), vec!["foo_bar", "fizz", "food"]);
+//! ```
+
+use std::ops::Range;
+
+use editor::core::Position;
+use editor::keyword::KeywordClass;
+
+/// What completion completes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompletionKind {
+    /// The keyword before the cursor.
+    Keyword,
+    /// The current line before the cursor, except its indentation.
+    Line,
+}
+
+/// A completion in progress.
+#[derive(Clone, Debug)]
+pub(crate) struct Completion {
+    pub kind: CompletionKind,
+    /// Where the completed text starts.
+    pub start: Position,
+    /// The text typed before completion started.
+    pub typed: String,
+    pub candidates: Vec<String>,
+    /// The index of the shown candidate, or the number of candidates if the typed text is shown.
+    pub index: usize,
+}
+
+impl Completion {
+    /// Returns the text which completion has put in the buffer.
+    pub fn shown(&self) -> &str {
+        self.candidates.get(self.index).unwrap_or(&self.typed)
+    }
+
+    /// Moves to the next or previous candidate, wrapping around through the typed text.
+    pub fn cycle(&mut self, forward: bool) {
+        let n = self.candidates.len() + 1;
+        self.index = if forward {
+            (self.index + 1) % n
+        } else {
+            (self.index + n - 1) % n
+        };
+    }
+}
+
+/// Returns the range of the keyword which ends at `cursor`, possibly empty.
+pub fn keyword_prefix(s: &[char], cursor: usize, class: &KeywordClass) -> Range<usize> {
+    let n = s[..cursor]
+        .iter()
+        .rev()
+        .take_while(|&&ch| class.is_keyword(ch))
+        .count();
+    cursor - n..cursor
+}
+
+/// Orders candidates at offsets by their distance from `cursor`, dropping duplicates.
+fn by_distance(mut candidates: Vec<(usize, String)>, cursor: usize) -> Vec<String> {
+    candidates.sort_by_key(|&(i, _)| cursor.abs_diff(i));
+    let mut result: Vec<String> = Vec::new();
+    for (_, c) in candidates {
+        if !result.contains(&c) {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Returns keywords of `s` which complete the keyword ending at `cursor`,
+/// ordered by their distance from it.
+pub fn keyword_candidates(s: &[char], cursor: usize, class: &KeywordClass) -> Vec<String> {
+    let prefix = keyword_prefix(s, cursor, class);
+    let prefix = &s[prefix];
+    let mut candidates = Vec::new();
+    let mut i = 0;
+    while i < s.len() {
+        let n = s[i..].iter().take_while(|&&ch| class.is_keyword(ch)).count();
+        if n == 0 {
+            i += 1;
+            continue;
+        }
+        let word = &s[i..i + n];
+        let typed = i <= cursor && cursor <= i + n;
+        if !typed && n > prefix.len() && word.starts_with(prefix) {
+            candidates.push((i, word.iter().collect()));
+        }
+        i += n;
+    }
+    by_distance(candidates, cursor)
+}
+
+/// Returns lines which complete the line `line` up to `column`, without
+/// indentation, ordered by their distance from it.
+pub fn line_candidates(lines: &[&[char]], line: usize, column: usize) -> Vec<String> {
+    let trim = |l: &[char]| {
+        let n = l.iter().take_while(|ch| ch.is_whitespace()).count();
+        l[n..].to_vec()
+    };
+    let prefix = trim(&lines[line][..column]);
+    let candidates = lines
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i != line)
+        .map(|(i, l)| (i, trim(l)))
+        .filter(|(_, l)| l.len() > prefix.len() && l.starts_with(&prefix))
+        .map(|(i, l)| (i, l.into_iter().collect()))
+        .collect();
+    by_distance(candidates, line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyword_candidates() {
+        let class = KeywordClass::default();
+        let s: Vec<char> = "abc ab abd x ab".chars().collect();
+        assert_eq!(keyword_candidates(&s, 6, &class), vec!["abd", "abc"]);
+        assert_eq!(keyword_candidates(&s, 15, &class), vec!["abd", "abc"]);
+        assert_eq!(keyword_candidates(&s, 12, &class), Vec::<String>::new());
+        assert_eq!(keyword_prefix(&s, 6, &class), 4..6);
+        assert_eq!(keyword_prefix(&s, 4, &class), 4..4);
+    }
+
+    #[test]
+    fn test_line_candidates() {
+        let text = ["fn a() {", "    let x = 1;", "  let", "    let y = 2;", "let"];
+        let lines: Vec<Vec<char>> = text.iter().map(|l| l.chars().collect()).collect();
+        let lines: Vec<&[char]> = lines.iter().map(|l| &l[..]).collect();
+        assert_eq!(
+            line_candidates(&lines, 2, 5),
+            vec!["let x = 1;", "let y = 2;"]
+        );
+        assert_eq!(line_candidates(&lines, 4, 3), vec!["let y = 2;", "let x = 1;"]);
+    }
+}
@@ -1,6 +1,8 @@
 use std::cmp::Ordering;
 use std::ops::Range;
 
+use editor::keyword::KeywordClass;
+
 /// `Position` represents a two-dimensional position which has line and column.
 #[derive(PartialEq, Debug, Clone, Copy)]
 pub struct Position {
@@ -73,26 +75,6 @@ impl Core {
         })
     }
 
-    pub fn reset(&mut self, buffer: &str, line: usize, column: usize) {
-        let chars: Vec<char> = buffer.chars().collect();
-        let mut indices: Vec<usize> = chars
-            .iter()
-            .enumerate()
-            .filter_map(|(i, ch)| if *ch == '\n' { Some(i) } else { None })
-            .collect();
-        let char_count = chars.len();
-        if indices.last() != Some(&char_count) {
-            indices.push(char_count);
-        }
-        assert!(indices.len() > line);
-        let width = indices[line] - if line == 0 { 0 } else { indices[line - 1] + 1 };
-        assert!(width >= column);
-        self.buffer = chars;
-        self.newline_indices = indices;
-        self.line = line;
-        self.column = column;
-    }
-
     pub fn buffer(&self) -> &[char] {
         &self.buffer
     }
@@ -139,6 +121,21 @@ impl Core {
         Some(line_offset + column)
     }
 
+    pub fn position(&self, offset: usize) -> Option<Position> {
+        let line = match self.newline_indices.binary_search(&offset) {
+            Ok(i) | Err(i) => i,
+        };
+        if line >= self.line_count() {
+            return None;
+        }
+        let line_offset = if line == 0 {
+            0
+        } else {
+            self.newline_indices[line - 1] + 1
+        };
+        Some(Position::new(line, offset - line_offset))
+    }
+
     pub fn current_offset(&self) -> usize {
         self.offset(self.line, self.column).expect(&format!(
             "current_offset: unexpected error (line: {}, column: {})",
@@ -253,6 +250,10 @@ impl Core {
             column
         ));
         let ch = self.buffer.remove(offset);
+        if ch == '\n' && line + 1 == self.line_count() {
+            // The last line ends at the end of the buffer instead of the newline.
+            return;
+        }
         if ch == '\n' {
             self.newline_indices.remove(line);
         }
@@ -293,7 +294,10 @@ impl Core {
         }
     }
 
-    pub fn next_position(&self, f: fn(char) -> bool) -> Option<Position> {
+    pub fn next_position<F>(&self, f: F) -> Option<Position>
+    where
+        F: Fn(char) -> bool,
+    {
         let off = self.current_offset();
         let indices = &self.newline_indices[self.line..];
         let mut it = self.buffer[off..].iter();
@@ -313,7 +317,10 @@ impl Core {
         })
     }
 
-    pub fn previous_position(&self, f: fn(char) -> bool) -> Option<Position> {
+    pub fn previous_position<F>(&self, f: F) -> Option<Position>
+    where
+        F: Fn(char) -> bool,
+    {
         let off = self.current_offset();
         let indices = &self.newline_indices[..self.line];
         let mut it = self.buffer[..off].iter();
@@ -333,23 +340,26 @@ impl Core {
             .or(Some(Position::new(0, 0)))
     }
 
-    pub fn next_keyword_position(&self) -> Option<Position> {
-        self.next_position(char::is_alphanumeric)
+    pub fn next_keyword_position(&self, class: &KeywordClass) -> Option<Position> {
+        self.next_position(|ch| class.is_keyword(ch))
     }
 
-    pub fn previous_keyword_position(&self) -> Option<Position> {
-        self.previous_position(char::is_alphanumeric)
+    pub fn previous_keyword_position(&self, class: &KeywordClass) -> Option<Position> {
+        self.previous_position(|ch| class.is_keyword(ch))
     }
 
-    pub fn next_symbol_position(&self) -> Option<Position> {
-        self.next_position(char::is_symbol)
+    pub fn next_symbol_position(&self, class: &KeywordClass) -> Option<Position> {
+        self.next_position(|ch| class.is_symbol(ch))
     }
 
-    pub fn previous_symbol_position(&self) -> Option<Position> {
-        self.previous_position(char::is_symbol)
+    pub fn previous_symbol_position(&self, class: &KeywordClass) -> Option<Position> {
+        self.previous_position(|ch| class.is_symbol(ch))
     }
 
-    pub fn next_end_position(&self, f: fn(char) -> bool) -> Option<Position> {
+    pub fn next_end_position<F>(&self, f: F) -> Option<Position>
+    where
+        F: Fn(char) -> bool,
+    {
         let off = self.current_offset();
         let indices = &self.newline_indices[self.line..];
         let mut it = self.buffer[off..].iter();
@@ -369,7 +379,10 @@ impl Core {
         })
     }
 
-    pub fn previous_end_position(&self, f: fn(char) -> bool) -> Option<Position> {
+    pub fn previous_end_position<F>(&self, f: F) -> Option<Position>
+    where
+        F: Fn(char) -> bool,
+    {
         let off = self.current_offset();
         let indices = &self.newline_indices[..self.line];
         let mut it = self.buffer[..off].iter();
@@ -388,23 +401,26 @@ impl Core {
             .or(Some(Position::new(0, 0)))
     }
 
-    pub fn next_keyword_end_position(&self) -> Option<Position> {
-        self.next_end_position(char::is_alphanumeric)
+    pub fn next_keyword_end_position(&self, class: &KeywordClass) -> Option<Position> {
+        self.next_end_position(|ch| class.is_keyword(ch))
     }
 
-    pub fn previous_keyword_end_position(&self) -> Option<Position> {
-        self.previous_end_position(char::is_alphanumeric)
+    pub fn previous_keyword_end_position(&self, class: &KeywordClass) -> Option<Position> {
+        self.previous_end_position(|ch| class.is_keyword(ch))
     }
 
-    pub fn next_symbol_end_position(&self) -> Option<Position> {
-        self.next_end_position(char::is_symbol)
+    pub fn next_symbol_end_position(&self, class: &KeywordClass) -> Option<Position> {
+        self.next_end_position(|ch| class.is_symbol(ch))
     }
 
-    pub fn previous_symbol_end_position(&self) -> Option<Position> {
-        self.previous_end_position(char::is_symbol)
+    pub fn previous_symbol_end_position(&self, class: &KeywordClass) -> Option<Position> {
+        self.previous_end_position(|ch| class.is_symbol(ch))
     }
 
-    pub fn after_position(&self, f: fn(char) -> bool) -> Option<Position> {
+    pub fn after_position<F>(&self, f: F) -> Option<Position>
+    where
+        F: Fn(char) -> bool,
+    {
         self.next_end_position(f).map(|p| if p.column <
             self.line_width(p.line)
                 .unwrap()
@@ -415,7 +431,10 @@ impl Core {
         })
     }
 
-    pub fn before_position(&self, f: fn(char) -> bool) -> Option<Position> {
+    pub fn before_position<F>(&self, f: F) -> Option<Position>
+    where
+        F: Fn(char) -> bool,
+    {
         self.previous_position(f).and_then(|p| if p.column == 0 {
             if p.line == 0 {
                 None
@@ -427,20 +446,20 @@ impl Core {
         })
     }
 
-    pub fn after_keyword_position(&self) -> Option<Position> {
-        self.after_position(char::is_alphanumeric)
+    pub fn after_keyword_position(&self, class: &KeywordClass) -> Option<Position> {
+        self.after_position(|ch| class.is_keyword(ch))
     }
 
-    pub fn before_keyword_position(&self) -> Option<Position> {
-        self.before_position(char::is_alphanumeric)
+    pub fn before_keyword_position(&self, class: &KeywordClass) -> Option<Position> {
+        self.before_position(|ch| class.is_keyword(ch))
     }
 
-    pub fn after_symbol_position(&self) -> Option<Position> {
-        self.after_position(char::is_symbol)
+    pub fn after_symbol_position(&self, class: &KeywordClass) -> Option<Position> {
+        self.after_position(|ch| class.is_symbol(ch))
     }
 
-    pub fn before_symbol_position(&self) -> Option<Position> {
-        self.before_position(char::is_symbol)
+    pub fn before_symbol_position(&self, class: &KeywordClass) -> Option<Position> {
+        self.before_position(|ch| class.is_symbol(ch))
     }
 }
 
@@ -455,16 +474,6 @@ impl Clone for Core {
     }
 }
 
-trait Character {
-    fn is_symbol(self) -> bool;
-}
-
-impl Character for char {
-    fn is_symbol(self) -> bool {
-        !(self.is_alphanumeric() || self.is_whitespace())
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -549,6 +558,30 @@ mod tests {
         assert_eq!(editor.offset(1, 4), None);
     }
 
+    #[test]
+    fn test_position() {
+        let buffer = "Hello, world!\nThe 2nd line.";
+        let editor = Core::new(buffer, 0, 0).unwrap();
+        assert_eq!(editor.position(0), Some(Position::new(0, 0)));
+        assert_eq!(editor.position(13), Some(Position::new(0, 13)));
+        assert_eq!(editor.position(14), Some(Position::new(1, 0)));
+        assert_eq!(editor.position(27), Some(Position::new(1, 13)));
+        assert_eq!(editor.position(28), None);
+
+        let editor = Core::new("", 0, 0).unwrap();
+        assert_eq!(editor.position(0), Some(Position::new(0, 0)));
+        assert_eq!(editor.position(1), None);
+
+        let buffer = "世界\n\nabc";
+        let editor = Core::new(buffer, 0, 0).unwrap();
+        for line in 0..editor.line_count() {
+            for column in 0..editor.line_width(line).unwrap() + 1 {
+                let offset = editor.offset(line, column).unwrap();
+                assert_eq!(editor.position(offset), Some(Position::new(line, column)));
+            }
+        }
+    }
+
     #[test]
     fn test_move_right() {
         let buffer = "Hello, world!\nThe 2nd line.";
@@ -735,54 +768,58 @@ mod tests {
 
     #[test]
     fn test_next_keyword_position() {
+        let class = KeywordClass::default();
         let buffer = "**\n\
                       a**";
         let editor = Core::new(buffer, 0, 1).unwrap();
-        assert_eq!(editor.next_keyword_position(), Some(Position::new(1, 0)));
+        assert_eq!(editor.next_keyword_position(&class), Some(Position::new(1, 0)));
 
         let editor = Core::new(buffer, 1, 3).unwrap();
-        assert_eq!(editor.next_keyword_position(), None);
+        assert_eq!(editor.next_keyword_position(&class), None);
 
         let buffer = " 12wb12 ";
         let editor = Core::new(buffer, 0, 0).unwrap();
-        assert_eq!(editor.next_keyword_position(), Some(Position::new(0, 1)));
+        assert_eq!(editor.next_keyword_position(&class), Some(Position::new(0, 1)));
 
         let editor = Core::new(buffer, 0, 1).unwrap();
-        assert_eq!(editor.next_keyword_position(), None);
+        assert_eq!(editor.next_keyword_position(&class), None);
     }
 
     #[test]
     fn test_previous_keyword_position() {
+        let class = KeywordClass::default();
         let buffer = "**\n\
                       a**";
         let editor = Core::new(buffer, 0, 1).unwrap();
-        assert_eq!(editor.previous_keyword_position(), None);
+        assert_eq!(editor.previous_keyword_position(&class), None);
 
         let editor = Core::new(buffer, 1, 3).unwrap();
         assert_eq!(
-            editor.previous_keyword_position(),
+            editor.previous_keyword_position(&class),
             Some(Position::new(1, 0))
         );
     }
 
     #[test]
     fn test_next_symbol_position() {
+        let class = KeywordClass::default();
         let buffer = "ab\n\
                       *cd";
         let editor = Core::new(buffer, 0, 1).unwrap();
 
-        assert_eq!(editor.next_symbol_position(), Some(Position::new(1, 0)));
+        assert_eq!(editor.next_symbol_position(&class), Some(Position::new(1, 0)));
     }
 
     #[test]
     fn test_previous_symbol_position() {
+        let class = KeywordClass::default();
         let buffer = "ab\n\
                       *cd";
         let editor = Core::new(buffer, 0, 1).unwrap();
-        assert_eq!(editor.previous_symbol_position(), None);
+        assert_eq!(editor.previous_symbol_position(&class), None);
 
         let editor = Core::new(buffer, 1, 3).unwrap();
-        assert_eq!(editor.previous_symbol_position(), Some(Position::new(1, 0)));
+        assert_eq!(editor.previous_symbol_position(&class), Some(Position::new(1, 0)));
     }
 
     #[test]
@@ -0,0 +1,754 @@
+//! A sequence CRDT for text, as an alternative to `ot`.
+//!
+//! `Text` is a Replicated Growable Array: every character gets a unique `Id`
+//! and remembers the character it was inserted after, so concurrent updates
+//! can be merged in any order and every replica converges to the same text.
+//! Deleted characters are kept as tombstones.
+//!
+//! A `StateVector` summarizes which updates a replica has integrated, so that
+//! a peer can compute the updates the replica is missing. Both updates and
+//! state vectors have a compact binary encoding.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::crdt::Replica;
+//!
+//! let mut a = Replica::new(1);
+//! let mut b = Replica::new(2);
+//! let u1 = a.insert_string_at("ac", 0, 0);
+//! for u in &u1 {
+//!     b.merge(u.clone());
+//! }
+//! let u2 = a.insert_at('b', 0, 1).unwrap();
+//! let u3 = b.insert_at('d', 0, 2).unwrap();
+//! a.merge(u3);
+//! b.merge(u2);
+//! assert_eq!(a.editor().buffer_as_str(), "abcd");
+//! assert_eq!(b.editor().buffer_as_str(), "abcd");
+//! ```
+
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt;
+
+use editor::{Editor, Position};
+
+/// A unique identifier of an update, which is a pair of a client and a sequence number.
+///
+/// Sequence numbers of a client start at 1 and are contiguous.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Id {
+    /// A client which made the update.
+    pub client: u64,
+    /// A sequence number in the client.
+    pub seq: u64,
+}
+
+/// A portable update of a `Text`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Update {
+    /// An insertion of a character.
+    Insert {
+        /// The identifier of the update and the inserted character.
+        id: Id,
+        /// A Lamport timestamp which orders concurrent insertions.
+        lamport: u64,
+        /// The character which the new character was inserted after, or
+        /// `None` for the beginning of the text.
+        origin: Option<Id>,
+        /// The inserted character.
+        ch: char,
+    },
+    /// A deletion of a character.
+    Delete {
+        /// The identifier of the update.
+        id: Id,
+        /// The deleted character.
+        target: Id,
+    },
+}
+
+impl Update {
+    /// Returns the identifier of the update.
+    pub fn id(&self) -> Id {
+        match *self {
+            Update::Insert { id, .. } | Update::Delete { id, .. } => id,
+        }
+    }
+}
+
+/// A visible change made by integrating an update.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Change {
+    /// A character was inserted at a character offset.
+    Insert(usize, char),
+    /// A character was deleted at a character offset.
+    Delete(usize),
+}
+
+/// An error which can be returned when decoding binary data.
+#[derive(PartialEq, Debug)]
+pub enum DecodeError {
+    /// The data ended in the middle of a value.
+    UnexpectedEnd,
+    /// A tag byte is unknown.
+    Tag(u8),
+    /// A value is not a valid `char`.
+    Char(u64),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::UnexpectedEnd => write!(f, "unexpected end of data"),
+            DecodeError::Tag(n) => write!(f, "unknown tag {}", n),
+            DecodeError::Char(n) => write!(f, "invalid character {:#x}", n),
+        }
+    }
+}
+
+impl error::Error for DecodeError {
+    fn description(&self) -> &str {
+        match *self {
+            DecodeError::UnexpectedEnd => "unexpected end of data",
+            DecodeError::Tag(..) => "unknown tag",
+            DecodeError::Char(..) => "invalid character",
+        }
+    }
+}
+
+/// The latest sequence number which has been integrated, per client.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct StateVector {
+    clocks: BTreeMap<u64, u64>,
+}
+
+impl StateVector {
+    /// Creates an empty `StateVector`.
+    pub fn new() -> StateVector {
+        StateVector::default()
+    }
+
+    /// Returns the latest sequence number of a client, or 0 if none.
+    pub fn get(&self, client: u64) -> u64 {
+        self.clocks.get(&client).cloned().unwrap_or(0)
+    }
+
+    /// Returns `true` if an update has been integrated.
+    pub fn contains(&self, id: Id) -> bool {
+        id.seq <= self.get(id.client)
+    }
+
+    fn set(&mut self, id: Id) {
+        self.clocks.insert(id.client, id.seq);
+    }
+
+    /// Encodes the state vector into bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_varint(&mut buf, self.clocks.len() as u64);
+        for (&client, &seq) in &self.clocks {
+            write_varint(&mut buf, client);
+            write_varint(&mut buf, seq);
+        }
+        buf
+    }
+
+    /// Decodes a state vector from bytes.
+    pub fn decode(data: &[u8]) -> Result<StateVector, DecodeError> {
+        let mut r = Reader { data, pos: 0 };
+        let mut sv = StateVector::new();
+        for _ in 0..r.varint()? {
+            let client = r.varint()?;
+            let seq = r.varint()?;
+            sv.clocks.insert(client, seq);
+        }
+        Ok(sv)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Item {
+    id: Id,
+    lamport: u64,
+    ch: char,
+    deleted: bool,
+}
+
+/// A replicated text.
+#[derive(Clone, Debug)]
+pub struct Text {
+    client: u64,
+    seq: u64,
+    lamport: u64,
+    items: Vec<Item>,
+    state: StateVector,
+    log: Vec<Update>,
+    pending: Vec<Update>,
+}
+
+impl Text {
+    /// Creates an empty `Text` of a client.
+    ///
+    /// Every replica must have a distinct client.
+    pub fn new(client: u64) -> Text {
+        Text {
+            client,
+            seq: 0,
+            lamport: 0,
+            items: Vec::new(),
+            state: StateVector::new(),
+            log: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns the number of visible characters.
+    pub fn len(&self) -> usize {
+        self.items.iter().filter(|x| !x.deleted).count()
+    }
+
+    /// Returns `true` if there are no visible characters.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the state vector.
+    pub fn state_vector(&self) -> &StateVector {
+        &self.state
+    }
+
+    /// Returns the updates which have not been integrated by a replica of `sv`.
+    pub fn missing(&self, sv: &StateVector) -> Vec<Update> {
+        self.log
+            .iter()
+            .filter(|u| !sv.contains(u.id()))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns updates which have been received but wait for their dependencies.
+    pub fn pending(&self) -> &[Update] {
+        &self.pending
+    }
+
+    fn next_id(&mut self) -> Id {
+        self.seq += 1;
+        Id {
+            client: self.client,
+            seq: self.seq,
+        }
+    }
+
+    /// Returns the index of the `n`th visible item, or `items.len()` if there is no such item.
+    fn index_of_visible(&self, n: usize) -> usize {
+        self.items
+            .iter()
+            .enumerate()
+            .filter(|&(_, x)| !x.deleted)
+            .map(|(i, _)| i)
+            .nth(n)
+            .unwrap_or(self.items.len())
+    }
+
+    fn visible_offset(&self, index: usize) -> usize {
+        self.items[..index].iter().filter(|x| !x.deleted).count()
+    }
+
+    fn index_of_id(&self, id: Id) -> Option<usize> {
+        self.items.iter().position(|x| x.id == id)
+    }
+
+    /// Inserts a character at a character offset, returning the update.
+    ///
+    /// Returns `None` if the offset is out of the range.
+    pub fn insert(&mut self, offset: usize, ch: char) -> Option<Update> {
+        if offset > self.len() {
+            return None;
+        }
+        let origin = if offset == 0 {
+            None
+        } else {
+            Some(self.items[self.index_of_visible(offset - 1)].id)
+        };
+        let id = self.next_id();
+        let u = Update::Insert {
+            id,
+            lamport: self.lamport + 1,
+            origin,
+            ch,
+        };
+        self.integrate(&u);
+        Some(u)
+    }
+
+    /// Deletes a character at a character offset, returning the update.
+    ///
+    /// Returns `None` if the offset is out of the range.
+    pub fn delete(&mut self, offset: usize) -> Option<Update> {
+        if offset >= self.len() {
+            return None;
+        }
+        let target = self.items[self.index_of_visible(offset)].id;
+        let id = self.next_id();
+        let u = Update::Delete { id, target };
+        self.integrate(&u);
+        Some(u)
+    }
+
+    /// Merges an update from any replica, returning the visible changes.
+    ///
+    /// Updates may arrive in any order and more than once; an update whose
+    /// dependencies are unknown is kept pending until they arrive.
+    pub fn apply(&mut self, u: Update) -> Vec<Change> {
+        let mut changes = Vec::new();
+        if self.state.contains(u.id()) || self.pending.contains(&u) {
+            return changes;
+        }
+        self.pending.push(u);
+        while let Some(i) = self.pending.iter().position(|u| self.is_ready(u)) {
+            let u = self.pending.remove(i);
+            changes.extend(self.integrate(&u));
+        }
+        changes
+    }
+
+    fn is_ready(&self, u: &Update) -> bool {
+        let id = u.id();
+        if id.seq != self.state.get(id.client) + 1 {
+            return false;
+        }
+        match *u {
+            Update::Insert { origin, .. } => origin.is_none_or(|o| self.state.contains(o)),
+            Update::Delete { target, .. } => self.state.contains(target),
+        }
+    }
+
+    fn integrate(&mut self, u: &Update) -> Option<Change> {
+        self.state.set(u.id());
+        self.log.push(u.clone());
+        match *u {
+            Update::Insert {
+                id,
+                lamport,
+                origin,
+                ch,
+            } => {
+                if lamport > self.lamport {
+                    self.lamport = lamport;
+                }
+                let mut i = origin.map_or(0, |o| {
+                    self.index_of_id(o).expect("integrate: unknown origin") + 1
+                });
+                while i < self.items.len() &&
+                    (self.items[i].lamport, self.items[i].id.client) > (lamport, id.client)
+                {
+                    i += 1;
+                }
+                self.items.insert(
+                    i,
+                    Item {
+                        id,
+                        lamport,
+                        ch,
+                        deleted: false,
+                    },
+                );
+                Some(Change::Insert(self.visible_offset(i), ch))
+            }
+            Update::Delete { target, .. } => {
+                let i = self.index_of_id(target).expect("integrate: unknown target");
+                if self.items[i].deleted {
+                    return None;
+                }
+                self.items[i].deleted = true;
+                Some(Change::Delete(self.visible_offset(i)))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Text {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s: String = self.items
+            .iter()
+            .filter(|x| !x.deleted)
+            .map(|x| x.ch)
+            .collect();
+        f.write_str(&s)
+    }
+}
+
+/// Encodes updates into bytes.
+///
+/// # Examples
+///
+/// ```
+/// use edit::editor::crdt::{decode_updates, encode_updates, Text};
+///
+/// let mut text = Text::new(7);
+/// let u = vec![text.insert(0, '世').unwrap(), text.delete(0).unwrap()];
+/// assert_eq!(decode_updates(&encode_updates(&u)), Ok(u));
+/// ```
+pub fn encode_updates(updates: &[Update]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    write_varint(&mut buf, updates.len() as u64);
+    for u in updates {
+        match *u {
+            Update::Insert {
+                id,
+                lamport,
+                origin,
+                ch,
+            } => {
+                buf.push(0);
+                write_id(&mut buf, id);
+                write_varint(&mut buf, lamport);
+                match origin {
+                    None => buf.push(0),
+                    Some(o) => {
+                        buf.push(1);
+                        write_id(&mut buf, o);
+                    }
+                }
+                write_varint(&mut buf, ch as u64);
+            }
+            Update::Delete { id, target } => {
+                buf.push(1);
+                write_id(&mut buf, id);
+                write_id(&mut buf, target);
+            }
+        }
+    }
+    buf
+}
+
+/// Decodes updates from bytes.
+pub fn decode_updates(data: &[u8]) -> Result<Vec<Update>, DecodeError> {
+    let mut r = Reader { data, pos: 0 };
+    let n = r.varint()?;
+    let mut updates = Vec::new();
+    for _ in 0..n {
+        let u = match r.byte()? {
+            0 => {
+                let id = r.id()?;
+                let lamport = r.varint()?;
+                let origin = match r.byte()? {
+                    0 => None,
+                    1 => Some(r.id()?),
+                    t => return Err(DecodeError::Tag(t)),
+                };
+                let n = r.varint()?;
+                let ch = if n > u64::from(u32::MAX) {
+                    None
+                } else {
+                    ::std::char::from_u32(n as u32)
+                };
+                Update::Insert {
+                    id,
+                    lamport,
+                    origin,
+                    ch: ch.ok_or(DecodeError::Char(n))?,
+                }
+            }
+            1 => {
+                let id = r.id()?;
+                let target = r.id()?;
+                Update::Delete { id, target }
+            }
+            t => return Err(DecodeError::Tag(t)),
+        };
+        updates.push(u);
+    }
+    Ok(updates)
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        buf.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+fn write_id(buf: &mut Vec<u8>, id: Id) {
+    write_varint(buf, id.client);
+    write_varint(buf, id.seq);
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let b = *self.data.get(self.pos).ok_or(DecodeError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn varint(&mut self) -> Result<u64, DecodeError> {
+        let mut n = 0;
+        let mut shift = 0;
+        loop {
+            let b = self.byte()?;
+            if shift < 64 {
+                n |= u64::from(b & 0x7f) << shift;
+            }
+            if b & 0x80 == 0 {
+                return Ok(n);
+            }
+            shift += 7;
+        }
+    }
+
+    fn id(&mut self) -> Result<Id, DecodeError> {
+        let client = self.varint()?;
+        let seq = self.varint()?;
+        Ok(Id { client, seq })
+    }
+}
+
+/// An `Editor` backed by a `Text`.
+///
+/// Every edit made through a `Replica` produces updates for the other
+/// replicas, and merged updates are reflected in the editor.
+#[derive(Clone)]
+pub struct Replica {
+    editor: Editor,
+    text: Text,
+}
+
+impl Replica {
+    /// Creates a new `Replica` of a client, which has an empty buffer.
+    pub fn new(client: u64) -> Replica {
+        Replica {
+            editor: Editor::new("", 0, 0).unwrap(),
+            text: Text::new(client),
+        }
+    }
+
+    /// Returns the editor.
+    pub fn editor(&self) -> &Editor {
+        &self.editor
+    }
+
+    /// Returns the text.
+    pub fn text(&self) -> &Text {
+        &self.text
+    }
+
+    /// Inserts a character at a character position, returning the update.
+    ///
+    /// If a position is out of the range, nothing happens.
+    pub fn insert_at(&mut self, ch: char, line: usize, column: usize) -> Option<Update> {
+        let offset = self.editor.offset_position(Position::new(line, column))?;
+        self.editor.insert_at(ch, line, column);
+        self.text.insert(offset, ch)
+    }
+
+    /// Inserts a string at a character position, returning the updates.
+    ///
+    /// If a position is out of the range, nothing happens.
+    pub fn insert_string_at(&mut self, s: &str, line: usize, column: usize) -> Vec<Update> {
+        let offset = match self.editor.offset_position(Position::new(line, column)) {
+            Some(n) => n,
+            None => return Vec::new(),
+        };
+        self.editor.insert_string_at(s, line, column);
+        s.chars()
+            .enumerate()
+            .filter_map(|(i, ch)| self.text.insert(offset + i, ch))
+            .collect()
+    }
+
+    /// Deletes a character at a character position, returning the update.
+    ///
+    /// If a position is out of the range, nothing happens.
+    pub fn delete_at(&mut self, line: usize, column: usize) -> Option<Update> {
+        let offset = self.editor.offset_position(Position::new(line, column))?;
+        let u = self.text.delete(offset)?;
+        self.editor.delete_at(line, column);
+        Some(u)
+    }
+
+    /// Merges an update from another replica into the text and the editor.
+    pub fn merge(&mut self, u: Update) {
+        for change in self.text.apply(u) {
+            match change {
+                Change::Insert(n, ch) => {
+                    let p = self.editor.position_at(n).unwrap();
+                    self.editor.insert_at(ch, p.line, p.column);
+                }
+                Change::Delete(n) => {
+                    let p = self.editor.position_at(n).unwrap();
+                    self.editor.delete_at(p.line, p.column);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use editor::testing::{assert_converged, simulate, Rng};
+
+    #[test]
+    fn test_insert_and_delete() {
+        let mut text = Text::new(1);
+        text.insert(0, 'b');
+        text.insert(0, 'a');
+        text.insert(2, 'c');
+        assert_eq!(text.insert(4, 'x'), None);
+        assert_eq!(text.to_string(), "abc");
+        text.delete(1);
+        assert_eq!(text.delete(2), None);
+        assert_eq!(text.to_string(), "ac");
+        assert_eq!(text.len(), 2);
+        assert_eq!(text.state_vector().get(1), 4);
+    }
+
+    #[test]
+    fn test_apply_out_of_order() {
+        let mut a = Text::new(1);
+        let u: Vec<Update> = "abc".chars().enumerate().map(|(i, ch)| a.insert(i, ch).unwrap()).collect();
+        let d = a.delete(1).unwrap();
+
+        let mut b = Text::new(2);
+        assert_eq!(b.apply(d.clone()), vec![]);
+        assert_eq!(b.apply(u[2].clone()), vec![]);
+        assert_eq!(b.apply(u[1].clone()), vec![]);
+        assert_eq!(b.pending().len(), 3);
+        assert_eq!(
+            b.apply(u[0].clone()),
+            vec![
+                Change::Insert(0, 'a'),
+                Change::Insert(1, 'b'),
+                Change::Insert(2, 'c'),
+                Change::Delete(1),
+            ]
+        );
+        assert_eq!(b.apply(u[0].clone()), vec![]);
+        assert_eq!(b.to_string(), "ac");
+        assert!(b.pending().is_empty());
+    }
+
+    #[test]
+    fn test_state_vector() {
+        let mut a = Text::new(1);
+        let mut b = Text::new(2);
+        a.insert(0, 'a');
+        for u in a.missing(b.state_vector()) {
+            b.apply(u);
+        }
+        a.insert(1, 'b');
+        b.insert(1, 'c');
+
+        let sv = StateVector::decode(&b.state_vector().encode()).unwrap();
+        assert_eq!(&sv, b.state_vector());
+        let missing = a.missing(&sv);
+        assert_eq!(missing.len(), 1);
+        for u in decode_updates(&encode_updates(&missing)).unwrap() {
+            b.apply(u);
+        }
+        for u in b.missing(a.state_vector()) {
+            a.apply(u);
+        }
+        assert_eq!(a.to_string(), b.to_string());
+    }
+
+    #[test]
+    fn test_decode_error() {
+        assert_eq!(decode_updates(&[]), Err(DecodeError::UnexpectedEnd));
+        assert_eq!(decode_updates(&[1, 2]), Err(DecodeError::Tag(2)));
+        assert_eq!(decode_updates(&[1, 0, 1, 1, 1, 0, 0x80]), Err(DecodeError::UnexpectedEnd));
+        assert_eq!(
+            decode_updates(&[1, 0, 1, 1, 1, 0, 0x80, 0xb0, 0x03]),
+            Err(DecodeError::Char(0xd800))
+        );
+    }
+
+    #[test]
+    fn test_replica() {
+        let mut a = Replica::new(1);
+        let mut b = Replica::new(2);
+        for u in a.insert_string_at("ab\ncd", 0, 0) {
+            b.merge(u);
+        }
+        let u1 = a.delete_at(0, 2).unwrap();
+        let u2 = b.insert_at('x', 1, 0).unwrap();
+        assert_eq!(b.delete_at(1, 3), None);
+        a.merge(u2);
+        b.merge(u1);
+        assert_eq!(a.editor().buffer_as_str(), "abxcd");
+        assert_eq!(b.editor().buffer_as_str(), "abxcd");
+    }
+
+    fn random_edit(rng: &mut Rng, replica: &mut Replica) -> Vec<Update> {
+        let len = replica.text().len();
+        let p = replica.editor().position_at(rng.below(len + 1)).unwrap();
+        if len > 0 && rng.below(3) == 0 {
+            return replica.delete_at(p.line, p.column).into_iter().collect();
+        }
+        let s = rng.insertion();
+        replica.insert_string_at(s, p.line, p.column)
+    }
+
+    #[test]
+    fn test_convergence() {
+        simulate(|rng| {
+            let mut replicas: Vec<Replica> = (1..4).map(Replica::new).collect();
+            let mut network: Vec<(usize, Update)> = Vec::new();
+            for _ in 0..60 {
+                match rng.below(4) {
+                    0 | 1 => {
+                        let i = rng.below(replicas.len());
+                        for u in random_edit(rng, &mut replicas[i]) {
+                            for j in 0..replicas.len() {
+                                if j != i {
+                                    network.push((j, u.clone()));
+                                }
+                            }
+                        }
+                    }
+                    2 => {
+                        if !network.is_empty() {
+                            let k = rng.below(network.len());
+                            let (j, u) = network.swap_remove(k);
+                            replicas[j].merge(u.clone());
+                            if rng.below(4) == 0 {
+                                network.push((j, u));
+                            }
+                        }
+                    }
+                    _ => {
+                        // Synchronizes two replicas through encoded state vectors and updates.
+                        let i = rng.below(replicas.len());
+                        let j = rng.below(replicas.len());
+                        let sv = replicas[j].text().state_vector().encode();
+                        let sv = StateVector::decode(&sv).unwrap();
+                        let data = encode_updates(&replicas[i].text().missing(&sv));
+                        for u in decode_updates(&data).unwrap() {
+                            replicas[j].merge(u);
+                        }
+                    }
+                }
+            }
+            while !network.is_empty() {
+                let k = rng.below(network.len());
+                let (j, u) = network.swap_remove(k);
+                replicas[j].merge(u);
+            }
+
+            assert_converged(replicas.iter().flat_map(|r| {
+                vec![r.editor().buffer_as_str(), r.text().to_string()]
+            }));
+            for r in &replicas {
+                assert!(r.text().pending().is_empty());
+            }
+        });
+    }
+}
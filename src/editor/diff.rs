@@ -0,0 +1,755 @@
+//! Line-level differences between two buffers.
+//!
+//! A `Diff` compares two `Editor`s line by line and groups the changes into
+//! hunks with surrounding context. Each hunk knows its range in both buffers
+//! as `Position`s, so it can be passed to `Editor::buffer_range`, and pairs
+//! of changed lines also carry their character-level changes.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::Editor;
+//! use edit::editor::diff::Algorithm;
+//!
+//! let old = Editor::new("a\nb\nc\n", 0, 0).unwrap();
+//! let new = Editor::new("a\nB\nc\n", 0, 0).unwrap();
+//! let diff = old.diff(&new, Algorithm::Myers);
+//! assert_eq!(
+//!     diff.unified("old", "new"),
+//!     "--- old\n\
+//!      +++ new\n\
+//!      @@ -1,3 +1,3 @@\n \
+//!      a\n\
+//!      -b\n\
+//!      +B\n \
+//!      c\n"
+//! );
+//! ```
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Range;
+
+use editor::{Editor, Position};
+
+/// An algorithm to compute differences.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Algorithm {
+    /// The O(ND) algorithm of Myers, which finds a shortest edit script.
+    Myers,
+    /// Patience diff, which aligns lines occurring once in both sides first
+    /// and falls back on Myers between them.
+    Patience,
+}
+
+/// A kind of `Op`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tag {
+    /// Elements are the same in both sides.
+    Equal,
+    /// Elements are only in the old side.
+    Delete,
+    /// Elements are only in the new side.
+    Insert,
+}
+
+/// A run of elements with the same `Tag`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Op {
+    /// The kind of the run.
+    pub tag: Tag,
+    /// A range of indices in the old side.
+    pub old: Range<usize>,
+    /// A range of indices in the new side.
+    pub new: Range<usize>,
+}
+
+/// Computes differences between two sequences.
+///
+/// Between two `Equal` runs, deletions always come before insertions.
+///
+/// # Examples
+///
+/// ```
+/// use edit::editor::diff::{diff_slices, Algorithm, Op, Tag};
+///
+/// let a: Vec<char> = "abc".chars().collect();
+/// let b: Vec<char> = "axc".chars().collect();
+/// let ops = diff_slices(&a, &b, Algorithm::Myers);
+/// assert_eq!(ops[1], Op { tag: Tag::Delete, old: 1..2, new: 1..1 });
+/// assert_eq!(ops[2], Op { tag: Tag::Insert, old: 2..2, new: 1..2 });
+/// ```
+pub fn diff_slices<T: Eq + Hash>(a: &[T], b: &[T], algorithm: Algorithm) -> Vec<Op> {
+    let mut tags = Vec::new();
+    match algorithm {
+        Algorithm::Myers => myers(a, b, &mut tags),
+        Algorithm::Patience => patience(a, b, &mut tags),
+    }
+    coalesce(&tags)
+}
+
+/// Appends an edit script of `a` into `b`, one tag per element.
+///
+/// This is the linear space variant of the algorithm, which splits the
+/// sequences at the middle snake of a shortest edit script and recurses on
+/// both sides of it.
+fn myers<T: PartialEq>(a: &[T], b: &[T], tags: &mut Vec<Tag>) {
+    let prefix = a.iter().zip(b).take_while(|&(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|&(x, y)| x == y)
+        .count();
+    for _ in 0..prefix {
+        tags.push(Tag::Equal);
+    }
+    let a1 = &a[prefix..a.len() - suffix];
+    let b1 = &b[prefix..b.len() - suffix];
+    if a1.is_empty() {
+        tags.extend(b1.iter().map(|_| Tag::Insert));
+    } else if b1.is_empty() {
+        tags.extend(a1.iter().map(|_| Tag::Delete));
+    } else {
+        // Both sides are not empty and differ at both ends, so the edit
+        // script has at least two edits, and each side of the middle snake
+        // has fewer.
+        let (x, y, u, v) = middle_snake(a1, b1);
+        myers(&a1[..x], &b1[..y], tags);
+        for _ in x..u {
+            tags.push(Tag::Equal);
+        }
+        myers(&a1[u..], &b1[v..], tags);
+    }
+    for _ in 0..suffix {
+        tags.push(Tag::Equal);
+    }
+}
+
+/// Returns the middle snake of a shortest edit script of `a` into `b`, as
+/// its start `(x, y)` and end `(u, v)`.
+///
+/// Paths are searched from both ends at once, keeping only the furthest
+/// reaching point of each diagonal, until they overlap.
+fn middle_snake<T: PartialEq>(a: &[T], b: &[T]) -> (usize, usize, usize, usize) {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let delta = n - m;
+    let max = (n + m + 1) / 2;
+    let offset = max + 1;
+    // `forward[k]` is the furthest x on diagonal `k` from the start, and
+    // `backward[k]` that from the end, where diagonals and coordinates are
+    // reversed.
+    let mut forward = vec![0isize; 2 * max as usize + 3];
+    let mut backward = vec![0isize; 2 * max as usize + 3];
+    for d in 0..max + 1 {
+        let mut k = -d;
+        while k <= d {
+            let i = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && forward[i - 1] < forward[i + 1]) {
+                forward[i + 1]
+            } else {
+                forward[i - 1] + 1
+            };
+            let mut y = x - k;
+            let (x0, y0) = (x, y);
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[i] = x;
+            let r = delta - k;
+            if delta % 2 != 0 && r.abs() < d && x + backward[(r + offset) as usize] >= n {
+                return (x0 as usize, y0 as usize, x as usize, y as usize);
+            }
+            k += 2;
+        }
+        let mut k = -d;
+        while k <= d {
+            let i = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && backward[i - 1] < backward[i + 1]) {
+                backward[i + 1]
+            } else {
+                backward[i - 1] + 1
+            };
+            let mut y = x - k;
+            let (x0, y0) = (x, y);
+            while x < n && y < m && a[(n - x - 1) as usize] == b[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[i] = x;
+            let f = delta - k;
+            if delta % 2 == 0 && f.abs() <= d && forward[(f + offset) as usize] + x >= n {
+                return ((n - x) as usize, (m - y) as usize, (n - x0) as usize, (m - y0) as usize);
+            }
+            k += 2;
+        }
+    }
+    unreachable!("no middle snake of {} and {} elements", n, m)
+}
+
+/// Appends an edit script of `a` into `b` using patience diff.
+fn patience<T: Eq + Hash>(a: &[T], b: &[T], tags: &mut Vec<Tag>) {
+    let prefix = a.iter().zip(b).take_while(|&(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|&(x, y)| x == y)
+        .count();
+    for _ in 0..prefix {
+        tags.push(Tag::Equal);
+    }
+    let a1 = &a[prefix..a.len() - suffix];
+    let b1 = &b[prefix..b.len() - suffix];
+
+    let mut counts: HashMap<&T, (usize, usize, usize)> = HashMap::new();
+    for (i, x) in a1.iter().enumerate() {
+        counts.entry(x).or_insert((0, 0, 0)).0 += 1;
+        counts.get_mut(x).unwrap().2 = i;
+    }
+    for x in b1 {
+        if let Some(c) = counts.get_mut(x) {
+            c.1 += 1;
+        }
+    }
+    // Pairs of indices of elements occurring once in each side, in the order of `b1`.
+    let uniques: Vec<(usize, usize)> = b1.iter()
+        .enumerate()
+        .filter_map(|(j, x)| match counts.get(x) {
+            Some(&(1, 1, i)) => Some((i, j)),
+            _ => None,
+        })
+        .collect();
+
+    if uniques.is_empty() {
+        myers(a1, b1, tags);
+    } else {
+        let (mut i, mut j) = (0, 0);
+        for (x, y) in longest_increasing(&uniques) {
+            patience(&a1[i..x], &b1[j..y], tags);
+            tags.push(Tag::Equal);
+            i = x + 1;
+            j = y + 1;
+        }
+        patience(&a1[i..], &b1[j..], tags);
+    }
+
+    for _ in 0..suffix {
+        tags.push(Tag::Equal);
+    }
+}
+
+/// Returns the longest subsequence of `pairs` whose first elements increase.
+fn longest_increasing(pairs: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    // `tails[l]` is the index of the smallest tail of increasing subsequences of length `l + 1`.
+    let mut tails: Vec<usize> = Vec::new();
+    let mut prev = vec![None; pairs.len()];
+    for (n, &(i, _)) in pairs.iter().enumerate() {
+        let l = match tails.binary_search_by(|&t| pairs[t].0.cmp(&i)) {
+            Ok(l) | Err(l) => l,
+        };
+        if l > 0 {
+            prev[n] = Some(tails[l - 1]);
+        }
+        if l == tails.len() {
+            tails.push(n);
+        } else {
+            tails[l] = n;
+        }
+    }
+    let mut result = Vec::new();
+    let mut n = tails.last().cloned();
+    while let Some(i) = n {
+        result.push(pairs[i]);
+        n = prev[i];
+    }
+    result.reverse();
+    result
+}
+
+/// Groups tags into runs, moving deletions before insertions between equal runs.
+fn coalesce(tags: &[Tag]) -> Vec<Op> {
+    let mut ops: Vec<Op> = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    let mut k = 0;
+    while k < tags.len() {
+        if tags[k] == Tag::Equal {
+            let n = tags[k..].iter().take_while(|&&t| t == Tag::Equal).count();
+            ops.push(Op {
+                tag: Tag::Equal,
+                old: x..x + n,
+                new: y..y + n,
+            });
+            x += n;
+            y += n;
+            k += n;
+            continue;
+        }
+        let n = tags[k..].iter().take_while(|&&t| t != Tag::Equal).count();
+        let deleted = tags[k..k + n].iter().filter(|&&t| t == Tag::Delete).count();
+        let inserted = n - deleted;
+        if deleted > 0 {
+            ops.push(Op {
+                tag: Tag::Delete,
+                old: x..x + deleted,
+                new: y..y,
+            });
+        }
+        if inserted > 0 {
+            ops.push(Op {
+                tag: Tag::Insert,
+                old: x + deleted..x + deleted,
+                new: y..y + inserted,
+            });
+        }
+        x += deleted;
+        y += inserted;
+        k += n;
+    }
+    ops
+}
+
+/// A line of a hunk.
+///
+/// The text includes the trailing newline unless the line is the last one
+/// of a buffer without a trailing newline.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiffLine {
+    /// A line in both buffers.
+    Context(String),
+    /// A line only in the old buffer.
+    Delete(String),
+    /// A line only in the new buffer.
+    Insert(String),
+}
+
+/// A character-level change between a deleted line and an inserted line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InlineChange {
+    /// A range in the old buffer.
+    pub old: Range<Position>,
+    /// A range in the new buffer.
+    pub new: Range<Position>,
+}
+
+/// A group of changes with surrounding context lines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hunk {
+    /// A range of lines in the old buffer.
+    pub old_lines: Range<usize>,
+    /// A range of lines in the new buffer.
+    pub new_lines: Range<usize>,
+    /// The range of `old_lines` in the old buffer.
+    pub old_range: Range<Position>,
+    /// The range of `new_lines` in the new buffer.
+    pub new_range: Range<Position>,
+    /// The lines of the hunk.
+    pub lines: Vec<DiffLine>,
+    /// Character-level changes of changed lines.
+    pub inline: Vec<InlineChange>,
+}
+
+impl Hunk {
+    /// Returns the header of the hunk in the unified format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use edit::editor::Editor;
+    /// use edit::editor::diff::Algorithm;
+    ///
+    /// let old = Editor::new("a\nb", 0, 0).unwrap();
+    /// let new = Editor::new("b", 0, 0).unwrap();
+    /// let diff = old.diff(&new, Algorithm::Myers);
+    /// assert_eq!(diff.hunks()[0].header(), "@@ -1,2 +1 @@");
+    /// ```
+    pub fn header(&self) -> String {
+        format!(
+            "@@ -{} +{} @@",
+            header_range(&self.old_lines),
+            header_range(&self.new_lines)
+        )
+    }
+}
+
+fn header_range(r: &Range<usize>) -> String {
+    match r.len() {
+        0 => format!("{},0", r.start),
+        1 => format!("{}", r.start + 1),
+        n => format!("{},{}", r.start + 1, n),
+    }
+}
+
+/// Differences between two buffers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diff {
+    hunks: Vec<Hunk>,
+}
+
+/// Lines of a buffer with the positions of their beginnings.
+pub(crate) struct Lines<'a> {
+    editor: &'a Editor,
+    pub(crate) lines: Vec<String>,
+    starts: Vec<usize>,
+}
+
+impl<'a> Lines<'a> {
+    pub(crate) fn new(editor: &'a Editor) -> Lines<'a> {
+        let mut lines = Vec::new();
+        let mut starts = vec![0];
+        let mut line = String::new();
+        for (i, &ch) in editor.buffer().iter().enumerate() {
+            line.push(ch);
+            if ch == '\n' {
+                lines.push(line);
+                line = String::new();
+                starts.push(i + 1);
+            }
+        }
+        if !line.is_empty() {
+            starts.push(editor.buffer().len());
+            lines.push(line);
+        }
+        Lines {
+            editor,
+            lines,
+            starts,
+        }
+    }
+
+    /// Returns the offset of the beginning of line `n`, or the length of the
+    /// buffer if `n` is the number of lines.
+    pub(crate) fn offset(&self, n: usize) -> usize {
+        self.starts[n]
+    }
+
+    /// Returns the position of the beginning of line `n`, or the end of the
+    /// buffer if `n` is the number of lines.
+    ///
+    /// If the buffer ends with a newline which `Editor` hides at the end of
+    /// the last line, the end of the buffer is just before that newline.
+    pub(crate) fn position(&self, n: usize) -> Position {
+        let editor = self.editor;
+        editor.position_at(self.starts[n]).unwrap_or_else(|| {
+            let last = editor.line_count() - 1;
+            Position::new(last, editor.line_width(last).unwrap())
+        })
+    }
+}
+
+impl Diff {
+    /// Computes differences between two buffers, with `context` lines around each change.
+    pub fn new(old: &Editor, new: &Editor, algorithm: Algorithm, context: usize) -> Diff {
+        let a = Lines::new(old);
+        let b = Lines::new(new);
+        let ops = diff_slices(&a.lines, &b.lines, algorithm);
+        let hunks = group(&ops, context)
+            .into_iter()
+            .map(|g| make_hunk(&a, &b, &g, algorithm))
+            .collect();
+        Diff { hunks }
+    }
+
+    /// Returns the hunks.
+    pub fn hunks(&self) -> &[Hunk] {
+        &self.hunks
+    }
+
+    /// Returns `true` if the buffers are the same.
+    pub fn is_empty(&self) -> bool {
+        self.hunks.is_empty()
+    }
+
+    /// Formats the differences in the unified format.
+    ///
+    /// Returns an empty string if the buffers are the same.
+    pub fn unified(&self, old_name: &str, new_name: &str) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+        let mut s = format!("--- {}\n+++ {}\n", old_name, new_name);
+        for h in &self.hunks {
+            s += &h.header();
+            s.push('\n');
+            for l in &h.lines {
+                let (prefix, text) = match *l {
+                    DiffLine::Context(ref t) => (' ', t),
+                    DiffLine::Delete(ref t) => ('-', t),
+                    DiffLine::Insert(ref t) => ('+', t),
+                };
+                s.push(prefix);
+                s += text;
+                if !text.ends_with('\n') {
+                    s += "\n\\ No newline at end of file\n";
+                }
+            }
+        }
+        s
+    }
+}
+
+/// Splits ops into groups of changes with at most `context` equal elements around them.
+fn group(ops: &[Op], context: usize) -> Vec<Vec<Op>> {
+    let mut groups = Vec::new();
+    let mut current: Vec<Op> = Vec::new();
+    for (i, op) in ops.iter().enumerate() {
+        if op.tag != Tag::Equal {
+            current.push(op.clone());
+            continue;
+        }
+        let n = op.old.len();
+        let first = i == 0;
+        let last = i == ops.len() - 1;
+        if first {
+            let k = n.min(context);
+            current.push(Op {
+                tag: Tag::Equal,
+                old: op.old.end - k..op.old.end,
+                new: op.new.end - k..op.new.end,
+            });
+        } else if last || n > 2 * context {
+            let k = n.min(context);
+            current.push(Op {
+                tag: Tag::Equal,
+                old: op.old.start..op.old.start + k,
+                new: op.new.start..op.new.start + k,
+            });
+            groups.push(current);
+            current = Vec::new();
+            if !last {
+                current.push(Op {
+                    tag: Tag::Equal,
+                    old: op.old.end - context..op.old.end,
+                    new: op.new.end - context..op.new.end,
+                });
+            }
+        } else {
+            current.push(op.clone());
+        }
+    }
+    groups.push(current);
+    groups
+        .into_iter()
+        .filter(|g| g.iter().any(|op| op.tag != Tag::Equal))
+        .map(|g| g.into_iter().filter(|op| !op.old.is_empty() || !op.new.is_empty()).collect())
+        .collect()
+}
+
+fn make_hunk(a: &Lines, b: &Lines, ops: &[Op], algorithm: Algorithm) -> Hunk {
+    let old_lines = ops[0].old.start..ops[ops.len() - 1].old.end;
+    let new_lines = ops[0].new.start..ops[ops.len() - 1].new.end;
+    let mut lines = Vec::new();
+    let mut inline = Vec::new();
+    for (i, op) in ops.iter().enumerate() {
+        match op.tag {
+            Tag::Equal => {
+                lines.extend(a.lines[op.old.clone()].iter().cloned().map(DiffLine::Context));
+            }
+            Tag::Delete => {
+                lines.extend(a.lines[op.old.clone()].iter().cloned().map(DiffLine::Delete));
+            }
+            Tag::Insert => {
+                lines.extend(b.lines[op.new.clone()].iter().cloned().map(DiffLine::Insert));
+                if i > 0 && ops[i - 1].tag == Tag::Delete {
+                    let deleted = ops[i - 1].old.clone();
+                    for (x, y) in deleted.zip(op.new.clone()) {
+                        inline.extend(inline_changes(a, b, x, y, algorithm));
+                    }
+                }
+            }
+        }
+    }
+    Hunk {
+        old_range: a.position(old_lines.start)..a.position(old_lines.end),
+        new_range: b.position(new_lines.start)..b.position(new_lines.end),
+        old_lines,
+        new_lines,
+        lines,
+        inline,
+    }
+}
+
+fn inline_changes(a: &Lines, b: &Lines, x: usize, y: usize, algorithm: Algorithm) -> Vec<InlineChange> {
+    let s: Vec<char> = a.lines[x].trim_end_matches('\n').chars().collect();
+    let t: Vec<char> = b.lines[y].trim_end_matches('\n').chars().collect();
+    let ops = diff_slices(&s, &t, algorithm);
+    let mut changes: Vec<InlineChange> = Vec::new();
+    for op in ops.into_iter().filter(|op| op.tag != Tag::Equal) {
+        let old = Position::new(x, op.old.start)..Position::new(x, op.old.end);
+        let new = Position::new(y, op.new.start)..Position::new(y, op.new.end);
+        // A deletion directly followed by an insertion is one replacement.
+        if let Some(last) = changes.last_mut() {
+            if last.old.end == old.start && last.new.end == new.start {
+                last.old.end = old.end;
+                last.new.end = new.end;
+                continue;
+            }
+        }
+        changes.push(InlineChange { old, new });
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use editor::testing::simulate;
+
+    fn apply<T: Clone + PartialEq + ::std::fmt::Debug>(a: &[T], b: &[T], ops: &[Op]) -> Vec<T> {
+        let mut result = Vec::new();
+        for op in ops {
+            match op.tag {
+                Tag::Equal => {
+                    assert_eq!(a[op.old.clone()], b[op.new.clone()]);
+                    result.extend_from_slice(&a[op.old.clone()]);
+                }
+                Tag::Delete => assert!(op.new.is_empty()),
+                Tag::Insert => {
+                    assert!(op.old.is_empty());
+                    result.extend_from_slice(&b[op.new.clone()]);
+                }
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn test_diff_slices() {
+        let cases = [
+            ("", ""),
+            ("abc", ""),
+            ("", "abc"),
+            ("abcabba", "cbabac"),
+            ("kitten", "sitting"),
+            ("same", "same"),
+            ("a世界b", "世a界"),
+        ];
+        for &(s, t) in &cases {
+            let a: Vec<char> = s.chars().collect();
+            let b: Vec<char> = t.chars().collect();
+            for &algorithm in &[Algorithm::Myers, Algorithm::Patience] {
+                let ops = diff_slices(&a, &b, algorithm);
+                assert_eq!(apply(&a, &b, &ops), b, "{:?} {:?} {:?}", s, t, algorithm);
+            }
+        }
+
+        // The shortest edit script of the example in the paper of Myers has 5 edits.
+        let a: Vec<char> = "abcabba".chars().collect();
+        let b: Vec<char> = "cbabac".chars().collect();
+        let edits: usize = diff_slices(&a, &b, Algorithm::Myers)
+            .iter()
+            .filter(|op| op.tag != Tag::Equal)
+            .map(|op| op.old.len() + op.new.len())
+            .sum();
+        assert_eq!(edits, 5);
+    }
+
+    #[test]
+    fn test_shortest_edit_script() {
+        simulate(|rng| {
+            let a: Vec<usize> = (0..rng.below(30)).map(|_| rng.below(4)).collect();
+            let b: Vec<usize> = (0..rng.below(30)).map(|_| rng.below(4)).collect();
+            let mut lcs = vec![vec![0; b.len() + 1]; a.len() + 1];
+            for i in 0..a.len() {
+                for j in 0..b.len() {
+                    lcs[i + 1][j + 1] = if a[i] == b[j] {
+                        lcs[i][j] + 1
+                    } else {
+                        lcs[i][j + 1].max(lcs[i + 1][j])
+                    };
+                }
+            }
+            let ops = diff_slices(&a, &b, Algorithm::Myers);
+            assert_eq!(apply(&a, &b, &ops), b);
+            let equal: usize = ops.iter().filter(|op| op.tag == Tag::Equal).map(|op| op.old.len()).sum();
+            assert_eq!(equal, lcs[a.len()][b.len()]);
+        });
+
+        // Nothing in common takes as many steps as elements, but linear space.
+        let a: Vec<usize> = (0..5000).collect();
+        let b: Vec<usize> = (5000..10000).collect();
+        let ops = diff_slices(&a, &b, Algorithm::Patience);
+        assert_eq!(ops[0], Op { tag: Tag::Delete, old: 0..5000, new: 0..0 });
+        assert_eq!(ops[1], Op { tag: Tag::Insert, old: 5000..5000, new: 0..5000 });
+    }
+
+    #[test]
+    fn test_patience() {
+        let a = vec!["{", "a", "}", "{", "b", "}"];
+        let b = vec!["{", "b", "}", "{", "a", "}"];
+        let ops = diff_slices(&a, &b, Algorithm::Patience);
+        assert_eq!(
+            ops,
+            vec![
+                Op { tag: Tag::Equal, old: 0..1, new: 0..1 },
+                Op { tag: Tag::Delete, old: 1..2, new: 1..1 },
+                Op { tag: Tag::Insert, old: 2..2, new: 1..2 },
+                Op { tag: Tag::Equal, old: 2..4, new: 2..4 },
+                Op { tag: Tag::Delete, old: 4..5, new: 4..4 },
+                Op { tag: Tag::Insert, old: 5..5, new: 4..5 },
+                Op { tag: Tag::Equal, old: 5..6, new: 5..6 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hunks() {
+        let old = Editor::new("1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12", 0, 0).unwrap();
+        let new = Editor::new("1\n2\nthree\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13", 0, 0).unwrap();
+        let diff = Diff::new(&old, &new, Algorithm::Myers, 2);
+        let hunks = diff.hunks();
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].old_lines, 0..5);
+        assert_eq!(hunks[0].new_lines, 0..5);
+        assert_eq!(hunks[0].inline, vec![
+            InlineChange {
+                old: Position::new(2, 0)..Position::new(2, 1),
+                new: Position::new(2, 0)..Position::new(2, 5),
+            },
+        ]);
+        assert_eq!(hunks[1].old_lines, 9..12);
+        assert_eq!(hunks[1].old_range, Position::new(9, 0)..Position::new(11, 2));
+        assert_eq!(hunks[1].new_range, Position::new(9, 0)..Position::new(12, 2));
+        let s: String = new.buffer_range(hunks[1].new_range.clone()).unwrap().iter().collect();
+        assert_eq!(s, "10\n11\n12\n13");
+        assert_eq!(
+            diff.unified("a", "b"),
+            "--- a\n\
+             +++ b\n\
+             @@ -1,5 +1,5 @@\n \
+             1\n \
+             2\n\
+             -3\n\
+             +three\n \
+             4\n \
+             5\n\
+             @@ -10,3 +10,4 @@\n \
+             10\n \
+             11\n\
+             -12\n\
+             \\ No newline at end of file\n\
+             +12\n\
+             +13\n\
+             \\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn test_same() {
+        let old = Editor::new("a\nb", 0, 0).unwrap();
+        let diff = Diff::new(&old, &old.clone(), Algorithm::Patience, 3);
+        assert!(diff.is_empty());
+        assert_eq!(diff.unified("a", "b"), "");
+    }
+
+    #[test]
+    fn test_empty() {
+        let old = Editor::new("", 0, 0).unwrap();
+        let new = Editor::new("a\n", 0, 0).unwrap();
+        let diff = Diff::new(&old, &new, Algorithm::Myers, 3);
+        assert_eq!(diff.unified("a", "b"), "--- a\n+++ b\n@@ -0,0 +1 @@\n+a\n");
+        assert_eq!(diff.hunks()[0].old_range, Position::new(0, 0)..Position::new(0, 0));
+    }
+}
@@ -0,0 +1,125 @@
+//! Searching for a character, like vim's `f`, `t`, `F` and `T`.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::find::{find_char, CharSearch};
+//!
+//! let s: Vec<char> = "f(a, (b), c)".chars().collect();
+//! assert_eq!(find_char(&s, 0, CharSearch::find_forward(')'), 2, false), Some(11));
+//! assert_eq!(find_char(&s, 11, CharSearch::till_backward('('), 1, false), Some(6));
+//! ```
+
+/// A search for a character.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CharSearch {
+    /// The character to search for.
+    pub ch: char,
+    /// Whether to search after the cursor.
+    pub forward: bool,
+    /// Whether to stop just before the character, rather than on it.
+    pub till: bool,
+}
+
+impl CharSearch {
+    /// Searches after the cursor, stopping on the character (`f`).
+    pub fn find_forward(ch: char) -> CharSearch {
+        CharSearch {
+            ch,
+            forward: true,
+            till: false,
+        }
+    }
+
+    /// Searches before the cursor, stopping on the character (`F`).
+    pub fn find_backward(ch: char) -> CharSearch {
+        CharSearch {
+            ch,
+            forward: false,
+            till: false,
+        }
+    }
+
+    /// Searches after the cursor, stopping just before the character (`t`).
+    pub fn till_forward(ch: char) -> CharSearch {
+        CharSearch {
+            ch,
+            forward: true,
+            till: true,
+        }
+    }
+
+    /// Searches before the cursor, stopping just after the character (`T`).
+    pub fn till_backward(ch: char) -> CharSearch {
+        CharSearch {
+            ch,
+            forward: false,
+            till: true,
+        }
+    }
+
+    /// Returns the search in the opposite direction.
+    pub fn reversed(self) -> CharSearch {
+        CharSearch {
+            forward: !self.forward,
+            ..self
+        }
+    }
+}
+
+/// Returns the offset the cursor at `cursor` moves to by `search` for the
+/// `count`-th occurrence, or `None` if there are fewer occurrences.
+///
+/// When `repeat` is set, a till search skips an occurrence next to the
+/// cursor, so that repeating it does not get stuck.
+pub fn find_char(
+    s: &[char],
+    cursor: usize,
+    search: CharSearch,
+    count: usize,
+    repeat: bool,
+) -> Option<usize> {
+    let skip = (search.till && repeat) as usize;
+    let n = count.max(1) - 1;
+    if search.forward {
+        (cursor + 1 + skip..s.len())
+            .filter(|&i| s[i] == search.ch)
+            .nth(n)
+            .map(|i| if search.till { i - 1 } else { i })
+    } else {
+        (0..cursor.saturating_sub(skip))
+            .rev()
+            .filter(|&i| s[i] == search.ch)
+            .nth(n)
+            .map(|i| if search.till { i + 1 } else { i })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_char() {
+        let s: Vec<char> = "a.b.c.d".chars().collect();
+        assert_eq!(find_char(&s, 0, CharSearch::find_forward('.'), 1, false), Some(1));
+        assert_eq!(find_char(&s, 0, CharSearch::find_forward('.'), 3, false), Some(5));
+        assert_eq!(find_char(&s, 0, CharSearch::find_forward('.'), 4, false), None);
+        assert_eq!(find_char(&s, 1, CharSearch::find_forward('.'), 1, false), Some(3));
+        assert_eq!(find_char(&s, 6, CharSearch::find_backward('.'), 2, false), Some(3));
+        assert_eq!(find_char(&s, 0, CharSearch::find_backward('.'), 1, false), None);
+    }
+
+    #[test]
+    fn test_till() {
+        let s: Vec<char> = "a.b.c".chars().collect();
+        assert_eq!(find_char(&s, 0, CharSearch::till_forward('.'), 1, false), Some(0));
+        assert_eq!(find_char(&s, 0, CharSearch::till_forward('.'), 1, true), Some(2));
+        assert_eq!(find_char(&s, 4, CharSearch::till_backward('.'), 1, false), Some(4));
+        assert_eq!(find_char(&s, 4, CharSearch::till_backward('.'), 1, true), Some(2));
+        assert_eq!(
+            CharSearch::till_forward('.').reversed(),
+            CharSearch::till_backward('.')
+        );
+    }
+}
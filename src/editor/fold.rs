@@ -0,0 +1,343 @@
+//! Folds of lines.
+//!
+//! A fold covers an inclusive range of lines and is either open or closed.
+//! Folds of a `Folds` are always nested or disjoint, and a closed fold is
+//! shown as one line, hiding folds inside it.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::fold::Folds;
+//!
+//! let mut folds = Folds::new();
+//! assert!(folds.create(2, 5));
+//! assert!(folds.create(3, 4));
+//! assert!(!folds.create(4, 6));
+//! folds.close(3);
+//! assert_eq!(folds.closed_fold_at(4), Some((3, 4)));
+//! ```
+
+/// A fold of lines from `start` to `end`, inclusive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fold {
+    /// The first line.
+    pub start: usize,
+    /// The last line.
+    pub end: usize,
+    /// Whether the fold is closed.
+    pub closed: bool,
+}
+
+impl Fold {
+    fn contains(&self, line: usize) -> bool {
+        self.start <= line && line <= self.end
+    }
+
+    fn encloses(&self, other: &Fold) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+}
+
+/// A set of nested folds.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Folds {
+    /// Folds sorted by their start, an outer one before inner ones.
+    folds: Vec<Fold>,
+}
+
+impl Folds {
+    /// Creates an empty set of folds.
+    pub fn new() -> Folds {
+        Folds::default()
+    }
+
+    /// Returns the folds, an outer fold before inner ones.
+    pub fn folds(&self) -> &[Fold] {
+        &self.folds
+    }
+
+    /// Removes all the folds.
+    pub fn clear(&mut self) {
+        self.folds.clear();
+    }
+
+    /// Adds an open fold from `start` to `end`, inclusive.
+    ///
+    /// Returns `false` if the fold would cross an existing one or already exists.
+    pub fn create(&mut self, start: usize, end: usize) -> bool {
+        if end < start {
+            return false;
+        }
+        let fold = Fold {
+            start,
+            end,
+            closed: false,
+        };
+        if !self.fits(&fold) {
+            return false;
+        }
+        self.insert(fold);
+        true
+    }
+
+    /// Returns whether a fold neither crosses nor equals an existing one.
+    fn fits(&self, fold: &Fold) -> bool {
+        !self.folds.iter().any(|f| {
+            let crosses = f.start <= fold.end && fold.start <= f.end &&
+                !f.encloses(fold) && !fold.encloses(f);
+            crosses || (f.start, f.end) == (fold.start, fold.end)
+        })
+    }
+
+    fn insert(&mut self, fold: Fold) {
+        let i = self.folds
+            .iter()
+            .position(|f| (f.start, usize::MAX - f.end) > (fold.start, usize::MAX - fold.end))
+            .unwrap_or(self.folds.len());
+        self.folds.insert(i, fold);
+    }
+
+    /// Replaces the folds with ones computed from lines, all of them open.
+    pub fn set(&mut self, folds: Vec<(usize, usize)>) {
+        self.folds.clear();
+        for (start, end) in folds {
+            self.create(start, end);
+        }
+    }
+
+    /// Returns the outermost closed fold containing a line, which is shown as one line.
+    pub fn closed_fold_at(&self, line: usize) -> Option<(usize, usize)> {
+        self.folds
+            .iter()
+            .find(|f| f.closed && f.contains(line))
+            .map(|f| (f.start, f.end))
+    }
+
+    /// Opens the closed fold shown at a line, returning `false` if there is none.
+    pub fn open(&mut self, line: usize) -> bool {
+        match self.folds.iter_mut().find(|f| f.closed && f.contains(line)) {
+            Some(f) => {
+                f.closed = false;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Closes the innermost open fold which contains a line and is not hidden.
+    ///
+    /// Returns `false` if there is no such fold.
+    pub fn close(&mut self, line: usize) -> bool {
+        let shown = self.folds
+            .iter()
+            .find(|f| f.closed && f.contains(line))
+            .cloned();
+        let fold = self.folds.iter_mut().rev().find(|f| {
+            !f.closed && f.contains(line) && shown.is_none_or(|s| f.encloses(&s))
+        });
+        match fold {
+            Some(f) => {
+                f.closed = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Opens the fold shown at a line if any, or closes a fold containing it otherwise.
+    pub fn toggle(&mut self, line: usize) -> bool {
+        self.open(line) || self.close(line)
+    }
+
+    /// Deletes the innermost fold containing a line, returning `false` if there is none.
+    pub fn delete(&mut self, line: usize) -> bool {
+        match self.folds.iter().rposition(|f| f.contains(line)) {
+            Some(i) => {
+                self.folds.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Shifts folds for `n` lines inserted at `line`.
+    ///
+    /// If `split` is set, the new lines come from splitting `line` and
+    /// follow it; otherwise they are inserted before it.
+    pub fn insert_lines(&mut self, line: usize, n: usize, split: bool) {
+        if n == 0 {
+            return;
+        }
+        for f in &mut self.folds {
+            if f.start > line || (!split && f.start == line) {
+                f.start += n;
+            }
+            if f.end >= line {
+                f.end += n;
+            }
+        }
+    }
+
+    /// Shifts folds for `n` lines following `line` joined into it.
+    ///
+    /// Folds lying within the joined lines are removed. A fold starting in
+    /// them starts at `line`, or after it if it would cross another fold.
+    pub fn delete_lines(&mut self, line: usize, n: usize) {
+        if n == 0 {
+            return;
+        }
+        let map = |l: usize| if l <= line {
+            l
+        } else if l > line + n {
+            l - n
+        } else {
+            line
+        };
+        let folds = ::std::mem::take(&mut self.folds);
+        for mut f in folds {
+            let joined = f.start > line && f.start <= line + n;
+            if joined && f.end <= line + n {
+                continue;
+            }
+            f.start = map(f.start);
+            f.end = map(f.end);
+            if joined && !self.fits(&f) {
+                f.start += 1;
+            }
+            if self.fits(&f) {
+                self.insert(f);
+            }
+        }
+    }
+}
+
+/// Computes folds from indentation levels of lines.
+///
+/// A fold of level `k` covers a maximal run of lines indented by at least
+/// `k` levels of `shiftwidth`. Blank lines take the smaller level of the
+/// surrounding non-blank lines.
+pub fn indent_folds(widths: &[Option<usize>], shiftwidth: usize) -> Vec<(usize, usize)> {
+    let sw = shiftwidth.max(1);
+    let levels: Vec<usize> = (0..widths.len())
+        .map(|i| match widths[i] {
+            Some(w) => w / sw,
+            None => {
+                let prev = widths[..i].iter().rev().find_map(|&w| w);
+                let next = widths[i + 1..].iter().find_map(|&w| w);
+                prev.unwrap_or(0).min(next.unwrap_or(0)) / sw
+            }
+        })
+        .collect();
+    let mut folds = Vec::new();
+    // Starts of runs for each level.
+    let mut starts: Vec<usize> = Vec::new();
+    for (i, &level) in levels.iter().chain(&[0]).enumerate() {
+        while starts.len() > level {
+            folds.push((starts.pop().unwrap(), i - 1));
+        }
+        while starts.len() < level {
+            starts.push(i);
+        }
+    }
+    folds
+}
+
+/// Computes folds from `{{{` and `}}}` markers in lines.
+///
+/// An unmatched marker is ignored.
+pub fn marker_folds<'a, I>(lines: I) -> Vec<(usize, usize)>
+where
+    I: Iterator<Item = &'a [char]>,
+{
+    let has = |l: &[char], m: char| l.windows(3).any(|w| w.iter().all(|&c| c == m));
+    let mut folds = Vec::new();
+    let mut starts = Vec::new();
+    for (i, l) in lines.enumerate() {
+        if has(l, '{') {
+            starts.push(i);
+        } else if has(l, '}') {
+            if let Some(s) = starts.pop() {
+                folds.push((s, i));
+            }
+        }
+    }
+    folds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(folds: &Folds) -> Vec<(usize, usize)> {
+        folds.folds().iter().map(|f| (f.start, f.end)).collect()
+    }
+
+    #[test]
+    fn test_open_close() {
+        let mut folds = Folds::new();
+        folds.create(0, 9);
+        folds.create(2, 4);
+        folds.create(3, 4);
+        folds.create(6, 7);
+        assert_eq!(ranges(&folds), vec![(0, 9), (2, 4), (3, 4), (6, 7)]);
+
+        assert!(folds.close(3));
+        assert_eq!(folds.closed_fold_at(4), Some((3, 4)));
+        assert!(folds.close(3));
+        assert_eq!(folds.closed_fold_at(4), Some((2, 4)));
+        assert!(folds.close(3));
+        assert!(!folds.close(3));
+        assert_eq!(folds.closed_fold_at(7), Some((0, 9)));
+
+        assert!(folds.open(3));
+        assert_eq!(folds.closed_fold_at(3), Some((2, 4)));
+        assert!(folds.toggle(3));
+        assert_eq!(folds.closed_fold_at(3), Some((3, 4)));
+        assert!(folds.toggle(3));
+        assert_eq!(folds.closed_fold_at(3), None);
+        assert!(folds.toggle(3));
+        assert_eq!(folds.closed_fold_at(3), Some((3, 4)));
+        assert!(folds.delete(5));
+        assert_eq!(ranges(&folds), vec![(2, 4), (3, 4), (6, 7)]);
+    }
+
+    #[test]
+    fn test_shift() {
+        let mut folds = Folds::new();
+        folds.create(2, 4);
+        folds.create(6, 7);
+        folds.insert_lines(2, 1, false);
+        assert_eq!(ranges(&folds), vec![(3, 5), (7, 8)]);
+        folds.insert_lines(5, 2, true);
+        assert_eq!(ranges(&folds), vec![(3, 7), (9, 10)]);
+        folds.delete_lines(2, 1);
+        assert_eq!(ranges(&folds), vec![(2, 6), (8, 9)]);
+        folds.delete_lines(5, 4);
+        assert_eq!(ranges(&folds), vec![(2, 5)]);
+
+        let mut folds = Folds::new();
+        folds.create(0, 2);
+        folds.create(3, 5);
+        folds.delete_lines(2, 1);
+        assert_eq!(ranges(&folds), vec![(0, 2), (3, 4)]);
+        folds.create(1, 2);
+        folds.delete_lines(0, 2);
+        assert_eq!(ranges(&folds), vec![(0, 0), (1, 2)]);
+    }
+
+    #[test]
+    fn test_indent_folds() {
+        let widths = [Some(0), Some(4), None, Some(8), Some(4), Some(0), Some(4)];
+        assert_eq!(indent_folds(&widths, 4), vec![(3, 3), (1, 4), (6, 6)]);
+    }
+
+    #[test]
+    fn test_marker_folds() {
+        let text = "a {{{\nb {{{\nc\n}}}\n}}}\n}}}\nd {{{";
+        let lines: Vec<Vec<char>> = text.lines().map(|l| l.chars().collect()).collect();
+        assert_eq!(
+            marker_folds(lines.iter().map(|l| &l[..])),
+            vec![(1, 3), (0, 4)]
+        );
+    }
+}
@@ -0,0 +1,765 @@
+//! Syntax highlighting with an incremental tokenizer.
+//!
+//! A language is described by a `Grammar`, a state machine whose states
+//! have ordered rules. A rule matches a `Pattern` at the current column,
+//! styles the matched characters and may push or pop a state, so that
+//! constructs like block comments can span lines. Characters which no rule
+//! matches take the style of the current state.
+//!
+//! A `Highlighter` keeps the spans of every line together with the stack
+//! of states at its beginning and end. After an edit, only changed lines
+//! are tokenized again, followed by lines whose starting stack changed.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::highlight::{rust, Highlighter, Span, Style};
+//!
+//! let lines: Vec<Vec<char>> = vec!["let x = 1; /*".chars().collect(), "*/".chars().collect()];
+//! let mut h = Highlighter::new(rust());
+//! h.update(lines.len(), |l| &lines[l]);
+//! assert_eq!(h.spans(0).unwrap()[0], Span { start: 0, end: 3, style: Style::Keyword });
+//! assert_eq!(h.spans(1), Some(&[Span { start: 0, end: 2, style: Style::Comment }][..]));
+//! ```
+
+use std::ops::Range;
+use std::sync::Arc;
+
+/// A style of highlighted characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Style {
+    /// Characters which are not highlighted.
+    Plain,
+    /// A keyword.
+    Keyword,
+    /// A name of a type.
+    Type,
+    /// A name of a function or a macro.
+    Function,
+    /// A string or a character literal.
+    String,
+    /// An escape sequence in a string.
+    Escape,
+    /// A number.
+    Number,
+    /// A constant like `true` or `null`.
+    Constant,
+    /// A comment.
+    Comment,
+    /// Punctuation.
+    Punctuation,
+    /// A key of an object.
+    Key,
+    /// A heading.
+    Heading,
+    /// Emphasized text.
+    Emphasis,
+    /// Strongly emphasized text.
+    Strong,
+    /// Code in text.
+    Code,
+    /// A link.
+    Link,
+}
+
+/// A pattern which a rule matches at a column.
+#[derive(Clone, Copy)]
+pub enum Pattern {
+    /// A literal string.
+    Literal(&'static str),
+    /// One of words which are not adjacent to other alphanumeric characters.
+    Keywords(&'static [&'static str]),
+    /// A character satisfying the first function followed by characters satisfying the second.
+    Run(fn(char) -> bool, fn(char) -> bool),
+    /// The rest of a line.
+    Rest,
+    /// A function which returns the length of a match at a column.
+    Custom(fn(&[char], usize) -> Option<usize>),
+}
+
+fn is_word(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+/// Returns whether `line` has `s` at column `i`.
+fn has(line: &[char], i: usize, s: &str) -> bool {
+    s.chars().enumerate().all(|(n, ch)| line.get(i + n) == Some(&ch))
+}
+
+impl Pattern {
+    /// Returns the length of a match at column `i`, which is not zero.
+    pub fn matches(&self, line: &[char], i: usize) -> Option<usize> {
+        let n = match *self {
+            Pattern::Literal(s) => has(line, i, s).then(|| s.chars().count()),
+            Pattern::Keywords(words) => {
+                if i > 0 && is_word(line[i - 1]) {
+                    return None;
+                }
+                let n = line[i..].iter().take_while(|&&ch| is_word(ch)).count();
+                let w: String = line[i..i + n].iter().collect();
+                words.contains(&&*w).then_some(n)
+            }
+            Pattern::Run(first, rest) => {
+                if i > 0 && is_word(line[i - 1]) && is_word(line[i]) || !first(line[i]) {
+                    return None;
+                }
+                Some(1 + line[i + 1..].iter().take_while(|&&ch| rest(ch)).count())
+            }
+            Pattern::Rest => Some(line.len() - i),
+            Pattern::Custom(f) => f(line, i),
+        };
+        n.filter(|&n| n > 0)
+    }
+}
+
+/// What a rule does to the stack of states after a match.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    /// Stays in the current state.
+    Stay,
+    /// Enters a state.
+    Push(usize),
+    /// Returns to the previous state.
+    Pop,
+}
+
+/// A rule of a state.
+#[derive(Clone, Copy)]
+pub struct Rule {
+    /// The pattern to match.
+    pub pattern: Pattern,
+    /// The style of matched characters.
+    pub style: Style,
+    /// The action after a match.
+    pub action: Action,
+}
+
+impl Rule {
+    /// Creates a rule which stays in the current state.
+    pub fn new(pattern: Pattern, style: Style) -> Rule {
+        Rule {
+            pattern,
+            style,
+            action: Action::Stay,
+        }
+    }
+
+    /// Makes the rule enter a state.
+    pub fn push(self, state: usize) -> Rule {
+        Rule {
+            action: Action::Push(state),
+            ..self
+        }
+    }
+
+    /// Makes the rule return to the previous state.
+    pub fn pop(self) -> Rule {
+        Rule {
+            action: Action::Pop,
+            ..self
+        }
+    }
+}
+
+/// A state of a grammar.
+#[derive(Clone)]
+pub struct State {
+    /// The style of characters which no rule matches.
+    pub style: Style,
+    /// Rules tried in order.
+    pub rules: Vec<Rule>,
+}
+
+/// A grammar of a language, which starts in the first state.
+#[derive(Clone)]
+pub struct Grammar {
+    /// The name of the language.
+    pub name: &'static str,
+    /// The states.
+    pub states: Vec<State>,
+}
+
+/// Characters of a line with the same style.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Span {
+    /// The first column.
+    pub start: usize,
+    /// The column just after the last character.
+    pub end: usize,
+    /// The style.
+    pub style: Style,
+}
+
+impl Grammar {
+    /// Tokenizes a line starting with a stack of states, returning the spans
+    /// of highlighted characters and the stack at the end of the line.
+    pub fn tokenize(&self, line: &[char], stack: &[usize]) -> (Vec<Span>, Vec<usize>) {
+        let mut stack = stack.to_vec();
+        let mut spans: Vec<Span> = Vec::new();
+        let mut push = |start: usize, end: usize, style: Style| {
+            if style == Style::Plain {
+                return;
+            }
+            match spans.last_mut() {
+                Some(s) if s.end == start && s.style == style => s.end = end,
+                _ => spans.push(Span { start, end, style }),
+            }
+        };
+        let mut i = 0;
+        while i < line.len() {
+            let state = &self.states[stack.last().cloned().unwrap_or(0)];
+            let m = state
+                .rules
+                .iter()
+                .filter_map(|r| r.pattern.matches(line, i).map(|n| (r, n)))
+                .next();
+            // A match never goes past the end of the line, such as an escape
+            // at the end.
+            let (r, n) = match m {
+                Some((r, n)) => (r, n.min(line.len() - i)),
+                None => {
+                    push(i, i + 1, state.style);
+                    i += 1;
+                    continue;
+                }
+            };
+            push(i, i + n, r.style);
+            i += n;
+            match r.action {
+                Action::Stay => (),
+                Action::Push(s) => stack.push(s),
+                // The first state is never left.
+                Action::Pop if stack.len() > 1 => {
+                    stack.pop();
+                }
+                Action::Pop => (),
+            }
+        }
+        (spans, stack)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Line {
+    start: Vec<usize>,
+    end: Vec<usize>,
+    spans: Vec<Span>,
+    dirty: bool,
+}
+
+impl Line {
+    fn dirty() -> Line {
+        Line {
+            start: Vec::new(),
+            end: Vec::new(),
+            spans: Vec::new(),
+            dirty: true,
+        }
+    }
+}
+
+/// Spans of lines kept up to date incrementally.
+#[derive(Clone)]
+pub struct Highlighter {
+    grammar: Arc<Grammar>,
+    lines: Vec<Line>,
+    /// Lines which include every changed line, so that an update need not
+    /// look for them in all the lines.
+    dirty: Range<usize>,
+}
+
+impl Highlighter {
+    /// Creates a `Highlighter` with no lines tokenized.
+    pub fn new(grammar: Grammar) -> Highlighter {
+        Highlighter {
+            grammar: Arc::new(grammar),
+            lines: Vec::new(),
+            dirty: 0..0,
+        }
+    }
+
+    /// Returns the grammar.
+    pub fn grammar(&self) -> &Grammar {
+        &self.grammar
+    }
+
+    /// Returns the spans of a line as of the last update.
+    pub fn spans(&self, line: usize) -> Option<&[Span]> {
+        self.lines.get(line).map(|l| &l.spans[..])
+    }
+
+    fn mark(&mut self, lines: Range<usize>) {
+        if lines.is_empty() {
+            return;
+        }
+        self.dirty = if self.dirty.is_empty() {
+            lines
+        } else {
+            self.dirty.start.min(lines.start)..self.dirty.end.max(lines.end)
+        };
+    }
+
+    /// Marks a line as changed.
+    pub fn invalidate(&mut self, line: usize) {
+        if let Some(l) = self.lines.get_mut(line) {
+            l.dirty = true;
+            self.mark(line..line + 1);
+        }
+    }
+
+    /// Marks all the lines as changed.
+    pub fn invalidate_all(&mut self) {
+        for l in &mut self.lines {
+            l.dirty = true;
+        }
+        self.dirty = 0..self.lines.len();
+    }
+
+    /// Records `n` lines inserted after `line`, which is changed too.
+    pub fn insert_lines(&mut self, line: usize, n: usize) {
+        let at = (line + 1).min(self.lines.len());
+        self.lines.splice(at..at, (0..n).map(|_| Line::dirty()));
+        if self.dirty.end > at {
+            self.dirty.end += n;
+            if self.dirty.start >= at {
+                self.dirty.start += n;
+            }
+        }
+        self.mark(at..at + n);
+        self.invalidate(line);
+    }
+
+    /// Records `n` lines following `line` joined into it.
+    pub fn delete_lines(&mut self, line: usize, n: usize) {
+        let len = self.lines.len();
+        let (at, end) = ((line + 1).min(len), (line + 1 + n).min(len));
+        self.lines.drain(at..end);
+        let shift = |l: usize| if l > end { l - (end - at) } else { l.min(at) };
+        self.dirty = shift(self.dirty.start)..shift(self.dirty.end);
+        self.invalidate(line);
+    }
+
+    /// Tokenizes changed lines and lines whose starting stack changed,
+    /// where `f` returns a line of the text with `count` lines.
+    ///
+    /// Returns the range of lines which are tokenized.
+    pub fn update<'a, F>(&mut self, count: usize, f: F) -> Range<usize>
+    where
+        F: Fn(usize) -> &'a [char],
+    {
+        if self.lines.len() > count {
+            self.lines.truncate(count);
+        }
+        let len = self.lines.len();
+        while self.lines.len() < count {
+            self.lines.push(Line::dirty());
+        }
+        self.mark(len..count);
+        let dirty = ::std::mem::replace(&mut self.dirty, 0..0);
+        let dirty = dirty.start.min(count)..dirty.end.min(count);
+        let first = match self.lines[dirty.clone()].iter().position(|l| l.dirty) {
+            Some(n) => dirty.start + n,
+            None => return 0..0,
+        };
+        let mut last = first;
+        let mut n = first;
+        while n < count {
+            let start = if n == 0 {
+                vec![0]
+            } else {
+                self.lines[n - 1].end.clone()
+            };
+            if !self.lines[n].dirty && self.lines[n].start == start {
+                match self.lines[n..dirty.end.max(n)].iter().position(|l| l.dirty) {
+                    Some(d) => {
+                        n += d;
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+            let (spans, end) = self.grammar.tokenize(f(n), &start);
+            self.lines[n] = Line {
+                start,
+                end,
+                spans,
+                dirty: false,
+            };
+            last = n;
+            n += 1;
+        }
+        first..last + 1
+    }
+}
+
+fn is_ident_start(ch: char) -> bool {
+    ch.is_alphabetic() || ch == '_'
+}
+
+fn is_upper(ch: char) -> bool {
+    ch.is_uppercase()
+}
+
+fn is_digit(ch: char) -> bool {
+    ch.is_ascii_digit()
+}
+
+/// Returns the length of a string literal closed in the line, or `None`.
+fn quoted(line: &[char], i: usize, q: char) -> Option<usize> {
+    if line[i] != q {
+        return None;
+    }
+    let mut n = i + 1;
+    while n < line.len() {
+        match line[n] {
+            '\\' => n += 2,
+            ch if ch == q => return Some(n + 1 - i),
+            _ => n += 1,
+        }
+    }
+    None
+}
+
+/// Returns the grammar of Rust.
+pub fn rust() -> Grammar {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+        "type", "unsafe", "use", "where", "while",
+    ];
+    fn line_comment(line: &[char], i: usize) -> Option<usize> {
+        has(line, i, "//").then_some(line.len() - i)
+    }
+    fn character(line: &[char], i: usize) -> Option<usize> {
+        let n = quoted(line, i, '\'')?;
+        // A lifetime like `'a` is not closed by a quote soon.
+        Some(n).filter(|&n| n <= 4 || line[i + 1] == '\\')
+    }
+    fn call(line: &[char], i: usize) -> Option<usize> {
+        if !is_ident_start(line[i]) || i > 0 && is_word(line[i - 1]) {
+            return None;
+        }
+        let n = line[i..].iter().take_while(|&&ch| is_word(ch)).count();
+        match line.get(i + n) {
+            Some(&'!') => Some(n + 1),
+            Some(&'(') => Some(n),
+            _ => None,
+        }
+    }
+    Grammar {
+        name: "rust",
+        states: vec![
+            State {
+                style: Style::Plain,
+                rules: vec![
+                    Rule::new(Pattern::Custom(line_comment), Style::Comment),
+                    Rule::new(Pattern::Literal("/*"), Style::Comment).push(1),
+                    Rule::new(Pattern::Literal("\""), Style::String).push(2),
+                    Rule::new(Pattern::Custom(character), Style::String),
+                    Rule::new(Pattern::Keywords(KEYWORDS), Style::Keyword),
+                    Rule::new(Pattern::Keywords(&["true", "false"]), Style::Constant),
+                    Rule::new(Pattern::Custom(call), Style::Function),
+                    Rule::new(Pattern::Run(is_upper, is_word), Style::Type),
+                    Rule::new(Pattern::Run(is_digit, is_word), Style::Number),
+                    Rule::new(Pattern::Run(is_ident_start, is_word), Style::Plain),
+                ],
+            },
+            State {
+                style: Style::Comment,
+                rules: vec![
+                    Rule::new(Pattern::Literal("/*"), Style::Comment).push(1),
+                    Rule::new(Pattern::Literal("*/"), Style::Comment).pop(),
+                ],
+            },
+            State {
+                style: Style::String,
+                rules: vec![
+                    Rule::new(Pattern::Custom(|l, i| (l[i] == '\\').then_some(2)), Style::Escape),
+                    Rule::new(Pattern::Literal("\""), Style::String).pop(),
+                ],
+            },
+        ],
+    }
+}
+
+/// Returns the grammar of JSON.
+pub fn json() -> Grammar {
+    fn key(line: &[char], i: usize) -> Option<usize> {
+        let n = quoted(line, i, '"')?;
+        let rest = &line[i + n..];
+        let ws = rest.iter().take_while(|ch| ch.is_whitespace()).count();
+        (rest.get(ws) == Some(&':')).then_some(n)
+    }
+    fn string(line: &[char], i: usize) -> Option<usize> {
+        // An unterminated string runs to the end of the line.
+        (line[i] == '"').then(|| quoted(line, i, '"').unwrap_or(line.len() - i))
+    }
+    fn number(line: &[char], i: usize) -> Option<usize> {
+        let digits = |n: usize| line[n..].iter().take_while(|ch| ch.is_ascii_digit()).count();
+        let mut n = i + usize::from(line[i] == '-');
+        let d = digits(n);
+        if d == 0 || i > 0 && is_word(line[i - 1]) {
+            return None;
+        }
+        n += d;
+        if line.get(n) == Some(&'.') && digits(n + 1) > 0 {
+            n += 1 + digits(n + 1);
+        }
+        if matches!(line.get(n), Some(&'e' | &'E')) {
+            let m = n + 1 + usize::from(matches!(line.get(n + 1), Some(&'+' | &'-')));
+            if digits(m) > 0 {
+                n = m + digits(m);
+            }
+        }
+        Some(n - i)
+    }
+    Grammar {
+        name: "json",
+        states: vec![
+            State {
+                style: Style::Plain,
+                rules: vec![
+                    Rule::new(Pattern::Custom(key), Style::Key),
+                    Rule::new(Pattern::Custom(string), Style::String),
+                    Rule::new(Pattern::Custom(number), Style::Number),
+                    Rule::new(Pattern::Keywords(&["true", "false", "null"]), Style::Constant),
+                    Rule::new(
+                        Pattern::Run(|ch| "{}[]:,".contains(ch), |_| false),
+                        Style::Punctuation,
+                    ),
+                ],
+            },
+        ],
+    }
+}
+
+/// Returns the grammar of Markdown.
+pub fn markdown() -> Grammar {
+    fn fence(line: &[char], i: usize) -> Option<usize> {
+        let indent = line.iter().take_while(|&&ch| ch == ' ').count();
+        (i == 0 && indent <= 3 && has(line, indent, "```")).then_some(line.len() - i)
+    }
+    fn heading(line: &[char], i: usize) -> Option<usize> {
+        let n = line.iter().take_while(|&&ch| ch == '#').count();
+        let spaced = line.get(n).is_none_or(|&ch| ch == ' ');
+        (i == 0 && (1..=6).contains(&n) && spaced).then_some(line.len())
+    }
+    fn list(line: &[char], i: usize) -> Option<usize> {
+        let indent = line.iter().take_while(|&&ch| ch == ' ').count();
+        if i != indent {
+            return None;
+        }
+        let n = match line.get(i) {
+            Some(&'-' | &'*' | &'+') => 1,
+            _ => {
+                let d = line[i..].iter().take_while(|ch| ch.is_ascii_digit()).count();
+                if d > 0 && matches!(line.get(i + d), Some(&'.' | &')')) {
+                    d + 1
+                } else {
+                    return None;
+                }
+            }
+        };
+        (line.get(i + n) == Some(&' ')).then_some(n)
+    }
+    fn quote(line: &[char], i: usize) -> Option<usize> {
+        (i == 0 && line[0] == '>').then_some(line.len())
+    }
+    fn delimited(line: &[char], i: usize, d: &str) -> Option<usize> {
+        let len = d.chars().count();
+        if !has(line, i, d) || line.get(i + len).is_none_or(|ch| ch.is_whitespace()) {
+            return None;
+        }
+        (i + len + 1..line.len())
+            .find(|&n| has(line, n, d) && !line[n - 1].is_whitespace())
+            .map(|n| n + len - i)
+    }
+    fn link(line: &[char], i: usize) -> Option<usize> {
+        if line[i] != '[' {
+            return None;
+        }
+        let close = i + line[i..].iter().position(|&ch| ch == ']')?;
+        if line.get(close + 1) != Some(&'(') {
+            return None;
+        }
+        line[close..].iter().position(|&ch| ch == ')').map(|n| close + n + 1 - i)
+    }
+    Grammar {
+        name: "markdown",
+        states: vec![
+            State {
+                style: Style::Plain,
+                rules: vec![
+                    Rule::new(Pattern::Custom(fence), Style::Code).push(1),
+                    Rule::new(Pattern::Custom(heading), Style::Heading),
+                    Rule::new(Pattern::Custom(quote), Style::Comment),
+                    Rule::new(Pattern::Custom(list), Style::Punctuation),
+                    Rule::new(Pattern::Literal("\\"), Style::Escape),
+                    Rule::new(Pattern::Custom(|l, i| delimited(l, i, "`")), Style::Code),
+                    Rule::new(Pattern::Custom(|l, i| delimited(l, i, "**")), Style::Strong),
+                    Rule::new(Pattern::Custom(|l, i| delimited(l, i, "*")), Style::Emphasis),
+                    Rule::new(Pattern::Custom(|l, i| delimited(l, i, "_")), Style::Emphasis),
+                    Rule::new(Pattern::Custom(link), Style::Link),
+                ],
+            },
+            State {
+                style: Style::Code,
+                rules: vec![Rule::new(Pattern::Custom(fence), Style::Code).pop()],
+            },
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(grammar: &Grammar, text: &str) -> Vec<Vec<(String, Style)>> {
+        let lines: Vec<Vec<char>> = text.lines().map(|l| l.chars().collect()).collect();
+        let mut h = Highlighter::new(grammar.clone());
+        h.update(lines.len(), |l| &lines[l]);
+        (0..lines.len())
+            .map(|l| {
+                h.spans(l)
+                    .unwrap()
+                    .iter()
+                    .map(|s| (lines[l][s.start..s.end].iter().collect(), s.style))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn s(text: &str, style: Style) -> (String, Style) {
+        (text.to_string(), style)
+    }
+
+    #[test]
+    fn test_rust() {
+        let text = "fn main() { // hi\n    let s = \"a\\n\"; /* x /* y */\n z */ println!('c', 1u8, Vec::new)\n}";
+        assert_eq!(
+            spans(&rust(), text),
+            vec![
+                vec![
+                    s("fn", Style::Keyword),
+                    s("main", Style::Function),
+                    s("// hi", Style::Comment),
+                ],
+                vec![
+                    s("let", Style::Keyword),
+                    s("\"a", Style::String),
+                    s("\\n", Style::Escape),
+                    s("\"", Style::String),
+                    s("/* x /* y */", Style::Comment),
+                ],
+                vec![
+                    s(" z */", Style::Comment),
+                    s("println!", Style::Function),
+                    s("'c'", Style::String),
+                    s("1u8", Style::Number),
+                    s("Vec", Style::Type),
+                ],
+                vec![],
+            ]
+        );
+        assert_eq!(
+            spans(&rust(), "\"a\\"),
+            vec![vec![s("\"a", Style::String), s("\\", Style::Escape)]]
+        );
+        let lifetime = spans(&rust(), "fn f<'a>(x: &'a str)");
+        assert!(!lifetime[0].iter().any(|&(_, style)| style == Style::String));
+    }
+
+    #[test]
+    fn test_json() {
+        let text = "{\"a\": [1, -2.5e3, true, null],\n \"b\" : \"x\\\"y\"}";
+        assert_eq!(
+            spans(&json(), text),
+            vec![
+                vec![
+                    s("{", Style::Punctuation),
+                    s("\"a\"", Style::Key),
+                    s(":", Style::Punctuation),
+                    s("[", Style::Punctuation),
+                    s("1", Style::Number),
+                    s(",", Style::Punctuation),
+                    s("-2.5e3", Style::Number),
+                    s(",", Style::Punctuation),
+                    s("true", Style::Constant),
+                    s(",", Style::Punctuation),
+                    s("null", Style::Constant),
+                    s("],", Style::Punctuation),
+                ],
+                vec![
+                    s("\"b\"", Style::Key),
+                    s(":", Style::Punctuation),
+                    s("\"x\\\"y\"", Style::String),
+                    s("}", Style::Punctuation),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_markdown() {
+        let text = "# Title\n- a *b* **c** `d` [e](f)\n```rust\n# not heading\n```\n> q";
+        assert_eq!(
+            spans(&markdown(), text),
+            vec![
+                vec![s("# Title", Style::Heading)],
+                vec![
+                    s("-", Style::Punctuation),
+                    s("*b*", Style::Emphasis),
+                    s("**c**", Style::Strong),
+                    s("`d`", Style::Code),
+                    s("[e](f)", Style::Link),
+                ],
+                vec![s("```rust", Style::Code)],
+                vec![s("# not heading", Style::Code)],
+                vec![s("```", Style::Code)],
+                vec![s("> q", Style::Comment)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_incremental() {
+        let mut lines: Vec<Vec<char>> = "a\n/* b\nc\nd */\ne\nf"
+            .lines()
+            .map(|l| l.chars().collect())
+            .collect();
+        let mut h = Highlighter::new(rust());
+        assert_eq!(h.update(lines.len(), |l| &lines[l]), 0..6);
+        assert_eq!(h.update(lines.len(), |l| &lines[l]), 0..0);
+
+        // A change which keeps the stack at the end of the line.
+        lines[2] = "cc".chars().collect();
+        h.invalidate(2);
+        assert_eq!(h.update(lines.len(), |l| &lines[l]), 2..3);
+
+        // Closing the comment early changes the next line, which ends with the same stack as before.
+        lines[2] = "c */".chars().collect();
+        h.invalidate(2);
+        assert_eq!(h.update(lines.len(), |l| &lines[l]), 2..4);
+        assert_eq!(h.spans(3), Some(&[][..]));
+
+        lines.insert(1, "x".chars().collect());
+        h.insert_lines(0, 1);
+        assert_eq!(h.update(lines.len(), |l| &lines[l]), 0..2);
+        assert_eq!(h.spans(3), Some(&[Span { start: 0, end: 4, style: Style::Comment }][..]));
+
+        lines.remove(1);
+        h.delete_lines(0, 1);
+        assert_eq!(h.update(lines.len(), |l| &lines[l]), 0..1);
+
+        // Changed lines are followed by later insertions and deletions.
+        h.invalidate(5);
+        lines.insert(2, "y".chars().collect());
+        h.insert_lines(1, 1);
+        assert_eq!(h.update(lines.len(), |l| &lines[l]), 1..7);
+        h.invalidate(6);
+        lines.remove(5);
+        h.delete_lines(4, 1);
+        assert_eq!(h.update(lines.len(), |l| &lines[l]), 4..6);
+    }
+}
@@ -0,0 +1,394 @@
+//! Indentation options and language-aware indentation rules.
+//!
+//! Widths of indentation are measured in display columns, where a tab
+//! advances to the next multiple of `tabstop`.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::indent::{indent_width, IndentOptions};
+//!
+//! let options = IndentOptions::default();
+//! let line: Vec<char> = "\t  x".chars().collect();
+//! assert_eq!(indent_width(&line, options.tabstop), 10);
+//! assert_eq!(options.indent_string(10), "          ");
+//! ```
+
+/// Options which control how lines are indented.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndentOptions {
+    /// The number of columns of one level of indentation.
+    pub shiftwidth: usize,
+    /// The number of columns a tab occupies.
+    pub tabstop: usize,
+    /// Whether indentation is made of spaces only.
+    pub expandtab: bool,
+    /// Whether shifting rounds indentation to a multiple of `shiftwidth`.
+    pub shiftround: bool,
+    /// Whether a new line copies indentation of the line it was split from.
+    pub autoindent: bool,
+}
+
+impl Default for IndentOptions {
+    fn default() -> IndentOptions {
+        IndentOptions {
+            shiftwidth: 4,
+            tabstop: 8,
+            expandtab: true,
+            shiftround: false,
+            autoindent: false,
+        }
+    }
+}
+
+impl IndentOptions {
+    /// Returns whitespace which indents a line by `width` columns.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use edit::editor::indent::IndentOptions;
+    ///
+    /// let options = IndentOptions {
+    ///     expandtab: false,
+    ///     ..IndentOptions::default()
+    /// };
+    /// assert_eq!(options.indent_string(10), "\t  ");
+    /// ```
+    pub fn indent_string(&self, width: usize) -> String {
+        if self.expandtab || self.tabstop == 0 {
+            return " ".repeat(width);
+        }
+        "\t".repeat(width / self.tabstop) + &" ".repeat(width % self.tabstop)
+    }
+
+    /// Returns `width` shifted by `levels` levels of indentation, which may be negative.
+    pub fn shift(&self, width: usize, levels: isize) -> usize {
+        let sw = self.shiftwidth.max(1);
+        let n = levels.unsigned_abs();
+        if levels >= 0 {
+            if self.shiftround {
+                (width / sw + n) * sw
+            } else {
+                width + n * sw
+            }
+        } else if self.shiftround {
+            // A partial level counts as one.
+            (width.div_ceil(sw)).saturating_sub(n) * sw
+        } else {
+            width.saturating_sub(n * sw)
+        }
+    }
+}
+
+/// Returns the width of leading whitespace of a line.
+pub fn indent_width(line: &[char], tabstop: usize) -> usize {
+    let mut width = 0;
+    for &ch in line {
+        match ch {
+            ' ' => width += 1,
+            '\t' if tabstop > 0 => width = (width / tabstop + 1) * tabstop,
+            _ => break,
+        }
+    }
+    width
+}
+
+/// Returns the number of leading whitespace characters of a line.
+pub(crate) fn indent_len(line: &[char]) -> usize {
+    line.iter()
+        .position(|&ch| ch != ' ' && ch != '\t')
+        .unwrap_or(line.len())
+}
+
+/// Returns a line with runs of whitespace rewritten for another tab width.
+///
+/// Tabs of `line` are expanded with `from`, and the whitespace is written
+/// back with tabs of `to` unless `expandtab` is set, so that the text keeps
+/// its display columns. Only leading whitespace is rewritten unless `all`
+/// is set.
+///
+/// # Examples
+///
+/// ```
+/// use edit::editor::indent::retab_line;
+///
+/// let line: Vec<char> = "\ta\tb".chars().collect();
+/// assert_eq!(retab_line(&line, 8, 4, true, false), "        a\tb");
+/// assert_eq!(retab_line(&line, 8, 4, true, true), "        a       b");
+/// assert_eq!(retab_line(&line, 8, 4, false, true), "\t\ta\t\tb");
+/// ```
+pub fn retab_line(line: &[char], from: usize, to: usize, expandtab: bool, all: bool) -> String {
+    let mut result = String::new();
+    let mut column = 0;
+    let mut i = 0;
+    while i < line.len() {
+        if line[i] != ' ' && line[i] != '\t' {
+            if !all {
+                result.extend(&line[i..]);
+                break;
+            }
+            result.push(line[i]);
+            column += 1;
+            i += 1;
+            continue;
+        }
+        let start = column;
+        let j = i + line[i..]
+            .iter()
+            .position(|&ch| ch != ' ' && ch != '\t')
+            .unwrap_or(line.len() - i);
+        for &ch in &line[i..j] {
+            column = match ch {
+                '\t' if from > 0 => (column / from + 1) * from,
+                _ => column + 1,
+            };
+        }
+        result += &whitespace(start, column, to, expandtab);
+        i = j;
+    }
+    result
+}
+
+/// Returns whitespace which fills display columns from `start` to `end`.
+fn whitespace(start: usize, end: usize, tabstop: usize, expandtab: bool) -> String {
+    if expandtab || tabstop == 0 {
+        return " ".repeat(end - start);
+    }
+    let mut s = String::new();
+    let mut column = start;
+    // A single space is kept even if a tab could fill it.
+    if end - start > 1 {
+        while (column / tabstop + 1) * tabstop <= end {
+            s.push('\t');
+            column = (column / tabstop + 1) * tabstop;
+        }
+    }
+    s + &" ".repeat(end - column)
+}
+
+/// A kind of indentation found in a text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IndentKind {
+    /// Lines are indented with tabs.
+    Tabs,
+    /// Lines are indented with spaces, by the number of columns per level.
+    Spaces(usize),
+}
+
+/// Indentation inferred from a text by `detect_indent`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DetectedIndent {
+    /// The kind of indentation.
+    pub kind: IndentKind,
+    /// How consistently the text agrees with `kind`, from 0 to 1.
+    pub confidence: f64,
+}
+
+impl DetectedIndent {
+    /// Returns options which follow the detected indentation, keeping the rest of `options`.
+    pub fn apply(&self, options: &IndentOptions) -> IndentOptions {
+        let mut options = options.clone();
+        match self.kind {
+            IndentKind::Tabs => {
+                options.expandtab = false;
+                options.shiftwidth = options.tabstop;
+            }
+            IndentKind::Spaces(n) => {
+                options.expandtab = true;
+                options.shiftwidth = n;
+            }
+        }
+        options
+    }
+}
+
+/// Infers indentation of lines from their leading whitespace.
+///
+/// Whether tabs or spaces are used is decided by the majority of indented
+/// lines, and the width of a level by the most frequent increase of
+/// indentation between consecutive non-blank lines. Returns `None` if no
+/// line is indented or the width cannot be inferred.
+///
+/// # Examples
+///
+/// ```
+/// use edit::editor::indent::{detect_indent, IndentKind};
+///
+/// let text = "a {\n  b {\n    c\n  }\n}\n";
+/// let lines: Vec<Vec<char>> = text.lines().map(|l| l.chars().collect()).collect();
+/// let detected = detect_indent(lines.iter().map(|l| &l[..])).unwrap();
+/// assert_eq!(detected.kind, IndentKind::Spaces(2));
+/// assert_eq!(detected.confidence, 1.0);
+/// ```
+pub fn detect_indent<'a, I>(lines: I) -> Option<DetectedIndent>
+where
+    I: Iterator<Item = &'a [char]>,
+{
+    let (mut tabs, mut spaces) = (0usize, 0usize);
+    // Votes for widths from 2 to 8.
+    let mut votes = [0usize; 9];
+    let mut smallest: Option<usize> = None;
+    let mut prev = 0;
+    for l in lines {
+        let len = indent_len(l);
+        if len == l.len() {
+            continue;
+        }
+        match l.first() {
+            Some(&'\t') => tabs += 1,
+            Some(&' ') => {
+                spaces += 1;
+                if !l[..len].contains(&'\t') {
+                    smallest = Some(smallest.map_or(len, |n| n.min(len)));
+                    if len > prev && len - prev < votes.len() && len - prev >= 2 {
+                        votes[len - prev] += 1;
+                    }
+                }
+            }
+            _ => (),
+        }
+        prev = if l[..len].contains(&'\t') { usize::MAX } else { len };
+    }
+    let total = tabs + spaces;
+    if total == 0 {
+        return None;
+    }
+    if tabs >= spaces {
+        return Some(DetectedIndent {
+            kind: IndentKind::Tabs,
+            confidence: tabs as f64 / total as f64,
+        });
+    }
+    let ratio = spaces as f64 / total as f64;
+    let all: usize = votes.iter().sum();
+    if all == 0 {
+        return smallest.filter(|n| (2..votes.len()).contains(n)).map(|n| {
+            DetectedIndent {
+                kind: IndentKind::Spaces(n),
+                // A guess only from the smallest indentation is a weak one.
+                confidence: ratio / 2.0,
+            }
+        });
+    }
+    // Prefers a smaller width when tied, since larger ones are often its multiples.
+    let (width, &n) = votes
+        .iter()
+        .enumerate()
+        .rev()
+        .max_by_key(|&(_, n)| n)
+        .unwrap();
+    Some(DetectedIndent {
+        kind: IndentKind::Spaces(width),
+        confidence: ratio * n as f64 / all as f64,
+    })
+}
+
+/// A language-aware rule set which decides indentation of lines.
+pub trait IndentRules {
+    /// Returns whether lines following `line` are indented one more level.
+    fn indents_after(&self, line: &str) -> bool;
+
+    /// Returns whether `line` is indented one less level than the lines preceding it.
+    fn dedents(&self, line: &str) -> bool;
+}
+
+/// Rules for languages which delimit blocks with brackets, like Rust or C.
+///
+/// A line whose brackets are left open indents the next lines, and a line
+/// beginning with a close bracket is dedented.
+///
+/// # Examples
+///
+/// ```
+/// use edit::editor::indent::{BracketRules, IndentRules};
+///
+/// assert!(BracketRules.indents_after("fn main() {"));
+/// assert!(!BracketRules.indents_after("let v = [1, 2];"));
+/// assert!(BracketRules.dedents("    }"));
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BracketRules;
+
+impl IndentRules for BracketRules {
+    fn indents_after(&self, line: &str) -> bool {
+        let mut depth: isize = 0;
+        for ch in line.chars() {
+            match ch {
+                '{' | '(' | '[' => depth += 1,
+                '}' | ')' | ']' if depth > 0 => depth -= 1,
+                _ => (),
+            }
+        }
+        depth > 0
+    }
+
+    fn dedents(&self, line: &str) -> bool {
+        matches!(line.trim_start().chars().next(), Some('}' | ')' | ']'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shift() {
+        let mut options = IndentOptions::default();
+        assert_eq!(options.shift(2, 1), 6);
+        assert_eq!(options.shift(2, -1), 0);
+        assert_eq!(options.shift(6, -1), 2);
+        options.shiftround = true;
+        assert_eq!(options.shift(2, 1), 4);
+        assert_eq!(options.shift(6, 2), 12);
+        assert_eq!(options.shift(6, -1), 4);
+        assert_eq!(options.shift(8, -1), 4);
+        assert_eq!(options.shift(0, -1), 0);
+    }
+
+    #[test]
+    fn test_indent_width() {
+        let line: Vec<char> = "  \t x".chars().collect();
+        assert_eq!(indent_width(&line, 8), 9);
+        assert_eq!(indent_width(&line, 4), 5);
+        assert_eq!(indent_len(&line), 4);
+        assert_eq!(indent_len(&[' ', ' ']), 2);
+    }
+
+    #[test]
+    fn test_retab_line() {
+        let chars = |s: &str| s.chars().collect::<Vec<char>>();
+        assert_eq!(retab_line(&chars("  \t x"), 4, 8, true, false), "     x");
+        assert_eq!(retab_line(&chars("         x"), 4, 4, false, false), "\t\t x");
+        assert_eq!(retab_line(&chars("ab  c  \td"), 4, 4, false, true), "ab\tc\td");
+        assert_eq!(retab_line(&chars("abc d"), 4, 4, false, true), "abc d");
+        assert_eq!(retab_line(&chars(""), 4, 4, false, true), "");
+        assert_eq!(retab_line(&chars("  "), 4, 2, false, true), "\t");
+    }
+
+    fn detect(text: &str) -> Option<DetectedIndent> {
+        let lines: Vec<Vec<char>> = text.lines().map(|l| l.chars().collect()).collect();
+        detect_indent(lines.iter().map(|l| &l[..]))
+    }
+
+    #[test]
+    fn test_detect_indent() {
+        assert_eq!(detect("a\n\nb"), None);
+
+        let d = detect("a\n\tb\n\t\tc\n    d").unwrap();
+        assert_eq!(d.kind, IndentKind::Tabs);
+        assert!((d.confidence - 2.0 / 3.0).abs() < 1e-9);
+
+        let d = detect("a\n    b\n        c\n\n    d\n      e").unwrap();
+        assert_eq!(d.kind, IndentKind::Spaces(4));
+        assert!((d.confidence - 2.0 / 3.0).abs() < 1e-9);
+
+        // Tied votes.
+        let d = detect("a\n  b\nc\n    d").unwrap();
+        assert_eq!(d.kind, IndentKind::Spaces(2));
+        assert_eq!(d.confidence, 0.5);
+
+        // Continuation lines only.
+        assert_eq!(detect("/*\n * a\n */"), None);
+    }
+}
@@ -0,0 +1,81 @@
+//! Joining lines, like vim's `J`.
+//!
+//! The indentation of the joined line is removed, and the lines are
+//! separated by a space, except after whitespace, before `)` or with an
+//! empty line. When both lines begin with the same comment leader, the
+//! leader of the joined line is removed too.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::comment::default_leaders;
+//! use edit::editor::join::join_separator;
+//!
+//! let leaders = default_leaders();
+//! let line: Vec<char> = "// a".chars().collect();
+//! let next: Vec<char> = "  // b".chars().collect();
+//! assert_eq!(join_separator(&line, &next, false, &leaders), (5, " "));
+//! ```
+
+use editor::comment::comment_leader;
+
+/// Returns the number of characters to remove from the beginning of `next`
+/// when joining it to `line`, and the separator to put between them.
+///
+/// With `join_spaces`, two spaces separate lines after the end of a
+/// sentence, like vim's `joinspaces`.
+pub fn join_separator(
+    line: &[char],
+    next: &[char],
+    join_spaces: bool,
+    leaders: &[String],
+) -> (usize, &'static str) {
+    let mut skip = next.iter().take_while(|ch| ch.is_whitespace()).count();
+    if let (Some(a), Some(b)) = (comment_leader(line, leaders), comment_leader(next, leaders)) {
+        if line[a] == next[b.clone()] {
+            skip = b.end + next[b.end..].iter().take_while(|ch| ch.is_whitespace()).count();
+        }
+    }
+    let separator = match (line.last(), next.get(skip)) {
+        (None, _) | (_, None) | (_, Some(')')) => "",
+        (Some(ch), _) if ch.is_whitespace() => "",
+        (Some('.'), _) | (Some('!'), _) | (Some('?'), _) if join_spaces => "  ",
+        _ => " ",
+    };
+    (skip, separator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use editor::comment::default_leaders;
+
+    fn join(line: &str, next: &str, join_spaces: bool) -> String {
+        let (line, next): (Vec<char>, Vec<char>) = (line.chars().collect(), next.chars().collect());
+        let (skip, separator) = join_separator(&line, &next, join_spaces, &default_leaders());
+        let mut s: String = line.into_iter().collect();
+        s += separator;
+        s.extend(&next[skip..]);
+        s
+    }
+
+    #[test]
+    fn test_join_separator() {
+        assert_eq!(join("foo(", "    bar)", false), "foo( bar)");
+        assert_eq!(join("foo(bar", "  )", false), "foo(bar)");
+        assert_eq!(join("a ", " b", false), "a b");
+        assert_eq!(join("", "  b", false), "b");
+        assert_eq!(join("a", "   ", false), "a");
+        assert_eq!(join("End.", "Next", false), "End. Next");
+        assert_eq!(join("End.", "Next", true), "End.  Next");
+    }
+
+    #[test]
+    fn test_comment_leaders() {
+        assert_eq!(join("  // a", "  //   b", false), "  // a b");
+        assert_eq!(join("/// a", "/// b", false), "/// a b");
+        assert_eq!(join("/// a", "// b", false), "/// a // b");
+        assert_eq!(join("# a", "# b", false), "# a # b");
+        assert_eq!(join("//! a", "//! b", false), "//! a b");
+    }
+}
@@ -0,0 +1,241 @@
+//! Classes of keyword characters, and subwords of keywords.
+//!
+//! A `KeywordClass` decides which characters make up a keyword, and is
+//! written in the syntax of vim's `iskeyword` option: comma-separated parts,
+//! each of which is a character, a character code, or a range of them like
+//! `a-z` or `48-57`. `@` stands for alphabetic characters and `@-@` for the
+//! `@` itself. A part after `^` excludes characters instead, and later parts
+//! take precedence over earlier ones.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::keyword::KeywordClass;
+//!
+//! let class = KeywordClass::parse("@,48-57,_,-,^x").unwrap();
+//! assert!(class.is_keyword('a'));
+//! assert!(class.is_keyword('-'));
+//! assert!(!class.is_keyword('x'));
+//! assert!(class.is_symbol('+'));
+//! ```
+
+use std::error;
+use std::fmt;
+use std::ops::Range;
+
+/// The default class, the same as vim's.
+pub const DEFAULT_KEYWORD: &str = "@,48-57,_,192-255";
+
+/// An error which can be returned when parsing a `KeywordClass`, with the offending part.
+#[derive(PartialEq, Debug)]
+pub struct ParseError(pub String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid keyword part: {:?}", self.0)
+    }
+}
+
+impl error::Error for ParseError {
+    fn description(&self) -> &str {
+        "invalid keyword part"
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Part {
+    Alphabetic,
+    Range(u32, u32),
+}
+
+impl Part {
+    fn contains(self, ch: char) -> bool {
+        match self {
+            Part::Alphabetic => ch.is_alphabetic(),
+            Part::Range(s, e) => s <= ch as u32 && ch as u32 <= e,
+        }
+    }
+}
+
+/// A set of characters which make up keywords.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeywordClass {
+    /// Parts with whether they include characters, in the order of precedence.
+    parts: Vec<(Part, bool)>,
+}
+
+impl Default for KeywordClass {
+    fn default() -> KeywordClass {
+        KeywordClass::parse(DEFAULT_KEYWORD).unwrap()
+    }
+}
+
+/// Reads a character or a character code from the beginning of `s`.
+fn endpoint(s: &str) -> Option<(u32, &str)> {
+    let digits = s.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 {
+        return s[..digits].parse().ok().map(|n| (n, &s[digits..]));
+    }
+    let ch = s.chars().next()?;
+    Some((ch as u32, &s[ch.len_utf8()..]))
+}
+
+impl KeywordClass {
+    /// Parses a class in the syntax of `iskeyword`.
+    pub fn parse(s: &str) -> Result<KeywordClass, ParseError> {
+        let mut parts = Vec::new();
+        for item in s.split(',').filter(|item| !item.is_empty()) {
+            let (body, include) = match item.strip_prefix('^') {
+                Some(rest) if !rest.is_empty() => (rest, false),
+                _ => (item, true),
+            };
+            if body == "@" {
+                parts.push((Part::Alphabetic, include));
+                continue;
+            }
+            let invalid = || ParseError(item.to_string());
+            let (start, rest) = endpoint(body).ok_or_else(invalid)?;
+            let end = match rest.strip_prefix('-') {
+                Some(rest) => match endpoint(rest) {
+                    Some((end, "")) if start <= end => end,
+                    _ => return Err(invalid()),
+                },
+                None if rest.is_empty() => start,
+                None => return Err(invalid()),
+            };
+            parts.push((Part::Range(start, end), include));
+        }
+        Ok(KeywordClass { parts })
+    }
+
+    /// Returns `true` if `ch` is a keyword character.
+    pub fn is_keyword(&self, ch: char) -> bool {
+        self.parts
+            .iter()
+            .rev()
+            .find(|&&(part, _)| part.contains(ch))
+            .is_some_and(|&(_, include)| include)
+    }
+
+    /// Returns `true` if `ch` is neither a keyword character nor whitespace.
+    pub fn is_symbol(&self, ch: char) -> bool {
+        !(self.is_keyword(ch) || ch.is_whitespace())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Case {
+    Upper,
+    Lower,
+    Digit,
+}
+
+/// Splits keywords of a line into subwords, returning their ranges.
+///
+/// A subword is a run of digits, a run of lowercase letters optionally led
+/// by an uppercase one, or a run of uppercase letters not followed by a
+/// lowercase one, so `parseHTTPResponse` is split into `parse`, `HTTP` and
+/// `Response`. Keyword characters which are not alphanumeric, like `_`,
+/// separate subwords without belonging to any.
+///
+/// # Examples
+///
+/// ```
+/// use edit::editor::keyword::{subwords, KeywordClass};
+///
+/// let line: Vec<char> = "get_HTTPStatus2".chars().collect();
+/// let class = KeywordClass::default();
+/// assert_eq!(subwords(&line, &class), vec![0..3, 4..8, 8..14, 14..15]);
+/// ```
+pub fn subwords(line: &[char], class: &KeywordClass) -> Vec<Range<usize>> {
+    let case = |i: usize| {
+        let ch = *line.get(i)?;
+        if !class.is_keyword(ch) {
+            None
+        } else if ch.is_numeric() {
+            Some(Case::Digit)
+        } else if ch.is_uppercase() {
+            Some(Case::Upper)
+        } else if ch.is_alphabetic() {
+            Some(Case::Lower)
+        } else {
+            None
+        }
+    };
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < line.len() {
+        let start = i;
+        let c = match case(i) {
+            Some(c) => c,
+            None => {
+                i += 1;
+                continue;
+            }
+        };
+        while case(i) == Some(c) {
+            i += 1;
+        }
+        if c == Case::Upper && case(i) == Some(Case::Lower) {
+            if i - start > 1 {
+                // The last uppercase letter leads the next subword.
+                i -= 1;
+            } else {
+                while case(i) == Some(Case::Lower) {
+                    i += 1;
+                }
+            }
+        }
+        ranges.push(start..i);
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default() {
+        let class = KeywordClass::default();
+        assert!("aZ09_éÿ".chars().all(|ch| class.is_keyword(ch)));
+        assert!("-*@ \t".chars().all(|ch| !class.is_keyword(ch)));
+        assert!(class.is_symbol('-'));
+        assert!(!class.is_symbol(' '));
+    }
+
+    #[test]
+    fn test_parse() {
+        let class = KeywordClass::parse("a-c,@-@,^,45,^b").unwrap();
+        assert!(class.is_keyword('a'));
+        assert!(!class.is_keyword('b'));
+        assert!(class.is_keyword('@'));
+        assert!(class.is_keyword('^'));
+        assert!(class.is_keyword('-'));
+        assert!(!class.is_keyword('d'));
+
+        let class = KeywordClass::parse("@,^a-z").unwrap();
+        assert!(class.is_keyword('A'));
+        assert!(!class.is_keyword('q'));
+
+        assert_eq!(KeywordClass::parse("z-a"), Err(ParseError("z-a".to_string())));
+        assert!(KeywordClass::parse("ab").is_err());
+        assert!(KeywordClass::parse("a-").is_err());
+    }
+
+    #[test]
+    fn test_subwords() {
+        let class = KeywordClass::default();
+        let split = |s: &str| {
+            let line: Vec<char> = s.chars().collect();
+            subwords(&line, &class)
+                .into_iter()
+                .map(|r| line[r].iter().collect::<String>())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(split("parseHTTPResponse"), vec!["parse", "HTTP", "Response"]);
+        assert_eq!(split("snake_case_name"), vec!["snake", "case", "name"]);
+        assert_eq!(split("__a1B x-URL"), vec!["a", "1", "B", "x", "URL"]);
+        assert!(split("** _").is_empty());
+    }
+}
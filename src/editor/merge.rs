@@ -0,0 +1,256 @@
+//! Three-way merging with conflict markers.
+//!
+//! `merge` combines two descendants of a common base line by line, like
+//! diff3. Changes made by only one side are taken automatically; regions
+//! changed differently by both sides become conflicts delimited by the
+//! standard markers:
+//!
+//! ```text
+//! <<<<<<< ours
+//! lines of ours
+//! =======
+//! lines of theirs
+//! >>>>>>> theirs
+//! ```
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::merge::merge;
+//!
+//! let merged = merge("a\nb\nc\n", "A\nb\nc\n", "a\nb\nC\n", "ours", "theirs");
+//! assert_eq!(merged.text, "A\nb\nC\n");
+//! assert_eq!(merged.conflicts, 0);
+//! ```
+
+use editor::diff::{diff_slices, Algorithm, Op, Tag};
+
+/// A result of `merge`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Merged {
+    /// The merged text, which may contain conflict markers.
+    pub text: String,
+    /// The number of conflicts.
+    pub conflicts: usize,
+}
+
+/// Returns, for each element of the old side, the index of the equal element of the new side.
+fn matches(ops: &[Op], len: usize) -> Vec<Option<usize>> {
+    let mut m = vec![None; len];
+    for op in ops.iter().filter(|op| op.tag == Tag::Equal) {
+        for (i, j) in op.old.clone().zip(op.new.clone()) {
+            m[i] = Some(j);
+        }
+    }
+    m
+}
+
+/// Merges `ours` and `theirs`, which are derived from `base`.
+///
+/// Conflicting regions are labeled with `ours_label` and `theirs_label`.
+pub fn merge(base: &str, ours: &str, theirs: &str, ours_label: &str, theirs_label: &str) -> Merged {
+    let a: Vec<&str> = base.split_inclusive('\n').collect();
+    let o: Vec<&str> = ours.split_inclusive('\n').collect();
+    let t: Vec<&str> = theirs.split_inclusive('\n').collect();
+    let mo = matches(&diff_slices(&a, &o, Algorithm::Myers), a.len());
+    let mt = matches(&diff_slices(&a, &t, Algorithm::Myers), a.len());
+
+    let mut text = String::new();
+    let mut conflicts = 0;
+    let (mut i, mut j, mut k) = (0, 0, 0);
+    while i < a.len() || j < o.len() || k < t.len() {
+        if i < a.len() && mo[i] == Some(j) && mt[i] == Some(k) {
+            text += a[i];
+            i += 1;
+            j += 1;
+            k += 1;
+            continue;
+        }
+        // The next line of the base which is kept by both sides ends the unstable chunk.
+        let (i2, j2, k2) = match (i..a.len()).find(|&n| mo[n].is_some() && mt[n].is_some()) {
+            Some(n) => (n, mo[n].unwrap(), mt[n].unwrap()),
+            None => (a.len(), o.len(), t.len()),
+        };
+        let (x, y, z) = (&a[i..i2], &o[j..j2], &t[k..k2]);
+        if x == y {
+            text.extend(z.iter().cloned());
+        } else if x == z || y == z {
+            text.extend(y.iter().cloned());
+        } else {
+            conflicts += 1;
+            text += &format!("<<<<<<< {}\n", ours_label);
+            push_lines(&mut text, y);
+            text += "=======\n";
+            push_lines(&mut text, z);
+            text += &format!(">>>>>>> {}\n", theirs_label);
+        }
+        i = i2;
+        j = j2;
+        k = k2;
+    }
+    Merged { text, conflicts }
+}
+
+/// Appends lines, terminating the last one so that a marker can follow.
+fn push_lines(text: &mut String, lines: &[&str]) {
+    text.extend(lines.iter().cloned());
+    if !lines.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+}
+
+/// Lines of conflict markers in a buffer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Conflict {
+    /// The line of `<<<<<<<`.
+    pub start: usize,
+    /// The line of `|||||||`, which begins the base section of the diff3 style, if any.
+    pub base: Option<usize>,
+    /// The line of `=======`.
+    pub separator: usize,
+    /// The line of `>>>>>>>`.
+    pub end: usize,
+}
+
+impl Conflict {
+    /// Returns the lines of ours, excluding markers.
+    pub fn ours(&self) -> ::std::ops::Range<usize> {
+        self.start + 1..self.base.unwrap_or(self.separator)
+    }
+
+    /// Returns the lines of theirs, excluding markers.
+    pub fn theirs(&self) -> ::std::ops::Range<usize> {
+        self.separator + 1..self.end
+    }
+}
+
+/// A way to resolve a conflict.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Resolution {
+    /// Takes the lines of ours.
+    Ours,
+    /// Takes the lines of theirs.
+    Theirs,
+    /// Takes the lines of ours followed by the lines of theirs.
+    Both,
+}
+
+/// Finds conflicts from lines, ignoring incomplete ones.
+pub(crate) fn find_conflicts<'a, I>(lines: I) -> Vec<Conflict>
+where
+    I: Iterator<Item = &'a [char]>,
+{
+    let marker = |l: &[char], ch: char| {
+        l.len() >= 7 && l[..7].iter().all(|&c| c == ch) && l.get(7).is_none_or(|&c| c == ' ')
+    };
+    let mut conflicts = Vec::new();
+    let mut current: Option<Conflict> = None;
+    for (n, l) in lines.enumerate() {
+        if marker(l, '<') {
+            current = Some(Conflict {
+                start: n,
+                base: None,
+                separator: 0,
+                end: 0,
+            });
+            continue;
+        }
+        let c = match current.as_mut() {
+            Some(c) => c,
+            None => continue,
+        };
+        if marker(l, '|') && c.separator == 0 && c.base.is_none() {
+            c.base = Some(n);
+        } else if l == ['='; 7] && c.separator == 0 {
+            c.separator = n;
+        } else if marker(l, '>') && c.separator != 0 {
+            c.end = n;
+            conflicts.push(*c);
+            current = None;
+        }
+    }
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use editor::Editor;
+
+    #[test]
+    fn test_merge() {
+        let base = "1\n2\n3\n4\n5\n";
+        let merged = merge(base, "1\n2\n3\n4\n5\n6\n", "0\n1\n2\n3\n4\n5\n", "o", "t");
+        assert_eq!(merged.text, "0\n1\n2\n3\n4\n5\n6\n");
+
+        let merged = merge(base, "1\nx\n3\n4\n5\n", "1\nx\n3\n4\n", "o", "t");
+        assert_eq!(merged.text, "1\nx\n3\n4\n");
+        assert_eq!(merged.conflicts, 0);
+
+        let merged = merge(base, "1\nx\n3\n4\ny\n", "1\nz\n3\n4\n", "o", "t");
+        assert_eq!(
+            merged.text,
+            "1\n\
+             <<<<<<< o\n\
+             x\n\
+             =======\n\
+             z\n\
+             >>>>>>> t\n\
+             3\n\
+             4\n\
+             <<<<<<< o\n\
+             y\n\
+             =======\n\
+             >>>>>>> t\n"
+        );
+        assert_eq!(merged.conflicts, 2);
+    }
+
+    #[test]
+    fn test_merge_without_newline() {
+        let merged = merge("a", "b", "c", "o", "t");
+        assert_eq!(merged.text, "<<<<<<< o\nb\n=======\nc\n>>>>>>> t\n");
+        let merged = merge("a\nb", "a\nb", "a\nc", "o", "t");
+        assert_eq!(merged.text, "a\nc");
+    }
+
+    #[test]
+    fn test_merge_with_at_end() {
+        let merged = |buffer: &str, base: &str, disk: &str| {
+            let mut editor = Editor::new(buffer, 0, 0).unwrap();
+            let conflicts = editor.merge_with(base, disk);
+            (editor.buffer_as_str(), conflicts)
+        };
+        assert_eq!(merged("x\ny\n", "x\ny\n", "x\ny\nz"), (String::from("x\ny\nz"), 0));
+        assert_eq!(merged("x\ny\n", "x\ny\n", "x\nq"), (String::from("x\nq"), 0));
+        assert_eq!(merged("x\ny\n", "x\ny\n", "x\ny"), (String::from("x\ny"), 0));
+        assert_eq!(merged("a\n", "a\n", ""), (String::new(), 0));
+    }
+
+    #[test]
+    fn test_find_conflicts() {
+        let text = "a\n\
+                    <<<<<<< o\n\
+                    b\n\
+                    ||||||| base\n\
+                    =======\n\
+                    >>>>>>> t\n\
+                    <<<<<<<\n\
+                    =======";
+        let lines: Vec<Vec<char>> = text.lines().map(|l| l.chars().collect()).collect();
+        let conflicts = find_conflicts(lines.iter().map(|l| &l[..]));
+        assert_eq!(
+            conflicts,
+            vec![
+                Conflict {
+                    start: 1,
+                    base: Some(3),
+                    separator: 4,
+                    end: 5,
+                },
+            ]
+        );
+        assert_eq!(conflicts[0].ours(), 2..3);
+        assert_eq!(conflicts[0].theirs(), 5..5);
+    }
+}
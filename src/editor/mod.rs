@@ -21,14 +21,65 @@
 //! );
 //! ```
 
+pub mod bracket;
+pub mod case;
+pub mod comment;
+pub mod complete;
 mod core;
 mod core2;
+pub mod crdt;
+pub mod diff;
+pub mod find;
+pub mod fold;
+pub mod highlight;
+pub mod indent;
 mod iterator2d;
+pub mod join;
+pub mod keyword;
+pub mod merge;
+pub mod number;
+pub mod ot;
+pub mod patch;
+pub mod prose;
+pub mod quote;
+pub mod reflow;
+pub mod snippet;
+pub mod sort;
+#[cfg(test)]
+mod testing;
 
+use editor::bracket::{bracket_kind, BracketIndex};
+use editor::case::{convert_case, Case};
+use editor::comment::{default_leaders, toggle_block_comment, toggle_line_comments,
+                      CommentSyntax};
+use editor::complete::{keyword_candidates, keyword_prefix, line_candidates, Completion,
+                       CompletionKind};
 use editor::core::Core;
 pub use editor::core::Position;
+use editor::diff::{diff_slices, Algorithm, Diff, Lines, Tag};
+use editor::find::{find_char, CharSearch};
+use editor::fold::{indent_folds, marker_folds, Folds};
+use editor::highlight::{Grammar, Highlighter, Span, Style};
+use editor::indent::{detect_indent, indent_len, indent_width, retab_line, DetectedIndent,
+                     IndentOptions, IndentRules};
+use editor::join::join_separator;
+use editor::keyword::{subwords, KeywordClass};
+use editor::merge::{find_conflicts, merge, Conflict, Resolution};
+use editor::number::increment_at;
+use editor::ot::{Component, Operation, OperationError};
+use editor::patch::{Applier, HunkResult, Patch};
+use editor::prose::{is_blank, sentence_starts};
+use editor::quote::{quote_pairs, QuoteScope};
+use editor::reflow::{default_leaders as default_reflow_leaders, reflow, words};
+use editor::snippet::{Snippet, SnippetSession};
+use editor::sort::{compare_lines, sort_lines, SortOptions};
 
+use std::cmp::Ordering;
 use std::ops::Range;
+use std::sync::Arc;
+
+/// The confidence above which `Editor::new` follows detected indentation.
+const MIN_INDENT_CONFIDENCE: f64 = 0.5;
 
 /// A pair of parentheses.
 ///
@@ -53,11 +104,31 @@ pub struct Paren {
 #[derive(Clone)]
 pub struct Editor {
     core: Core,
+    indent: IndentOptions,
+    indent_rules: Option<Arc<dyn IndentRules + Send + Sync>>,
+    folds: Folds,
+    highlighter: Option<Highlighter>,
+    brackets: BracketIndex,
+    quote_scope: QuoteScope,
+    keyword: KeywordClass,
+    abbreviations: Vec<String>,
+    find_multiline: bool,
+    last_find: Option<CharSearch>,
+    completion: Option<Completion>,
+    snippet: Option<SnippetSession>,
+    join_spaces: bool,
+    comment_leaders: Vec<String>,
+    comment_syntax: CommentSyntax,
+    reflow_leaders: Vec<String>,
 }
 
 impl Editor {
     /// Creates a new `Editor` which has a buffer and a position of the cursor.
     ///
+    /// Indentation options follow the indentation detected from the buffer
+    /// when it is consistent enough, and can be overridden by
+    /// `set_indent_options`.
+    ///
     /// # Examples
     ///
     /// ```
@@ -69,7 +140,34 @@ impl Editor {
     /// assert!(editor.is_err());
     /// ```
     pub fn new(buffer: &str, line: usize, column: usize) -> Result<Editor, String> {
-        Core::new(buffer, line, column).map(|core| Editor { core })
+        Core::new(buffer, line, column).map(|core| {
+            let mut editor = Editor {
+                core,
+                indent: IndentOptions::default(),
+                indent_rules: None,
+                folds: Folds::new(),
+                highlighter: None,
+                brackets: BracketIndex::default(),
+                quote_scope: QuoteScope::default(),
+                keyword: KeywordClass::default(),
+                abbreviations: Vec::new(),
+                find_multiline: false,
+                last_find: None,
+                completion: None,
+                snippet: None,
+                join_spaces: false,
+                comment_leaders: default_leaders(),
+                comment_syntax: CommentSyntax::default(),
+                reflow_leaders: default_reflow_leaders(),
+            };
+            editor.reindex_brackets(0..editor.line_count());
+            if let Some(d) = editor.detect_indent() {
+                if d.confidence >= MIN_INDENT_CONFIDENCE {
+                    editor.indent = d.apply(&editor.indent);
+                }
+            }
+            editor
+        })
     }
 
     /// Shows the content of the buffer.
@@ -247,7 +345,10 @@ impl Editor {
     /// let pos = editor.next_position(char::is_uppercase);
     /// assert_eq!(pos, Some(Position::new(1, 0)));
     /// ```
-    pub fn next_position(&self, f: fn(char) -> bool) -> Option<Position> {
+    pub fn next_position<F>(&self, f: F) -> Option<Position>
+    where
+        F: Fn(char) -> bool,
+    {
         self.core.next_position(f)
     }
 
@@ -263,7 +364,10 @@ impl Editor {
     /// let pos = editor.previous_position(char::is_uppercase);
     /// assert_eq!(pos, Some(Position::new(0, 0)));
     /// ```
-    pub fn previous_position(&self, f: fn(char) -> bool) -> Option<Position> {
+    pub fn previous_position<F>(&self, f: F) -> Option<Position>
+    where
+        F: Fn(char) -> bool,
+    {
         self.core.previous_position(f)
     }
 
@@ -280,7 +384,7 @@ impl Editor {
     /// assert_eq!(pos, Some(Position::new(1, 0)));
     /// ```
     pub fn next_keyword_position(&self) -> Option<Position> {
-        self.core.next_keyword_position()
+        self.core.next_keyword_position(&self.keyword)
     }
 
     /// Returns a position at the beginning of a previous keyword.
@@ -296,7 +400,7 @@ impl Editor {
     /// assert_eq!(pos, Some(Position::new(0, 0)));
     /// ```
     pub fn previous_keyword_position(&self) -> Option<Position> {
-        self.core.previous_keyword_position()
+        self.core.previous_keyword_position(&self.keyword)
     }
 
     /// Returns a position at the beginning of a next symbol.
@@ -312,7 +416,7 @@ impl Editor {
     /// assert_eq!(pos, Some(Position::new(1, 0)));
     /// ```
     pub fn next_symbol_position(&self) -> Option<Position> {
-        self.core.next_symbol_position()
+        self.core.next_symbol_position(&self.keyword)
     }
 
     /// Returns a position at the beginning of a previous symbol.
@@ -328,7 +432,7 @@ impl Editor {
     /// assert_eq!(pos, Some(Position::new(0, 0)));
     /// ```
     pub fn previous_symbol_position(&self) -> Option<Position> {
-        self.core.previous_symbol_position()
+        self.core.previous_symbol_position(&self.keyword)
     }
 
     /// Returns a position at the end of a next keyword.
@@ -344,7 +448,7 @@ impl Editor {
     /// assert_eq!(pos, Some(Position::new(1, 1)));
     /// ```
     pub fn next_keyword_end_position(&self) -> Option<Position> {
-        self.core.next_keyword_end_position()
+        self.core.next_keyword_end_position(&self.keyword)
     }
 
     /// Returns a position at the end of a previous keyword.
@@ -360,7 +464,7 @@ impl Editor {
     /// assert_eq!(pos, Some(Position::new(0, 1)));
     /// ```
     pub fn previous_keyword_end_position(&self) -> Option<Position> {
-        self.core.previous_keyword_end_position()
+        self.core.previous_keyword_end_position(&self.keyword)
     }
 
     /// Returns a position at the end of a next symbol.
@@ -376,7 +480,7 @@ impl Editor {
     /// assert_eq!(pos, Some(Position::new(1, 1)));
     /// ```
     pub fn next_symbol_end_position(&self) -> Option<Position> {
-        self.core.next_symbol_end_position()
+        self.core.next_symbol_end_position(&self.keyword)
     }
 
     /// Returns a position at the end of a previous symbol.
@@ -392,7 +496,7 @@ impl Editor {
     /// assert_eq!(pos, Some(Position::new(0, 1)));
     /// ```
     pub fn previous_symbol_end_position(&self) -> Option<Position> {
-        self.core.previous_symbol_end_position()
+        self.core.previous_symbol_end_position(&self.keyword)
     }
 
     /// Returns a position just after the end of a next keyword.
@@ -408,7 +512,7 @@ impl Editor {
     /// assert_eq!(pos, Some(Position::new(1, 2)));
     /// ```
     pub fn after_keyword_position(&self) -> Option<Position> {
-        self.core.after_keyword_position()
+        self.core.after_keyword_position(&self.keyword)
     }
 
     /// Returns a position just before a previous keyword.
@@ -424,7 +528,7 @@ impl Editor {
     /// assert_eq!(pos, Some(Position::new(0, 0)));
     /// ```
     pub fn before_keyword_position(&self) -> Option<Position> {
-        self.core.before_keyword_position()
+        self.core.before_keyword_position(&self.keyword)
     }
 
     /// Returns a position just after the end of a next symbol.
@@ -440,7 +544,7 @@ impl Editor {
     /// assert_eq!(pos, Some(Position::new(1, 2)));
     /// ```
     pub fn after_symbol_position(&self) -> Option<Position> {
-        self.core.after_symbol_position()
+        self.core.after_symbol_position(&self.keyword)
     }
 
     /// Returns a position just before a previous symbol.
@@ -456,7 +560,7 @@ impl Editor {
     /// assert_eq!(pos, Some(Position::new(0, 0)));
     /// ```
     pub fn before_symbol_position(&self) -> Option<Position> {
-        self.core.before_symbol_position()
+        self.core.before_symbol_position(&self.keyword)
     }
 
     /// Returns a position at the beginning of a next keyword or symbol.
@@ -475,8 +579,8 @@ impl Editor {
     /// ```
     pub fn next_word_position(&self) -> Option<Position> {
         // TODO: Reduce comparison.
-        let a = self.core.next_keyword_position();
-        let b = self.core.next_symbol_position();
+        let a = self.core.next_keyword_position(&self.keyword);
+        let b = self.core.next_symbol_position(&self.keyword);
         if a.is_none() {
             return b;
         }
@@ -502,8 +606,8 @@ impl Editor {
     /// ```
     pub fn previous_word_position(&self) -> Option<Position> {
         // TODO: Reduce comparison.
-        let a = self.core.previous_keyword_position();
-        let b = self.core.previous_symbol_position();
+        let a = self.core.previous_keyword_position(&self.keyword);
+        let b = self.core.previous_symbol_position(&self.keyword);
         if a.is_none() {
             return b;
         }
@@ -513,6 +617,241 @@ impl Editor {
         if a.unwrap() > b.unwrap() { a } else { b }
     }
 
+    /// Returns the class of characters which make up keywords.
+    pub fn keyword_class(&self) -> &KeywordClass {
+        &self.keyword
+    }
+
+    /// Sets the class of characters which make up keywords, used by keyword,
+    /// symbol and word motions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// use edit::editor::keyword::KeywordClass;
+    /// let mut editor = Editor::new("(set-car! x)", 0, 1).unwrap();
+    /// assert_eq!(editor.next_symbol_position(), Some(Position::new(0, 4)));
+    /// editor.set_keyword_class(KeywordClass::parse("@,48-57,_,-,!").unwrap());
+    /// assert_eq!(editor.next_symbol_position(), Some(Position::new(0, 11)));
+    /// ```
+    pub fn set_keyword_class(&mut self, class: KeywordClass) {
+        self.keyword = class;
+    }
+
+    /// Returns the first or last position of subwords, computed by `at`,
+    /// after or before the cursor.
+    fn subword_position<F>(&self, forward: bool, at: F) -> Option<Position>
+    where
+        F: Fn(&Range<usize>) -> usize,
+    {
+        let cursor = Position::new(self.line(), self.column());
+        let lines: Box<dyn Iterator<Item = usize>> = if forward {
+            Box::new(self.line()..self.line_count())
+        } else {
+            Box::new((0..self.line() + 1).rev())
+        };
+        for line in lines {
+            let ranges = subwords(self.line_buffer(line).unwrap(), &self.keyword);
+            let mut ps = ranges.iter().map(|r| Position::new(line, at(r)));
+            let found = if forward {
+                ps.find(|&p| p > cursor)
+            } else {
+                ps.filter(|&p| p < cursor).last()
+            };
+            if found.is_some() {
+                return found;
+            }
+        }
+        None
+    }
+
+    /// Returns a position at the beginning of a next subword.
+    ///
+    /// Subwords are parts of keywords split at case changes and at
+    /// non-alphanumeric keyword characters like `_`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// let editor = Editor::new("parseHTTPResponse", 0, 1).unwrap();
+    /// assert_eq!(editor.next_subword_position(), Some(Position::new(0, 5)));
+    /// ```
+    pub fn next_subword_position(&self) -> Option<Position> {
+        self.subword_position(true, |r| r.start)
+    }
+
+    /// Returns a position at the beginning of a previous subword.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// let editor = Editor::new("snake_case_name", 0, 11).unwrap();
+    /// assert_eq!(editor.previous_subword_position(), Some(Position::new(0, 6)));
+    /// ```
+    pub fn previous_subword_position(&self) -> Option<Position> {
+        self.subword_position(false, |r| r.start)
+    }
+
+    /// Returns a position at the end of a next subword.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// let editor = Editor::new("parseHTTPResponse", 0, 4).unwrap();
+    /// assert_eq!(editor.next_subword_end_position(), Some(Position::new(0, 8)));
+    /// ```
+    pub fn next_subword_end_position(&self) -> Option<Position> {
+        self.subword_position(true, |r| r.end - 1)
+    }
+
+    /// Returns a position at the end of a previous subword.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// let editor = Editor::new("snake_case\nname", 1, 2).unwrap();
+    /// assert_eq!(editor.previous_subword_end_position(), Some(Position::new(0, 9)));
+    /// ```
+    pub fn previous_subword_end_position(&self) -> Option<Position> {
+        self.subword_position(false, |r| r.end - 1)
+    }
+
+    /// Returns words after which `.` does not end a sentence, like `Mr`.
+    pub fn abbreviations(&self) -> &[String] {
+        &self.abbreviations
+    }
+
+    /// Sets words after which `.` does not end a sentence.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// let mut editor = Editor::new("Ask Dr. Who. Now", 0, 0).unwrap();
+    /// assert_eq!(editor.next_sentence_position(), Some(Position::new(0, 8)));
+    /// editor.set_abbreviations(vec!["Dr".to_string()]);
+    /// assert_eq!(editor.next_sentence_position(), Some(Position::new(0, 13)));
+    /// ```
+    pub fn set_abbreviations(&mut self, abbreviations: Vec<String>) {
+        self.abbreviations = abbreviations;
+    }
+
+    /// Returns a position at the beginning of a next sentence.
+    ///
+    /// A sentence ends with `.`, `!` or `?` followed by whitespace, or at a blank line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// let editor = Editor::new("One. Two!\n\
+    ///                           Three", 0, 1).unwrap();
+    /// assert_eq!(editor.next_sentence_position(), Some(Position::new(0, 5)));
+    /// ```
+    pub fn next_sentence_position(&self) -> Option<Position> {
+        let n = self.core.current_offset();
+        sentence_starts(self.buffer(), &self.abbreviations)
+            .into_iter()
+            .find(|&i| i > n)
+            .and_then(|i| self.position_at(i))
+    }
+
+    /// Returns a position at the beginning of a previous sentence, which
+    /// may be the one the cursor is in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// let editor = Editor::new("One. Two!\n\
+    ///                           Three", 1, 2).unwrap();
+    /// assert_eq!(editor.previous_sentence_position(), Some(Position::new(1, 0)));
+    /// ```
+    pub fn previous_sentence_position(&self) -> Option<Position> {
+        let n = self.core.current_offset();
+        sentence_starts(self.buffer(), &self.abbreviations)
+            .into_iter()
+            .rev()
+            .find(|&i| i < n)
+            .and_then(|i| self.position_at(i))
+    }
+
+    fn is_blank_line(&self, line: usize) -> bool {
+        is_blank(self.line_buffer(line).unwrap())
+    }
+
+    /// Returns a position at the beginning of the blank line after a paragraph,
+    /// or at the end of the buffer if the paragraph is the last one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// let editor = Editor::new("a\nb\n\n\nc", 0, 0).unwrap();
+    /// assert_eq!(editor.next_paragraph_position(), Some(Position::new(2, 0)));
+    ///
+    /// let editor = Editor::new("a\nb\n\n\nc", 2, 0).unwrap();
+    /// assert_eq!(editor.next_paragraph_position(), Some(Position::new(4, 1)));
+    /// ```
+    pub fn next_paragraph_position(&self) -> Option<Position> {
+        let count = self.line_count();
+        let mut i = self.line();
+        while i < count && self.is_blank_line(i) {
+            i += 1;
+        }
+        while i < count && !self.is_blank_line(i) {
+            i += 1;
+        }
+        if i < count {
+            return Some(Position::new(i, 0));
+        }
+        let end = Position::new(count - 1, self.line_width(count - 1).unwrap());
+        (end > Position::new(self.line(), self.column())).then_some(end)
+    }
+
+    /// Returns a position at the beginning of the blank line before a paragraph,
+    /// or at the beginning of the buffer if the paragraph is the first one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// let editor = Editor::new("a\n\nb\nc", 3, 0).unwrap();
+    /// assert_eq!(editor.previous_paragraph_position(), Some(Position::new(1, 0)));
+    ///
+    /// let editor = Editor::new("a\n\nb\nc", 1, 0).unwrap();
+    /// assert_eq!(editor.previous_paragraph_position(), Some(Position::new(0, 0)));
+    /// ```
+    pub fn previous_paragraph_position(&self) -> Option<Position> {
+        let mut i = self.line();
+        while i > 0 && self.is_blank_line(i) {
+            i -= 1;
+        }
+        while i > 0 && !self.is_blank_line(i) {
+            i -= 1;
+        }
+        if i < self.line() && self.is_blank_line(i) {
+            return Some(Position::new(i, 0));
+        }
+        let beginning = Position::new(0, 0);
+        (beginning < Position::new(self.line(), self.column())).then_some(beginning)
+    }
+
     /// Searches for a character after the cursor in the current line, returning its index.
     ///
     /// # Examples
@@ -549,6 +888,127 @@ impl Editor {
         line[..self.column()].iter().rposition(|&x| x == ch)
     }
 
+    /// Returns whether character searches go beyond the current line.
+    pub fn find_multiline(&self) -> bool {
+        self.find_multiline
+    }
+
+    /// Sets whether character searches go beyond the current line.
+    pub fn set_find_multiline(&mut self, multiline: bool) {
+        self.find_multiline = multiline;
+    }
+
+    /// Returns the last character search made by `find_character` or `delete_to_character`.
+    pub fn last_find(&self) -> Option<CharSearch> {
+        self.last_find
+    }
+
+    fn character_offset(&self, search: CharSearch, count: usize, repeat: bool) -> Option<usize> {
+        let n = self.core.current_offset();
+        let (start, end) = if self.find_multiline {
+            (0, self.buffer().len())
+        } else {
+            let start = n - self.column();
+            (start, start + self.current_line_buffer().len())
+        };
+        find_char(&self.buffer()[start..end], n - start, search, count, repeat).map(|i| i + start)
+    }
+
+    /// Returns the position a character search for the `count`-th occurrence moves to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// use edit::editor::find::CharSearch;
+    /// let mut editor = Editor::new("a, b, c\n\
+    ///                               d, e", 0, 0).unwrap();
+    /// let p = editor.character_position(CharSearch::till_forward(','), 2);
+    /// assert_eq!(p, Some(Position::new(0, 3)));
+    /// assert_eq!(editor.character_position(CharSearch::find_forward(','), 3), None);
+    ///
+    /// editor.set_find_multiline(true);
+    /// let p = editor.character_position(CharSearch::find_forward(','), 3);
+    /// assert_eq!(p, Some(Position::new(1, 1)));
+    /// ```
+    pub fn character_position(&self, search: CharSearch, count: usize) -> Option<Position> {
+        self.character_offset(search, count, false)
+            .and_then(|i| self.position_at(i))
+    }
+
+    fn move_to_offset(&mut self, offset: Option<usize>) -> bool {
+        match offset.and_then(|i| self.position_at(i)) {
+            Some(p) => {
+                self.set_line(p.line);
+                self.set_column(p.column);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves a cursor by a character search for the `count`-th occurrence,
+    /// remembering the search for `repeat_find`.
+    ///
+    /// Returns `false` without moving if there are fewer occurrences.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::find::CharSearch;
+    /// let mut editor = Editor::new("a.b.c.d", 0, 0).unwrap();
+    /// assert!(editor.find_character(CharSearch::find_forward('.'), 2));
+    /// assert_eq!(editor.column(), 3);
+    /// assert!(!editor.find_character(CharSearch::find_forward('.'), 2));
+    /// assert_eq!(editor.column(), 3);
+    /// ```
+    pub fn find_character(&mut self, search: CharSearch, count: usize) -> bool {
+        self.last_find = Some(search);
+        let offset = self.character_offset(search, count, false);
+        self.move_to_offset(offset)
+    }
+
+    /// Repeats the last character search (`;`), returning `false` if the
+    /// cursor does not move.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::find::CharSearch;
+    /// let mut editor = Editor::new("a.b.c.d", 0, 0).unwrap();
+    /// editor.find_character(CharSearch::till_forward('.'), 1);
+    /// assert_eq!(editor.column(), 0);
+    /// assert!(editor.repeat_find(1));
+    /// assert_eq!(editor.column(), 2);
+    /// assert!(editor.repeat_find(1));
+    /// assert_eq!(editor.column(), 4);
+    /// assert!(editor.repeat_find_reversed(1));
+    /// assert_eq!(editor.column(), 2);
+    /// ```
+    pub fn repeat_find(&mut self, count: usize) -> bool {
+        match self.last_find {
+            Some(search) => {
+                let offset = self.character_offset(search, count, true);
+                self.move_to_offset(offset)
+            }
+            None => false,
+        }
+    }
+
+    /// Repeats the last character search in the opposite direction (`,`).
+    pub fn repeat_find_reversed(&mut self, count: usize) -> bool {
+        match self.last_find {
+            Some(search) => {
+                let offset = self.character_offset(search.reversed(), count, true);
+                self.move_to_offset(offset)
+            }
+            None => false,
+        }
+    }
+
     /// Match parentheses.
     ///
     /// # Examples
@@ -570,11 +1030,112 @@ impl Editor {
     /// let editor = Editor::new("a ( (b) ) c", 0, 8).unwrap();
     /// assert_eq!(editor.match_paren(), Some(2));
     /// ```
+    ///
+    /// Parentheses in strings and comments are ignored if the buffer is
+    /// highlighted by `set_grammar`.
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::highlight::rust;
+    /// let mut editor = Editor::new("f(\")\", ')')", 0, 1).unwrap();
+    /// assert_eq!(editor.match_paren(), Some(3));
+    /// editor.set_grammar(rust());
+    /// assert_eq!(editor.match_paren(), Some(10));
+    /// ```
     pub fn match_paren(&self) -> Option<usize> {
-        self.match_pair(Paren {
-            open: '(',
-            close: ')',
-        })
+        let n = self.core.current_offset();
+        match self.buffer().get(n) {
+            Some(&'(') | Some(&')') => self.brackets.matching(n),
+            _ => None,
+        }
+    }
+
+    /// Matches a bracket of `()`, `[]` or `{}` at the cursor, returning the offset of its partner.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let editor = Editor::new("a[{b}]", 0, 2).unwrap();
+    /// assert_eq!(editor.match_bracket(), Some(4));
+    /// ```
+    pub fn match_bracket(&self) -> Option<usize> {
+        self.brackets.matching(self.core.current_offset())
+    }
+
+    /// Returns positions of the innermost pair of brackets containing a position,
+    /// where a bracket belongs to its own pair.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// let editor = Editor::new("f(a,\n  [b]) c", 0, 0).unwrap();
+    /// assert_eq!(
+    ///     editor.enclosing_brackets(Position::new(1, 0)),
+    ///     Some((Position::new(0, 1), Position::new(1, 5)))
+    /// );
+    /// assert_eq!(editor.enclosing_brackets(Position::new(1, 7)), None);
+    /// ```
+    pub fn enclosing_brackets(&self, p: Position) -> Option<(Position, Position)> {
+        let (open, close) = self.brackets.enclosing(self.offset_position(p)?)?;
+        Some((self.position_at(open)?, self.position_at(close)?))
+    }
+
+    /// Returns positions of brackets which have no partner.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// let editor = Editor::new("(a]\n{", 0, 0).unwrap();
+    /// assert_eq!(
+    ///     editor.unmatched_brackets(),
+    ///     vec![Position::new(0, 0), Position::new(0, 2), Position::new(1, 0)]
+    /// );
+    /// ```
+    pub fn unmatched_brackets(&self) -> Vec<Position> {
+        self.brackets
+            .unmatched()
+            .into_iter()
+            .filter_map(|n| self.position_at(n))
+            .collect()
+    }
+
+    /// Updates state tracking offsets for `removed` characters at `offset` replaced with `inserted` ones.
+    fn spliced(&mut self, offset: usize, removed: &[char], inserted: &[char]) {
+        self.brackets_edited(offset, removed, inserted);
+        self.snippet_edited(offset, removed.len(), inserted.len());
+    }
+
+    fn brackets_edited(&mut self, offset: usize, removed: &[char], inserted: &[char]) {
+        // Inserted brackets to skip are found when the lines are highlighted again.
+        self.brackets.edit(self.core.buffer(), offset, removed.len(), inserted.len(), |_| false);
+    }
+
+    /// Reads brackets of lines in a range again, skipping ones in strings and comments.
+    fn reindex_brackets(&mut self, lines: Range<usize>) {
+        let len = self.buffer().len();
+        let offset = |l: usize| self.core.offset(l, 0).unwrap_or(len);
+        let (start, end) = (offset(lines.start), offset(lines.end));
+        let mut skip = vec![false; end - start];
+        if let Some(ref h) = self.highlighter {
+            for l in lines {
+                let at = offset(l) - start;
+                let width = self.line_width(l).unwrap_or(0);
+                for span in h.spans(l).unwrap_or(&[]) {
+                    if matches!(span.style, Style::String | Style::Escape | Style::Comment) {
+                        let (s, e) = (span.start.min(width), span.end.min(width));
+                        for b in &mut skip[at + s..at + e] {
+                            *b = true;
+                        }
+                    }
+                }
+            }
+        }
+        self.brackets.reindex(self.core.buffer(), start..end, |i| skip[i - start]);
     }
 
     /// Matches a pair of parentheses.
@@ -592,11 +1153,33 @@ impl Editor {
     ///
     /// let editor = Editor::new(" [1, 3) ", 0, 1).unwrap();
     /// assert_eq!(editor.match_pair(Paren {open: '[', close: ')'}), Some(6));
+    ///
+    /// let editor = Editor::new("(a)", 0, 3).unwrap();
+    /// assert_eq!(editor.match_pair(Paren {open: '(', close: ')'}), None);
+    /// ```
+    ///
+    /// Pairs of `()`, `[]` and `{}` are matched like `match_bracket`, so
+    /// ones in strings and comments are ignored if the buffer is highlighted.
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Paren;
+    /// use edit::editor::highlight::rust;
+    /// let mut editor = Editor::new("{ \"}\" }", 0, 0).unwrap();
+    /// editor.set_grammar(rust());
+    /// assert_eq!(editor.match_pair(Paren {open: '{', close: '}'}), Some(6));
     /// ```
     pub fn match_pair(&self, p: Paren) -> Option<usize> {
         let n = self.core.current_offset();
         let mut level: usize = 0;
-        let x = self.buffer()[n];
+        let x = *self.buffer().get(n)?;
+        if bracket_kind(p.open) == Some((p.close, true)) {
+            return if x == p.open || x == p.close {
+                self.brackets.matching(n)
+            } else {
+                None
+            };
+        }
         if x == p.open {
             self.buffer()[n + 1..]
                 .iter()
@@ -635,7 +1218,11 @@ impl Editor {
         }
     }
 
-    /// Matches quotes.
+    /// Matches quotes, returning the offset of the partner of the quote at the cursor.
+    ///
+    /// Quotes are paired from the beginning of the current line, or of the
+    /// buffer if the scope is `QuoteScope::Buffer`, so the cursor may be on
+    /// either quote. Escaped quotes and raw strings are taken into account.
     ///
     /// # Examples
     ///
@@ -649,40 +1236,373 @@ impl Editor {
     ///
     /// let editor = Editor::new("a \" b  c", 0, 2).unwrap();
     /// assert_eq!(editor.match_quote('"'), None);
+    ///
+    /// let editor = Editor::new("x = \"a \\\" b\";", 0, 11).unwrap();
+    /// assert_eq!(editor.match_quote('"'), Some(4));
+    ///
+    /// let editor = Editor::new("r#\"a \" b\"#", 0, 2).unwrap();
+    /// assert_eq!(editor.match_quote('"'), Some(8));
+    /// ```
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::quote::QuoteScope;
+    /// let mut editor = Editor::new("\"a\nb\" \"c\"", 1, 1).unwrap();
+    /// assert_eq!(editor.match_quote('"'), Some(6));
+    /// editor.set_quote_scope(QuoteScope::Buffer);
+    /// assert_eq!(editor.match_quote('"'), Some(0));
     /// ```
     pub fn match_quote(&self, q: char) -> Option<usize> {
         let n = self.core.current_offset();
-        let x = self.buffer()[n];
-        if x != q {
+        if self.buffer().get(n) != Some(&q) {
             return None;
         }
-        self.buffer()[n + 1..].iter().position(|&c| c == q).map(
-            |i| {
-                i + n + 1
-            },
-        )
+        let start = match self.quote_scope {
+            QuoteScope::Line => self.offset_position(Position::new(self.line(), 0)).unwrap(),
+            QuoteScope::Buffer => 0,
+        };
+        let end = match self.quote_scope {
+            QuoteScope::Line => start + self.current_line_buffer().len(),
+            QuoteScope::Buffer => self.buffer().len(),
+        };
+        quote_pairs(&self.buffer()[start..end], q)
+            .into_iter()
+            .map(|(a, b)| (a + start, b + start))
+            .find(|&(a, b)| a == n || b == n)
+            .map(|(a, b)| if a == n { b } else { a })
     }
 
-    /// Returns character offset of a position.
+    /// Returns where quotes are paired from.
+    pub fn quote_scope(&self) -> QuoteScope {
+        self.quote_scope
+    }
+
+    /// Sets where quotes are paired from.
+    pub fn set_quote_scope(&mut self, scope: QuoteScope) {
+        self.quote_scope = scope;
+    }
+
+    /// Returns candidates which complete the text before the cursor, the closest first.
     ///
     /// # Examples
     ///
     /// ```
     /// # use edit::editor::Editor;
-    /// use edit::editor::Position;
-    /// let editor = Editor::new(
-    ///     "aa\n\
-    ///      abb\n\
-    ///      bbc",
-    ///     0,
-    ///     0,
-    /// ).unwrap();
-    /// assert_eq!(editor.offset_position(Position::new(2, 1)), Some(8));
+    /// use edit::editor::complete::CompletionKind;
+    /// let editor = Editor::new("alpha\n\
+    ///                           al\n\
+    ///                           also", 1, 2).unwrap();
+    /// let candidates = editor.completion_candidates(CompletionKind::Keyword);
+    /// assert_eq!(candidates, vec!["also", "alpha"]);
+    /// ```
+    pub fn completion_candidates(&self, kind: CompletionKind) -> Vec<String> {
+        match kind {
+            CompletionKind::Keyword => {
+                keyword_candidates(self.buffer(), self.core.current_offset(), &self.keyword)
+            }
+            CompletionKind::Line => {
+                let lines: Vec<&[char]> = (0..self.line_count())
+                    .map(|i| self.line_buffer(i).unwrap())
+                    .collect();
+                line_candidates(&lines, self.line(), self.column())
+            }
+        }
+    }
+
+    /// Completes the text before the cursor, returning the text put in the buffer.
+    ///
+    /// The first call replaces the text with the closest candidate, or the
+    /// farthest one if `forward` is `false`, as if going back from the typed
+    /// text. Calling it again right after, with `forward` either way,
+    /// replaces the shown candidate with the next or previous one, and the
+    /// typed text follows the last candidate. Returns `None` if there is no
+    /// candidate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::complete::CompletionKind;
+    /// let mut editor = Editor::new("fold fork f", 0, 11).unwrap();
+    /// assert_eq!(editor.complete(CompletionKind::Keyword, true), Some("fork".to_string()));
+    /// assert_eq!(editor.buffer_as_str(), "fold fork fork");
+    /// assert_eq!(editor.column(), 14);
+    /// assert_eq!(editor.complete(CompletionKind::Keyword, true), Some("fold".to_string()));
+    /// assert_eq!(editor.complete(CompletionKind::Keyword, true), Some("f".to_string()));
+    /// assert_eq!(editor.complete(CompletionKind::Keyword, false), Some("fold".to_string()));
+    /// assert_eq!(editor.buffer_as_str(), "fold fork fold");
+    /// ```
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::complete::CompletionKind;
+    /// let mut editor = Editor::new("fold fork f", 0, 11).unwrap();
+    /// assert_eq!(editor.complete(CompletionKind::Keyword, false), Some("fold".to_string()));
+    /// assert_eq!(editor.complete(CompletionKind::Keyword, false), Some("fork".to_string()));
+    /// assert_eq!(editor.complete(CompletionKind::Keyword, false), Some("f".to_string()));
+    /// assert_eq!(editor.buffer_as_str(), "fold fork f");
+    /// ```
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::complete::CompletionKind;
+    /// let mut editor = Editor::new("    return x;\n\
+    ///                               \n\
+    ///                               \tre", 2, 3).unwrap();
+    /// editor.complete(CompletionKind::Line, true);
+    /// assert_eq!(editor.buffer_as_str(), "    return x;\n\n\treturn x;");
+    /// ```
+    pub fn complete(&mut self, kind: CompletionKind, forward: bool) -> Option<String> {
+        let cursor = Position::new(self.line(), self.column());
+        // Completion goes on only if the buffer still shows the last candidate before the cursor.
+        let active = self.completion.take().filter(|c| {
+            let end = Position::new(c.start.line, c.start.column + c.shown().chars().count());
+            c.kind == kind && end == cursor && {
+                let shown = self.buffer_range(c.start..cursor).unwrap();
+                shown.iter().cloned().eq(c.shown().chars())
+            }
+        });
+        let completion = match active {
+            Some(mut c) => {
+                c.cycle(forward);
+                c
+            }
+            None => {
+                let candidates = self.completion_candidates(kind);
+                if candidates.is_empty() {
+                    return None;
+                }
+                let start = match kind {
+                    CompletionKind::Keyword => {
+                        let n = self.core.current_offset();
+                        let prefix = keyword_prefix(self.buffer(), n, &self.keyword);
+                        Position::new(cursor.line, cursor.column - prefix.len())
+                    }
+                    CompletionKind::Line => {
+                        let line = self.current_line_buffer();
+                        let n = line[..cursor.column]
+                            .iter()
+                            .take_while(|ch| ch.is_whitespace())
+                            .count();
+                        Position::new(cursor.line, n)
+                    }
+                };
+                Completion {
+                    kind,
+                    start,
+                    typed: self.buffer_range(start..cursor).unwrap().iter().collect(),
+                    index: if forward { 0 } else { candidates.len() - 1 },
+                    candidates,
+                }
+            }
+        };
+        let text = completion.shown().to_string();
+        self.replace(&text, completion.start..cursor);
+        self.set_line(completion.start.line);
+        self.set_column(completion.start.column + text.chars().count());
+        self.completion = Some(completion);
+        Some(text)
+    }
+
+    /// Inserts a snippet at a position, indenting its lines like the line
+    /// there, and selects its first tabstop.
+    ///
+    /// Returns the range of the selected tabstop. The tabstops follow edits
+    /// of the buffer, and mirrors of the selected one are updated as it is
+    /// edited.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// use edit::editor::snippet::Snippet;
+    /// let mut editor = Editor::new("  x", 0, 2).unwrap();
+    /// let snippet = Snippet::parse("let ${1:a} = $2;\n$1$0").unwrap();
+    /// let range = editor.insert_snippet(&snippet, 0, 2);
+    /// assert_eq!(range, Some(Position::new(0, 6)..Position::new(0, 7)));
+    /// assert_eq!(editor.buffer_as_str(), "  let a = ;\n  ax");
+    ///
+    /// editor.replace("bc", Position::new(0, 6)..Position::new(0, 7));
+    /// assert_eq!(editor.buffer_as_str(), "  let bc = ;\n  bcx");
+    /// assert_eq!(editor.next_tabstop(), Some(Position::new(0, 11)..Position::new(0, 11)));
+    /// assert_eq!(editor.next_tabstop(), Some(Position::new(1, 4)..Position::new(1, 4)));
+    /// assert_eq!(editor.next_tabstop(), None);
+    /// ```
+    pub fn insert_snippet(
+        &mut self,
+        snippet: &Snippet,
+        line: usize,
+        column: usize,
+    ) -> Option<Range<Position>> {
+        let offset = self.core.offset(line, column)?;
+        let indent: String = self.line_buffer(line)
+            .unwrap()
+            .iter()
+            .take_while(|ch| ch.is_whitespace())
+            .collect();
+        let snippet = snippet.indented(&indent);
+        self.snippet = None;
+        self.insert_string_at(snippet.text(), line, column);
+        self.snippet = Some(SnippetSession::new(&snippet, offset));
+        self.select_tabstop()
+    }
+
+    /// Moves the cursor to the current tabstop, ending the snippet at `$0`.
+    fn select_tabstop(&mut self) -> Option<Range<Position>> {
+        let (range, last) = {
+            let s = self.snippet.as_ref()?;
+            (s.tabstop().ranges[0].clone(), s.current + 1 == s.tabstops.len())
+        };
+        if last {
+            self.snippet = None;
+        }
+        let start = self.position_at(range.start).unwrap();
+        self.set_line(start.line);
+        self.set_column(start.column);
+        Some(start..self.position_at(range.end).unwrap())
+    }
+
+    /// Selects the next tabstop of the snippet, returning its range, or
+    /// `None` if no snippet is active.
+    ///
+    /// The snippet ends when `$0` is selected.
+    pub fn next_tabstop(&mut self) -> Option<Range<Position>> {
+        self.snippet.as_mut()?.current += 1;
+        self.select_tabstop()
+    }
+
+    /// Selects the previous tabstop of the snippet, returning its range, or
+    /// `None` if the first one is selected or no snippet is active.
+    pub fn previous_tabstop(&mut self) -> Option<Range<Position>> {
+        let s = self.snippet.as_mut()?;
+        if s.current == 0 {
+            return None;
+        }
+        s.current -= 1;
+        self.select_tabstop()
+    }
+
+    /// Returns ranges of the selected tabstop, the first one followed by its mirrors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// use edit::editor::snippet::Snippet;
+    /// let mut editor = Editor::new("", 0, 0).unwrap();
+    /// editor.insert_snippet(&Snippet::parse("${1|x,y|}+$1").unwrap(), 0, 0);
+    /// assert_eq!(
+    ///     editor.tabstop_ranges(),
+    ///     vec![Position::new(0, 0)..Position::new(0, 1), Position::new(0, 2)..Position::new(0, 3)]
+    /// );
+    /// assert_eq!(editor.tabstop_choices(), &["x", "y"]);
+    /// ```
+    pub fn tabstop_ranges(&self) -> Vec<Range<Position>> {
+        let s = match self.snippet {
+            Some(ref s) => s,
+            None => return Vec::new(),
+        };
+        s.tabstop()
+            .ranges
+            .iter()
+            .map(|r| self.position_at(r.start).unwrap()..self.position_at(r.end).unwrap())
+            .collect()
+    }
+
+    /// Returns choices of the selected tabstop, if any.
+    pub fn tabstop_choices(&self) -> &[String] {
+        self.snippet.as_ref().map_or(&[], |s| &s.tabstop().choices[..])
+    }
+
+    /// Stops tracking tabstops of the snippet.
+    pub fn end_snippet(&mut self) {
+        self.snippet = None;
+    }
+
+    fn snippet_edited(&mut self, offset: usize, removed: usize, inserted: usize) {
+        let mirror = match self.snippet {
+            Some(ref mut s) => {
+                s.edit(offset, removed, inserted);
+                let ranges = &s.tabstop().ranges;
+                s.mirroring.is_none() && ranges.len() > 1 && ranges[0].start <= offset
+                    && offset + inserted <= ranges[0].end
+            }
+            None => false,
+        };
+        if mirror {
+            self.mirror_tabstop();
+        }
+    }
+
+    /// Copies the first range of the selected tabstop to its mirrors.
+    fn mirror_tabstop(&mut self) {
+        let (t, ranges) = {
+            let s = self.snippet.as_ref().unwrap();
+            (s.current, s.tabstop().ranges.len())
+        };
+        let first = self.snippet.as_ref().unwrap().tabstop().ranges[0].clone();
+        let text: String = self.buffer()[first].iter().collect();
+        let cursor = Position::new(self.line(), self.column());
+        for r in 1..ranges {
+            let range = self.snippet.as_ref().unwrap().tabstops[t].ranges[r].clone();
+            if self.buffer()[range.clone()].iter().cloned().eq(text.chars()) {
+                continue;
+            }
+            let start = self.position_at(range.start).unwrap();
+            let end = self.position_at(range.end).unwrap();
+            self.snippet.as_mut().unwrap().mirroring = Some((t, r));
+            self.replace(&text, start..end);
+            self.snippet.as_mut().unwrap().mirroring = None;
+        }
+        self.set_line(cursor.line);
+        self.set_column(cursor.column);
+    }
+
+    /// Returns character offset of a position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// let editor = Editor::new(
+    ///     "aa\n\
+    ///      abb\n\
+    ///      bbc",
+    ///     0,
+    ///     0,
+    /// ).unwrap();
+    /// assert_eq!(editor.offset_position(Position::new(2, 1)), Some(8));
     /// ```
     pub fn offset_position(&self, p: Position) -> Option<usize> {
         self.core.offset(p.line, p.column)
     }
 
+    /// Returns the position of a character offset.
+    ///
+    /// Returns `None` if `n` is out of the range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// let editor = Editor::new(
+    ///     "aa\n\
+    ///      abb\n\
+    ///      bbc",
+    ///     0,
+    ///     0,
+    /// ).unwrap();
+    /// assert_eq!(editor.position_at(8), Some(Position::new(2, 1)));
+    /// assert_eq!(editor.position_at(100), None);
+    /// ```
+    pub fn position_at(&self, n: usize) -> Option<Position> {
+        self.core.position(n)
+    }
+
     /// Moves a cursor by `n` characters rightward.
     ///
     /// If the cursor will be out of the range, it is moved to the rightmost column.
@@ -736,8 +1656,23 @@ impl Editor {
     /// editor.move_up(1000);
     /// assert_eq!(editor.line(), 0);
     /// ```
+    ///
+    /// A closed fold counts as one line.
     pub fn move_up(&mut self, n: usize) {
-        self.core.move_up(n);
+        if !self.has_closed_fold() {
+            self.core.move_up(n);
+            return;
+        }
+        let mut line = self.line();
+        for _ in 0..n {
+            line = self.folds.closed_fold_at(line).map_or(line, |(s, _)| s);
+            if line == 0 {
+                break;
+            }
+            line -= 1;
+        }
+        line = self.folds.closed_fold_at(line).map_or(line, |(s, _)| s);
+        self.set_line(line);
     }
 
     /// Moves a cursor by `n` lines downward.
@@ -755,8 +1690,34 @@ impl Editor {
     /// editor.move_down(1000);
     /// assert_eq!(editor.line(), 3);
     /// ```
+    ///
+    /// A closed fold counts as one line.
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("0\n1\n2\n3\n4", 0, 0).unwrap();
+    /// editor.create_fold(1..3);
+    /// editor.close_fold(1);
+    /// editor.move_down(2);
+    /// assert_eq!(editor.line(), 3);
+    /// editor.move_up(1);
+    /// assert_eq!(editor.line(), 1);
+    /// ```
     pub fn move_down(&mut self, n: usize) {
-        self.core.move_down(n);
+        if !self.has_closed_fold() {
+            self.core.move_down(n);
+            return;
+        }
+        let mut line = self.line();
+        for _ in 0..n {
+            line = self.folds.closed_fold_at(line).map_or(line, |(_, e)| e);
+            if line + 1 >= self.line_count() {
+                break;
+            }
+            line += 1;
+        }
+        line = self.folds.closed_fold_at(line).map_or(line, |(s, _)| s);
+        self.set_line(line);
     }
 
     /// Moves a cursor to the beginning of the current line.
@@ -1015,159 +1976,701 @@ impl Editor {
         self.move_up(1);
     }
 
-    /// Join a line and next line.
+    /// Returns the folds of the buffer.
+    pub fn folds(&self) -> &Folds {
+        &self.folds
+    }
+
+    /// Creates an open fold of lines in a range.
+    ///
+    /// Returns `false` if the fold would cross an existing one.
     ///
     /// # Examples
     ///
     /// ```
     /// # use edit::editor::Editor;
-    /// let mut editor = Editor::new("a b c d\n\
-    ///                           x", 0, 3).unwrap();
-    /// editor.join(0);
-    /// assert_eq!(editor.buffer_as_str(), "a b c d x");
+    /// let mut editor = Editor::new("a\nb\nc\nd", 0, 0).unwrap();
+    /// assert!(editor.create_fold(0..3));
+    /// assert!(editor.create_fold(1..3));
+    /// assert!(!editor.create_fold(2..4));
+    ///
+    /// editor.insert_at('\n', 0, 0);
+    /// editor.delete_line(2);
+    /// assert!(editor.close_fold(1));
+    /// assert_eq!(editor.closed_fold_at(2), Some(1..3));
     /// ```
-    pub fn join(&mut self, line: usize) {
-        let c = self.line_width(line).expect(
-            &format!("line {}: out of range", line),
-        );
-        self.replace_char(' ', line, c);
+    pub fn create_fold(&mut self, range: Range<usize>) -> bool {
+        range.end > range.start && range.end <= self.line_count() &&
+            self.folds.create(range.start, range.end - 1)
     }
 
-    /// Inserts a character into the buffer at a character position.
-    ///
-    /// If a position is out of the range, nothing happens.
+    /// Replaces the folds with ones computed from indentation.
     ///
     /// # Examples
     ///
     /// ```
     /// # use edit::editor::Editor;
-    /// let mut editor = Editor::new("insert on character", 0, 0).unwrap();
-    /// editor.insert_at('e', 0, 9);
-    ///
-    /// assert_eq!(editor.buffer_as_str(), "insert one character");
+    /// let mut editor = Editor::new("fn f() {\n    a\n\n    b\n}", 0, 0).unwrap();
+    /// editor.fold_by_indent();
+    /// editor.close_fold(2);
+    /// assert_eq!(editor.closed_fold_at(3), Some(1..4));
     /// ```
-    pub fn insert_at(&mut self, ch: char, line: usize, column: usize) {
-        self.core.insert_at(ch, line, column);
+    pub fn fold_by_indent(&mut self) {
+        let widths: Vec<Option<usize>> = (0..self.line_count())
+            .map(|l| {
+                let b = self.line_buffer(l).unwrap();
+                if indent_len(b) == b.len() {
+                    None
+                } else {
+                    self.line_indent(l)
+                }
+            })
+            .collect();
+        let folds = indent_folds(&widths, self.indent.shiftwidth);
+        self.folds.set(folds);
     }
 
-    /// Inserts a string into the buffer at a character position.
-    ///
-    /// If a position is out of the range, nothing happens.
+    /// Replaces the folds with ones delimited by `{{{` and `}}}` markers.
     ///
     /// # Examples
     ///
     /// ```
     /// # use edit::editor::Editor;
-    /// let mut editor = Editor::new("insert", 0, 0).unwrap();
-    /// editor.insert_string_at(" string", 0, 6);
-    ///
-    /// assert_eq!(editor.buffer_as_str(), "insert string");
+    /// let mut editor = Editor::new("a {{{\nb\n}}}\nc", 0, 0).unwrap();
+    /// editor.fold_by_marker();
+    /// assert!(editor.toggle_fold(1));
+    /// assert_eq!(editor.closed_fold_at(1), Some(0..3));
     /// ```
-    pub fn insert_string_at(&mut self, s: &str, line: usize, column: usize) {
-        self.core.insert_string_at(s, line, column);
+    pub fn fold_by_marker(&mut self) {
+        let folds = marker_folds((0..self.line_count()).map(|l| self.line_buffer(l).unwrap()));
+        self.folds.set(folds);
     }
 
-    /// Deletes a `char` from the buffer at a character position.
+    fn has_closed_fold(&self) -> bool {
+        self.folds.folds().iter().any(|f| f.closed)
+    }
+
+    /// Returns lines of the closed fold which is shown at a line as one line.
+    pub fn closed_fold_at(&self, line: usize) -> Option<Range<usize>> {
+        self.folds.closed_fold_at(line).map(|(s, e)| s..e + 1)
+    }
+
+    /// Opens the closed fold shown at a line, returning `false` if there is none.
+    pub fn open_fold(&mut self, line: usize) -> bool {
+        self.folds.open(line)
+    }
+
+    /// Closes the innermost open fold containing a line, returning `false` if there is none.
+    pub fn close_fold(&mut self, line: usize) -> bool {
+        self.folds.close(line)
+    }
+
+    /// Opens the fold shown at a line, or closes a fold containing it.
+    pub fn toggle_fold(&mut self, line: usize) -> bool {
+        self.folds.toggle(line)
+    }
+
+    /// Deletes the innermost fold containing a line, returning `false` if there is none.
+    pub fn delete_fold(&mut self, line: usize) -> bool {
+        self.folds.delete(line)
+    }
+
+    /// Updates state which follows lines after an edit at `line`, which
+    /// inserted `inserted` line breaks and removed `removed` ones.
+    ///
+    /// If `split` is set, inserted lines follow `line`.
+    fn edited(&mut self, line: usize, inserted: usize, removed: usize, split: bool) {
+        self.folds.insert_lines(line, inserted, split);
+        self.folds.delete_lines(line, removed);
+        if let Some(ref mut h) = self.highlighter {
+            h.delete_lines(line, removed);
+            h.insert_lines(line, inserted);
+        }
+        self.rehighlight();
+    }
+
+    fn rehighlight(&mut self) {
+        if let Some(mut h) = self.highlighter.take() {
+            let lines = h.update(self.line_count(), |l| self.line_buffer(l).unwrap());
+            self.highlighter = Some(h);
+            // Brackets to skip may change in lines highlighted again.
+            self.reindex_brackets(lines);
+        }
+    }
+
+    /// Highlights the buffer with a grammar, which is kept up to date as the buffer is edited.
+    ///
+    /// The comment syntax and comment leaders are set for the language of the
+    /// grammar if it is known.
     ///
     /// # Examples
     ///
     /// ```
     /// # use edit::editor::Editor;
-    /// let mut editor = Editor::new("abc", 0, 3).unwrap();
-    /// editor.delete_at(0, 1);
-    ///
-    /// assert_eq!(editor.buffer_as_str(), "ac");
-    ///
-    /// assert_eq!(editor.column(), 2);
+    /// use edit::editor::highlight::{rust, Span, Style};
+    /// let mut editor = Editor::new("let a;\nb", 0, 0).unwrap();
+    /// editor.set_grammar(rust());
+    /// assert_eq!(editor.highlights(1), Some(&[][..]));
+    ///
+    /// editor.insert_string_at("/* ", 0, 0);
+    /// let comment = Span { start: 0, end: 1, style: Style::Comment };
+    /// assert_eq!(editor.highlights(1), Some(&[comment][..]));
     /// ```
-    pub fn delete_at(&mut self, line: usize, column: usize) {
-        self.core.delete_at(line, column);
+    pub fn set_grammar(&mut self, grammar: Grammar) {
+        if let Some(syntax) = CommentSyntax::for_language(grammar.name) {
+            self.set_comment_syntax(syntax);
+        }
+        self.highlighter = Some(Highlighter::new(grammar));
+        self.rehighlight();
     }
 
-    /// Deletes characters from the buffer in a character range.
+    /// Stops highlighting the buffer.
+    pub fn clear_grammar(&mut self) {
+        self.highlighter = None;
+        self.reindex_brackets(0..self.line_count());
+    }
+
+    /// Returns the highlighted spans of a line, or `None` if the buffer is not highlighted.
+    pub fn highlights(&self, line: usize) -> Option<&[Span]> {
+        self.highlighter.as_ref().and_then(|h| h.spans(line))
+    }
+
+    /// Join a line and next line, like vim's `J`. See `join_lines`.
     ///
     /// # Examples
     ///
     /// ```
     /// # use edit::editor::Editor;
-    /// use edit::editor::Position;
-    /// let mut editor = Editor::new("abcdefg \n hijk", 0, 3).unwrap();
-    /// editor.delete_range(Position::new(0, 4)..Position::new(1, 3));
+    /// let mut editor = Editor::new("a b c d\n\
+    ///                           x", 0, 3).unwrap();
+    /// editor.join(0);
+    /// assert_eq!(editor.buffer_as_str(), "a b c d x");
     ///
-    /// assert_eq!(editor.buffer_as_str(), "abcdjk");
+    /// let mut editor = Editor::new("foo(\n    bar)", 0, 0).unwrap();
+    /// editor.join(0);
+    /// assert_eq!(editor.buffer_as_str(), "foo( bar)");
     /// ```
-    pub fn delete_range(&mut self, range: Range<Position>) {
-        self.core.delete_range(range);
+    pub fn join(&mut self, line: usize) {
+        if line >= self.line_count() {
+            panic!("line {}: out of range", line);
+        }
+        self.join_lines(line, 2);
     }
 
-    /// Deletes a line from the buffer.
+    /// Returns whether joined lines are separated by two spaces after the
+    /// end of a sentence.
+    pub fn join_spaces(&self) -> bool {
+        self.join_spaces
+    }
+
+    /// Sets whether joined lines are separated by two spaces after the end
+    /// of a sentence, like vim's `joinspaces`.
     ///
     /// # Examples
     ///
     /// ```
     /// # use edit::editor::Editor;
-    /// let mut editor = Editor::new("a a\n\
-    ///                               b b b", 1, 3).unwrap();
-    /// editor.delete_line(0);
-    ///
-    /// assert_eq!(editor.buffer_as_str(), "b b b");
-    /// assert_eq!(editor.line(), 0);
-    /// assert_eq!(editor.column(), 3);
-    ///
-    /// editor.delete_line(0);
-    ///
-    /// assert_eq!(editor.buffer_as_str(), "");
-    /// assert_eq!(editor.line(), 0);
-    /// assert_eq!(editor.column(), 0);
-    ///
-    /// let mut editor = Editor::new("a b", 0, 2).unwrap();
-    /// editor.delete_line(0);
-    ///
-    /// assert_eq!(editor.buffer_as_str(), "");
-    /// assert_eq!(editor.line(), 0);
-    /// assert_eq!(editor.column(), 0);
+    /// let mut editor = Editor::new("Done.\nNext", 0, 0).unwrap();
+    /// editor.set_join_spaces(true);
+    /// editor.join(0);
+    /// assert_eq!(editor.buffer_as_str(), "Done.  Next");
     /// ```
-    pub fn delete_line(&mut self, line: usize) {
-        let end = if self.is_last_line(line) {
-            Position::new(line, self.line_width(line).unwrap())
-        } else {
-            Position::new(line + 1, 0)
-        };
-        self.delete_range(Position::new(line, 0)..end);
+    pub fn set_join_spaces(&mut self, join_spaces: bool) {
+        self.join_spaces = join_spaces;
     }
 
-    fn is_last_line(&self, line: usize) -> bool {
-        line == self.line_count() - 1
+    /// Returns the comment leaders, which are `//` and its doc comment forms
+    /// by default. They are also set by `set_comment_syntax`.
+    pub fn comment_leaders(&self) -> &[String] {
+        &self.comment_leaders
     }
 
-    /// Deletes the buffer in a line range.
+    /// Sets the comment leaders, like `//` and `#`.
     ///
     /// # Examples
     ///
     /// ```
     /// # use edit::editor::Editor;
-    /// let mut editor = Editor::new("a a\n\
-    ///                               b b b\n\
-    ///                               c c\n\
-    ///                               d d", 0, 3).unwrap();
-    /// editor.delete_line_range(1..3);
-    ///
-    /// assert_eq!(
-    ///     editor.buffer_as_str(),
-    ///     "a a\n\
-    ///      d d"
-    /// );
-    /// assert_eq!(editor.line(), 0);
-    /// assert_eq!(editor.column(), 3);
+    /// let mut editor = Editor::new("% a\n% b", 0, 0).unwrap();
+    /// editor.set_comment_leaders(vec!["%".to_string()]);
+    /// editor.join(0);
+    /// assert_eq!(editor.buffer_as_str(), "% a b");
     /// ```
-    pub fn delete_line_range(&mut self, range: Range<usize>) {
-        // TODO: Confirm that the position is set exactly.
-        for l in range.rev() {
-            self.delete_line(l);
-        }
+    pub fn set_comment_leaders(&mut self, leaders: Vec<String>) {
+        self.comment_leaders = leaders;
     }
 
-    /// Deletes the buffer to the cursor from the beginning at the line.
+    /// Joins `count` lines from `line`, at least two, like vim's `J`.
+    ///
+    /// The indentation of each joined line is removed, and lines are
+    /// separated by a space, except after whitespace, before `)` or with an
+    /// empty line. When both lines begin with the same comment leader, the
+    /// leader of the joined line is removed too. The cursor moves to where
+    /// the last line is joined.
+    ///
+    /// Returns `false` if there is no line to join.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("    // a\n    //  b\n    // c\nd", 0, 0).unwrap();
+    /// assert!(editor.join_lines(0, 3));
+    /// assert_eq!(editor.buffer_as_str(), "    // a b c\nd");
+    /// assert_eq!(editor.column(), 10);
+    /// assert!(!editor.join_lines(1, 2));
+    /// ```
+    pub fn join_lines(&mut self, line: usize, count: usize) -> bool {
+        self.join_with(line, count, false)
+    }
+
+    /// Joins `count` lines from `line`, at least two, without changing
+    /// whitespace, like vim's `gJ`.
+    ///
+    /// Returns `false` if there is no line to join.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("a\n  b\nc", 0, 0).unwrap();
+    /// assert!(editor.join_lines_raw(0, 5));
+    /// assert_eq!(editor.buffer_as_str(), "a  bc");
+    /// assert_eq!(editor.column(), 4);
+    /// ```
+    pub fn join_lines_raw(&mut self, line: usize, count: usize) -> bool {
+        self.join_with(line, count, true)
+    }
+
+    fn join_with(&mut self, line: usize, count: usize, raw: bool) -> bool {
+        let joins = (count.max(2) - 1).min(self.line_count().saturating_sub(line + 1));
+        if joins == 0 {
+            return false;
+        }
+        let mut column = 0;
+        for _ in 0..joins {
+            let (skip, separator) = if raw {
+                (0, "")
+            } else {
+                let current = self.line_buffer(line).unwrap();
+                let next = self.line_buffer(line + 1).unwrap();
+                join_separator(current, next, self.join_spaces, &self.comment_leaders)
+            };
+            column = self.line_width(line).unwrap();
+            let range = Position::new(line, column)..Position::new(line + 1, skip);
+            self.replace(separator, range);
+        }
+        self.set_line(line);
+        self.set_column(column);
+        true
+    }
+
+    /// Returns the comment leaders kept by reflowing besides the comment
+    /// leaders, which are `//`, `#`, `*` and `>` by default.
+    pub fn reflow_leaders(&self) -> &[String] {
+        &self.reflow_leaders
+    }
+
+    /// Sets the comment leaders kept by reflowing besides the comment leaders.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("% a b\n% c", 0, 0).unwrap();
+    /// editor.set_reflow_leaders(vec!["%".to_string()]);
+    /// editor.reflow_lines(0..2, 80);
+    /// assert_eq!(editor.buffer_as_str(), "% a b c");
+    /// ```
+    pub fn set_reflow_leaders(&mut self, leaders: Vec<String>) {
+        self.reflow_leaders = leaders;
+    }
+
+    /// Reflows lines in a range to `width` columns, like vim's `gq`.
+    ///
+    /// Paragraphs keep their indentation and comment leaders, and the cursor
+    /// stays on the same character of its word. See the `reflow` module for
+    /// details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new(
+    ///     "    // The quick brown fox\n    // jumps over the lazy dog\nfn f() {}",
+    ///     1,
+    ///     9,
+    /// ).unwrap();
+    /// editor.reflow_lines(0..2, 20);
+    /// assert_eq!(
+    ///     editor.buffer_as_str(),
+    ///     "    // The quick\n    // brown fox\n    // jumps over\n    // the lazy dog\nfn f() {}"
+    /// );
+    /// assert_eq!(editor.line(), 2);
+    /// assert_eq!(editor.column(), 9);
+    /// ```
+    pub fn reflow_lines(&mut self, range: Range<usize>, width: usize) {
+        if range.start >= range.end || range.end > self.line_count() {
+            return;
+        }
+        let (line, column) = (self.line(), self.column());
+        let (reflowed, cursor) = {
+            let lines: Vec<&[char]> = range.clone().map(|l| self.line_buffer(l).unwrap()).collect();
+            let mut leaders = self.reflow_leaders.clone();
+            leaders.extend(self.comment_leaders.iter().cloned());
+            let leaders = &leaders;
+            let reflowed = reflow(&lines, width, self.indent.tabstop, leaders);
+            let new_lines: Vec<Vec<char>> = reflowed.iter().map(|l| l.chars().collect()).collect();
+            let new_lines: Vec<&[char]> = new_lines.iter().map(|l| &l[..]).collect();
+            if new_lines == lines {
+                return;
+            }
+            let cursor = if range.contains(&line) {
+                let at = (line - range.start, column);
+                let old = words(&lines, leaders);
+                let new = words(&new_lines, leaders);
+                match old.iter().rposition(|&(l, ref r)| (l, r.start) <= at) {
+                    Some(k) => {
+                        let (l, ref r) = old[k];
+                        let offset = if l == at.0 { (at.1 - r.start).min(r.len()) } else { 0 };
+                        new.get(k).map_or((range.start, 0), |&(l, ref r)| {
+                            (range.start + l, r.start + offset)
+                        })
+                    }
+                    None => (range.start, column),
+                }
+            } else if line < range.start {
+                (line, column)
+            } else {
+                (line + reflowed.len() - range.len(), column)
+            };
+            (reflowed, cursor)
+        };
+        self.rewrite_lines(range, &reflowed);
+        self.set_line(cursor.0);
+        self.set_column(cursor.1);
+    }
+
+    /// Returns the comment syntax used to toggle comments.
+    pub fn comment_syntax(&self) -> &CommentSyntax {
+        &self.comment_syntax
+    }
+
+    /// Sets the comment syntax used to toggle comments, and the comment
+    /// leaders to those of the syntax. It is also set by `set_grammar` for
+    /// known languages. See `CommentSyntax::for_language`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::comment::CommentSyntax;
+    /// let mut editor = Editor::new("x = 1", 0, 0).unwrap();
+    /// editor.set_comment_syntax(CommentSyntax::for_language("python").unwrap());
+    /// editor.toggle_line_comment(0..1);
+    /// assert_eq!(editor.buffer_as_str(), "# x = 1");
+    /// assert_eq!(editor.comment_leaders(), ["# "]);
+    /// ```
+    pub fn set_comment_syntax(&mut self, syntax: CommentSyntax) {
+        self.comment_leaders = syntax.leaders.clone();
+        self.comment_syntax = syntax;
+    }
+
+    /// Toggles line comments of lines in a range in one edit.
+    ///
+    /// If every non-blank line is commented, the comment markers are
+    /// removed. Otherwise, markers are inserted at the minimum indentation of
+    /// the range. The cursor stays on the same character. See
+    /// `comment::toggle_line_comments` for details.
+    ///
+    /// Returns `false` if the language has no comments or every line is blank.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("fn f() {\n    g();\n\n    h();\n}", 1, 4).unwrap();
+    /// assert!(editor.toggle_line_comment(1..4));
+    /// assert_eq!(editor.buffer_as_str(), "fn f() {\n    // g();\n\n    // h();\n}");
+    /// assert_eq!(editor.column(), 7);
+    ///
+    /// assert!(editor.toggle_line_comment(1..4));
+    /// assert_eq!(editor.buffer_as_str(), "fn f() {\n    g();\n\n    h();\n}");
+    /// assert_eq!(editor.column(), 4);
+    /// ```
+    pub fn toggle_line_comment(&mut self, range: Range<usize>) -> bool {
+        if range.start >= range.end || range.end > self.line_count() {
+            return false;
+        }
+        let (line, column) = (self.line(), self.column());
+        let (toggled, column) = {
+            let lines: Vec<&[char]> = range.clone().map(|l| self.line_buffer(l).unwrap()).collect();
+            let syntax = &self.comment_syntax;
+            let toggled = match toggle_line_comments(&lines, syntax, self.indent.tabstop) {
+                Some(toggled) => toggled,
+                None => return false,
+            };
+            // The cursor moves with the text after the comment marker.
+            let column = if range.contains(&line) {
+                let old = lines[line - range.start];
+                let new: Vec<char> = toggled[line - range.start].chars().collect();
+                let text = |l: &[char]| l.iter().take_while(|ch| ch.is_whitespace()).count();
+                let start = text(old).min(text(&new));
+                if column < start {
+                    column
+                } else {
+                    (column + new.len()).saturating_sub(old.len()).max(start)
+                }
+            } else {
+                column
+            };
+            (toggled, column)
+        };
+        self.rewrite_lines(range, &toggled);
+        self.set_line(line);
+        self.set_column(column);
+        true
+    }
+
+    /// Toggles a block comment around the buffer in a range, ignoring
+    /// whitespace around it, and moves the cursor to the start of the range.
+    ///
+    /// Returns `false` if the language has no block comments or the range
+    /// is blank.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// use edit::editor::highlight::rust;
+    /// let mut editor = Editor::new("let x = f(a, b);", 0, 0).unwrap();
+    /// editor.set_grammar(rust());
+    /// let range = Position::new(0, 10)..Position::new(0, 14);
+    /// assert!(editor.toggle_block_comment(range));
+    /// assert_eq!(editor.buffer_as_str(), "let x = f(/* a, b */);");
+    /// assert_eq!(editor.column(), 10);
+    ///
+    /// let range = Position::new(0, 10)..Position::new(0, 20);
+    /// assert!(editor.toggle_block_comment(range));
+    /// assert_eq!(editor.buffer_as_str(), "let x = f(a, b);");
+    /// ```
+    pub fn toggle_block_comment(&mut self, range: Range<Position>) -> bool {
+        let toggled = {
+            let (open, close) = match self.comment_syntax.block {
+                Some(ref block) => block,
+                None => return false,
+            };
+            let s = match self.buffer_range(range.clone()) {
+                Some(s) if !is_blank(s) => s,
+                _ => return false,
+            };
+            toggle_block_comment(s, open, close)
+        };
+        self.replace(&toggled, range.clone());
+        self.set_line(range.start.line);
+        self.set_column(range.start.column);
+        true
+    }
+
+    /// Inserts a character into the buffer at a character position.
+    ///
+    /// If a position is out of the range, nothing happens.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("insert on character", 0, 0).unwrap();
+    /// editor.insert_at('e', 0, 9);
+    ///
+    /// assert_eq!(editor.buffer_as_str(), "insert one character");
+    /// ```
+    ///
+    /// With `autoindent`, a new line is indented like the line it is split
+    /// from, and a line is reindented when a character typed at its
+    /// indentation makes it dedented by the indentation rules.
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::indent::{BracketRules, IndentOptions};
+    /// let mut editor = Editor::new("    if x {", 0, 10).unwrap();
+    /// editor.set_indent_options(IndentOptions {
+    ///     autoindent: true,
+    ///     ..IndentOptions::default()
+    /// });
+    /// editor.set_indent_rules(BracketRules);
+    /// editor.insert_at('\n', 0, 10);
+    /// assert_eq!(editor.buffer_as_str(), "    if x {\n        ");
+    /// assert_eq!(editor.column(), 8);
+    ///
+    /// editor.insert_at('\n', 1, 8);
+    /// editor.insert_at('}', 2, 8);
+    /// assert_eq!(editor.buffer_as_str(), "    if x {\n        \n    }");
+    /// ```
+    pub fn insert_at(&mut self, ch: char, line: usize, column: usize) {
+        let offset = match self.core.offset(line, column) {
+            Some(offset) => offset,
+            None => return,
+        };
+        self.core.insert_at(ch, line, column);
+        self.spliced(offset, &[], &[ch]);
+        self.edited(line, usize::from(ch == '\n'), 0, column > 0);
+        if !self.indent.autoindent {
+            return;
+        }
+        if ch == '\n' {
+            let width = self.expected_indent(line + 1);
+            self.set_indent(line + 1, width);
+            return;
+        }
+        let line_buffer = self.line_buffer(line).unwrap();
+        if indent_len(line_buffer) == column && self.dedents(line) {
+            let width = self.expected_indent(line);
+            self.set_indent(line, width);
+        }
+    }
+
+    /// Inserts a string into the buffer at a character position.
+    ///
+    /// If a position is out of the range, nothing happens.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("insert", 0, 0).unwrap();
+    /// editor.insert_string_at(" string", 0, 6);
+    ///
+    /// assert_eq!(editor.buffer_as_str(), "insert string");
+    /// ```
+    pub fn insert_string_at(&mut self, s: &str, line: usize, column: usize) {
+        let offset = match self.core.offset(line, column) {
+            Some(offset) => offset,
+            None => return,
+        };
+        self.core.insert_string_at(s, line, column);
+        let chars: Vec<char> = s.chars().collect();
+        let n = chars.iter().filter(|&&ch| ch == '\n').count();
+        self.spliced(offset, &[], &chars);
+        self.edited(line, n, 0, column > 0);
+    }
+
+    /// Deletes a `char` from the buffer at a character position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("abc", 0, 3).unwrap();
+    /// editor.delete_at(0, 1);
+    ///
+    /// assert_eq!(editor.buffer_as_str(), "ac");
+    ///
+    /// assert_eq!(editor.column(), 2);
+    /// ```
+    pub fn delete_at(&mut self, line: usize, column: usize) {
+        let width = match self.line_width(line) {
+            Some(w) if column <= w => w,
+            _ => return,
+        };
+        let newline = width == column && line + 1 < self.line_count();
+        let offset = self.core.offset(line, column).unwrap();
+        let removed: Vec<char> = self.buffer().get(offset).cloned().into_iter().collect();
+        self.core.delete_at(line, column);
+        self.spliced(offset, &removed, &[]);
+        self.edited(line, 0, usize::from(newline), false);
+    }
+
+    /// Deletes characters from the buffer in a character range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// let mut editor = Editor::new("abcdefg \n hijk", 0, 3).unwrap();
+    /// editor.delete_range(Position::new(0, 4)..Position::new(1, 3));
+    ///
+    /// assert_eq!(editor.buffer_as_str(), "abcdjk");
+    /// ```
+    pub fn delete_range(&mut self, range: Range<Position>) {
+        let (line, n) = (range.start.line, range.end.line - range.start.line);
+        let removed = self.buffer_range(range.clone()).unwrap().to_vec();
+        let offset = self.offset_position(range.start).unwrap();
+        self.core.delete_range(range);
+        self.spliced(offset, &removed, &[]);
+        self.edited(line, 0, n, false);
+    }
+
+    /// Deletes a line from the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("a a\n\
+    ///                               b b b", 1, 3).unwrap();
+    /// editor.delete_line(0);
+    ///
+    /// assert_eq!(editor.buffer_as_str(), "b b b");
+    /// assert_eq!(editor.line(), 0);
+    /// assert_eq!(editor.column(), 3);
+    ///
+    /// editor.delete_line(0);
+    ///
+    /// assert_eq!(editor.buffer_as_str(), "");
+    /// assert_eq!(editor.line(), 0);
+    /// assert_eq!(editor.column(), 0);
+    ///
+    /// let mut editor = Editor::new("a b", 0, 2).unwrap();
+    /// editor.delete_line(0);
+    ///
+    /// assert_eq!(editor.buffer_as_str(), "");
+    /// assert_eq!(editor.line(), 0);
+    /// assert_eq!(editor.column(), 0);
+    /// ```
+    pub fn delete_line(&mut self, line: usize) {
+        let end = if self.is_last_line(line) {
+            Position::new(line, self.line_width(line).unwrap())
+        } else {
+            Position::new(line + 1, 0)
+        };
+        self.delete_range(Position::new(line, 0)..end);
+    }
+
+    fn is_last_line(&self, line: usize) -> bool {
+        line == self.line_count() - 1
+    }
+
+    /// Deletes the buffer in a line range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("a a\n\
+    ///                               b b b\n\
+    ///                               c c\n\
+    ///                               d d", 0, 3).unwrap();
+    /// editor.delete_line_range(1..3);
+    ///
+    /// assert_eq!(
+    ///     editor.buffer_as_str(),
+    ///     "a a\n\
+    ///      d d"
+    /// );
+    /// assert_eq!(editor.line(), 0);
+    /// assert_eq!(editor.column(), 3);
+    /// ```
+    pub fn delete_line_range(&mut self, range: Range<usize>) {
+        // TODO: Confirm that the position is set exactly.
+        for l in range.rev() {
+            self.delete_line(l);
+        }
+    }
+
+    /// Deletes the buffer to the cursor from the beginning at the line.
     ///
     /// # Examples
     ///
@@ -1223,72 +2726,755 @@ impl Editor {
         self.delete_range(s..e);
     }
 
-    /// Sort lines.
+    /// Deletes the buffer from the cursor to the beginning of a next subword,
+    /// or to the end of the line if no subword follows in it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("parseHTTPResponse\n\
+    ///                               snake_case_name", 1, 0).unwrap();
+    /// editor.delete_to_next_subword();
+    /// assert_eq!(editor.buffer_as_str(), "parseHTTPResponse\ncase_name");
+    ///
+    /// editor.set_line(0);
+    /// editor.set_column(5);
+    /// editor.delete_to_next_subword();
+    /// assert_eq!(editor.buffer_as_str(), "parseResponse\ncase_name");
+    /// ```
+    pub fn delete_to_next_subword(&mut self) {
+        let s = Position::new(self.line(), self.column());
+        let e = self.next_subword_position()
+            .filter(|p| p.line == s.line)
+            .unwrap_or_else(|| Position::new(s.line, self.core.current_line_width()));
+        self.delete_range(s..e);
+    }
+
+    /// Deletes the buffer to the cursor from the beginning of a previous
+    /// subword, or from the beginning of the line if no subword precedes in it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("snake_case_name", 0, 15).unwrap();
+    /// editor.delete_to_previous_subword();
+    /// assert_eq!(editor.buffer_as_str(), "snake_case_");
+    /// editor.delete_to_previous_subword();
+    /// assert_eq!(editor.buffer_as_str(), "snake_");
+    /// assert_eq!(editor.column(), 6);
+    /// ```
+    pub fn delete_to_previous_subword(&mut self) {
+        let e = Position::new(self.line(), self.column());
+        let s = self.previous_subword_position()
+            .filter(|p| p.line == e.line)
+            .unwrap_or_else(|| Position::new(e.line, 0));
+        self.delete_range(s..e);
+    }
+
+    /// Deletes the buffer between the cursor and a position, which is
+    /// typically the target of a motion like `next_paragraph_position`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("One. Two. Three.", 0, 5).unwrap();
+    /// let p = editor.next_sentence_position().unwrap();
+    /// editor.delete_to(p);
+    /// assert_eq!(editor.buffer_as_str(), "One. Three.");
+    ///
+    /// let p = editor.previous_sentence_position().unwrap();
+    /// editor.delete_to(p);
+    /// assert_eq!(editor.buffer_as_str(), "Three.");
+    /// ```
+    pub fn delete_to(&mut self, target: Position) {
+        let cursor = Position::new(self.line(), self.column());
+        if target < cursor {
+            self.delete_range(target..cursor);
+        } else {
+            self.delete_range(cursor..target);
+        }
+    }
+
+    /// Deletes the buffer from the cursor through a character search, like `dt)`,
+    /// remembering the search for `repeat_find`.
+    ///
+    /// A forward search deletes through the position it moves to, and a
+    /// backward one deletes up to the cursor. Returns `false` without any
+    /// change if there are fewer occurrences than `count`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::find::CharSearch;
+    /// let mut editor = Editor::new("f(a, g(b))", 0, 2).unwrap();
+    /// assert!(editor.delete_to_character(CharSearch::till_forward(')'), 1));
+    /// assert_eq!(editor.buffer_as_str(), "f())");
+    ///
+    /// let mut editor = Editor::new("f(a, g(b))", 0, 8).unwrap();
+    /// assert!(editor.delete_to_character(CharSearch::find_backward('('), 2));
+    /// assert_eq!(editor.buffer_as_str(), "f))");
+    /// ```
+    pub fn delete_to_character(&mut self, search: CharSearch, count: usize) -> bool {
+        self.last_find = Some(search);
+        let offset = match self.character_offset(search, count, false) {
+            Some(i) => i,
+            None => return false,
+        };
+        let target = if search.forward { offset + 1 } else { offset };
+        match self.position_at(target) {
+            Some(p) => {
+                self.delete_to(p);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sort lines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("a a\n\
+    ///                               c c\n\
+    ///                               b b b\n\
+    ///                               b a", 0, 3).unwrap();
+    /// editor.sort_line();
+    ///
+    /// assert_eq!(editor.buffer_as_str(),
+    ///     "a a\n\
+    ///      b a\n\
+    ///      b b b\n\
+    ///      c c"
+    /// );
+    /// assert_eq!(editor.line(), 0);
+    /// assert_eq!(editor.column(), 3);
+    /// ```
+    pub fn sort_line(&mut self) {
+        let n = self.line_count();
+        self.sort_line_range(0..n);
+    }
+
+    /// Sort lines in a range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("a a\n\
+    ///                               c c\n\
+    ///                               b b b\n\
+    ///                               b a", 0, 3).unwrap();
+    /// editor.sort_line_range(0..2);
+    ///
+    /// assert_eq!(editor.buffer_as_str(),
+    ///     "a a\n\
+    ///      c c\n\
+    ///      b b b\n\
+    ///      b a"
+    /// );
+    /// assert_eq!(editor.line(), 0);
+    /// assert_eq!(editor.column(), 3);
+    /// ```
+    pub fn sort_line_range(&mut self, range: Range<usize>) {
+        self.sort_lines(range, &SortOptions::default());
+    }
+
+    /// Sorts lines in a range by `options`, keeping the cursor on its line.
+    ///
+    /// If the cursor line is dropped as a duplicate, the cursor moves to the
+    /// line which is kept instead. Whether the buffer ends with a newline
+    /// does not change. See the `sort` module for the options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::sort::{SortOptions, SortOrder};
+    /// let mut editor = Editor::new("v1.10\nv1.9\nV1.9\nv1.2\n", 2, 3).unwrap();
+    /// let options = SortOptions {
+    ///     order: SortOrder::Version,
+    ///     ignore_case: true,
+    ///     unique: true,
+    ///     ..SortOptions::default()
+    /// };
+    /// editor.sort_lines(0..4, &options);
+    ///
+    /// assert_eq!(editor.buffer_as_str(), "v1.2\nv1.9\nv1.10\n");
+    /// assert_eq!(editor.line(), 1);
+    /// assert_eq!(editor.column(), 3);
+    /// ```
+    pub fn sort_lines(&mut self, range: Range<usize>, options: &SortOptions) {
+        if range.start >= range.end || range.end > self.line_count() {
+            return;
+        }
+        let line = self.line();
+        let (order, cursor) = {
+            let lines: Vec<&[char]> = range.clone().map(|l| self.line_buffer(l).unwrap()).collect();
+            let order = sort_lines(&lines, options);
+            if order.iter().cloned().eq(0..lines.len()) {
+                return;
+            }
+            let cursor = if range.contains(&line) {
+                let i = line - range.start;
+                let n = order
+                    .iter()
+                    .position(|&j| j == i)
+                    .or_else(|| {
+                        order.iter().position(|&j| {
+                            compare_lines(lines[j], lines[i], options) == Ordering::Equal
+                        })
+                    })
+                    .unwrap();
+                range.start + n
+            } else if line >= range.end {
+                line + order.len() - lines.len()
+            } else {
+                line
+            };
+            let order: Vec<usize> = order.into_iter().map(|i| range.start + i).collect();
+            (order, cursor)
+        };
+        self.rearrange_lines(range, &order, cursor);
+    }
+
+    /// Replaces lines in a range with copies of the lines at `order` in one
+    /// edit, and moves the cursor to the line `cursor` of the result,
+    /// keeping its column if possible.
+    fn rearrange_lines(&mut self, range: Range<usize>, order: &[usize], cursor: usize) {
+        let column = self.column();
+        let lines: Vec<String> = order
+            .iter()
+            .map(|&l| self.line_buffer(l).unwrap().iter().collect())
+            .collect();
+        self.rewrite_lines(range, &lines);
+        let cursor = cursor.min(self.line_count() - 1);
+        self.set_line(cursor);
+        self.set_column(column);
+    }
+
+    /// Replaces lines in a range with other lines in one edit.
+    fn rewrite_lines(&mut self, range: Range<usize>, lines: &[String]) {
+        let last = self.line_count() - 1;
+        let width = |l| self.line_width(l).unwrap();
+        let mut s = lines.join("\n");
+        // The newline after the last line of the buffer stays in place, so
+        // the buffer keeps its trailing newline, or lack of it.
+        let range = if range.end <= last {
+            if !lines.is_empty() {
+                s.push('\n');
+            }
+            Position::new(range.start, 0)..Position::new(range.end, 0)
+        } else if range.start > 0 {
+            if !lines.is_empty() {
+                s.insert(0, '\n');
+            }
+            Position::new(range.start - 1, width(range.start - 1))..Position::new(last, width(last))
+        } else {
+            Position::new(0, 0)..Position::new(last, width(last))
+        };
+        self.replace(&s, range);
+    }
+
+    /// Moves lines in a range before the line `to`, like vim's `:m`, where
+    /// `to` may be the number of lines to move them to the end. The cursor
+    /// stays on its line if it is in the range, or moves to the first moved
+    /// line.
+    ///
+    /// Returns `false` if the range is out of the buffer, or `to` is inside it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("a\nb\nc\nd", 0, 0).unwrap();
+    /// assert!(editor.move_lines(0..2, 4));
+    /// assert_eq!(editor.buffer_as_str(), "c\nd\na\nb");
+    /// assert_eq!(editor.line(), 2);
+    ///
+    /// assert!(editor.move_lines(3..4, 0));
+    /// assert_eq!(editor.buffer_as_str(), "b\nc\nd\na");
+    /// assert_eq!(editor.line(), 0);
+    /// assert!(!editor.move_lines(0..2, 1));
+    /// ```
+    pub fn move_lines(&mut self, range: Range<usize>, to: usize) -> bool {
+        let line_count = self.line_count();
+        if range.start >= range.end || range.end > line_count || to > line_count {
+            return false;
+        }
+        if range.start < to && to < range.end {
+            return false;
+        }
+        let line = self.line();
+        let offset = if range.contains(&line) { line - range.start } else { 0 };
+        if to <= range.start {
+            let order: Vec<usize> = range.clone().chain(to..range.start).collect();
+            self.rearrange_lines(to..range.end, &order, to + offset);
+        } else {
+            let order: Vec<usize> = (range.end..to).chain(range.clone()).collect();
+            let start = to - range.len();
+            self.rearrange_lines(range.start..to, &order, start + offset);
+        }
+        true
+    }
+
+    /// Moves lines in a range up by `count` lines, stopping at the beginning
+    /// of the buffer. Returns `false` if they cannot move.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("a\nb\nc\n", 2, 1).unwrap();
+    /// assert!(editor.move_lines_up(2..3, 5));
+    /// assert_eq!(editor.buffer_as_str(), "c\na\nb\n");
+    /// assert_eq!(editor.line(), 0);
+    /// assert!(!editor.move_lines_up(0..1, 1));
+    /// ```
+    pub fn move_lines_up(&mut self, range: Range<usize>, count: usize) -> bool {
+        range.start > 0 && self.move_lines(range.clone(), range.start.saturating_sub(count))
+    }
+
+    /// Moves lines in a range down by `count` lines, stopping at the end of
+    /// the buffer. Returns `false` if they cannot move.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("a\nb\nc", 0, 0).unwrap();
+    /// assert!(editor.move_lines_down(0..1, 1));
+    /// assert_eq!(editor.buffer_as_str(), "b\na\nc");
+    /// assert_eq!(editor.line(), 1);
+    /// assert!(!editor.move_lines_down(1..3, 1));
+    /// ```
+    pub fn move_lines_down(&mut self, range: Range<usize>, count: usize) -> bool {
+        let to = (range.end + count).min(self.line_count());
+        range.end < self.line_count() && self.move_lines(range, to)
+    }
+
+    /// Copies lines in a range before the line `to`, like vim's `:t`, where
+    /// `to` may be the number of lines to copy them to the end. The cursor
+    /// moves to its line in the copy if it is in the range, or to the first
+    /// copied line.
+    ///
+    /// Returns `false` if the range is out of the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("a\nb\nc", 1, 0).unwrap();
+    /// assert!(editor.copy_lines(0..2, 3));
+    /// assert_eq!(editor.buffer_as_str(), "a\nb\nc\na\nb");
+    /// assert_eq!(editor.line(), 4);
+    /// ```
+    pub fn copy_lines(&mut self, range: Range<usize>, to: usize) -> bool {
+        let line_count = self.line_count();
+        if range.start >= range.end || range.end > line_count || to > line_count {
+            return false;
+        }
+        let line = self.line();
+        let offset = if range.contains(&line) { line - range.start } else { 0 };
+        let order: Vec<usize> = range.collect();
+        self.rearrange_lines(to..to, &order, to + offset);
+        true
+    }
+
+    /// Inserts `count` copies of lines in a range after them. The cursor
+    /// moves to its line in the first copy if it is in the range, or to the
+    /// first copied line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("a\nb", 0, 1).unwrap();
+    /// editor.duplicate_lines(0..1, 2);
+    /// assert_eq!(editor.buffer_as_str(), "a\na\na\nb");
+    /// assert_eq!(editor.line(), 1);
+    /// assert_eq!(editor.column(), 1);
+    /// ```
+    pub fn duplicate_lines(&mut self, range: Range<usize>, count: usize) {
+        if count == 0 || range.start >= range.end || range.end > self.line_count() {
+            return;
+        }
+        let line = self.line();
+        let offset = if range.contains(&line) { line - range.start } else { 0 };
+        let order: Vec<usize> = (0..count).flat_map(|_| range.clone()).collect();
+        self.rearrange_lines(range.end..range.end, &order, range.end + offset);
+    }
+
+    /// Reverses the order of lines in a range, keeping the cursor on its line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("a\nb\nc\nd", 1, 0).unwrap();
+    /// editor.reverse_lines(0..3);
+    /// assert_eq!(editor.buffer_as_str(), "c\nb\na\nd");
+    /// assert_eq!(editor.line(), 1);
+    /// ```
+    pub fn reverse_lines(&mut self, range: Range<usize>) {
+        if range.start >= range.end || range.end > self.line_count() {
+            return;
+        }
+        let line = self.line();
+        let cursor = if range.contains(&line) {
+            range.start + range.end - 1 - line
+        } else {
+            line
+        };
+        let order: Vec<usize> = range.clone().rev().collect();
+        self.rearrange_lines(range, &order, cursor);
+    }
+
+    /// Keeps the lines in a range for which `keep` returns `true` given the
+    /// line and the lines kept before it, returning the number of removed
+    /// lines. The cursor stays on its line, or moves to the line `kept`
+    /// returns for it.
+    fn filter_lines<F, G>(&mut self, range: Range<usize>, keep: F, kept: G) -> usize
+    where
+        F: Fn(&[char], &[&[char]]) -> bool,
+        G: Fn(&[char], &[&[char]]) -> usize,
+    {
+        if range.start >= range.end || range.end > self.line_count() {
+            return 0;
+        }
+        let line = self.line();
+        let (order, cursor) = {
+            let mut lines: Vec<&[char]> = Vec::new();
+            let mut order = Vec::new();
+            let mut cursor = line;
+            for l in range.clone() {
+                let s = self.line_buffer(l).unwrap();
+                if keep(s, &lines) {
+                    if l == line {
+                        cursor = range.start + lines.len();
+                    }
+                    lines.push(s);
+                    order.push(l);
+                } else if l == line {
+                    cursor = range.start + kept(s, &lines);
+                }
+            }
+            if line >= range.end {
+                cursor = line + order.len() - range.len();
+            }
+            (order, cursor)
+        };
+        let removed = range.len() - order.len();
+        if removed > 0 {
+            self.rearrange_lines(range, &order, cursor);
+        }
+        removed
+    }
+
+    /// Removes lines in a range which are the same as an earlier line in it,
+    /// returning the number of removed lines. The cursor stays on its line,
+    /// or moves to the earlier line if its line is removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("a\nb\na\nc\nb\n", 4, 0).unwrap();
+    /// assert_eq!(editor.dedupe_lines(0..5), 2);
+    /// assert_eq!(editor.buffer_as_str(), "a\nb\nc\n");
+    /// assert_eq!(editor.line(), 1);
+    /// ```
+    pub fn dedupe_lines(&mut self, range: Range<usize>) -> usize {
+        self.filter_lines(
+            range,
+            |s, lines| !lines.contains(&s),
+            |s, lines| lines.iter().position(|&l| l == s).unwrap(),
+        )
+    }
+
+    /// Removes blank lines in a range, returning the number of removed
+    /// lines. The cursor stays on its line, or moves to the next line if its
+    /// line is removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("a\n\n  \nb\n\n", 1, 0).unwrap();
+    /// assert_eq!(editor.remove_blank_lines(0..5), 3);
+    /// assert_eq!(editor.buffer_as_str(), "a\nb\n");
+    /// assert_eq!(editor.line(), 1);
+    /// ```
+    pub fn remove_blank_lines(&mut self, range: Range<usize>) -> usize {
+        self.filter_lines(range, |s, _| !is_blank(s), |_, lines| lines.len())
+    }
+
+    /// Infers indentation of the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::indent::IndentKind;
+    /// let editor = Editor::new("a\n\tb\n\t\tc", 0, 0).unwrap();
+    /// assert_eq!(editor.detect_indent().unwrap().kind, IndentKind::Tabs);
+    /// assert!(!editor.indent_options().expandtab);
+    ///
+    /// let editor = Editor::new("a\n  b\n    c", 0, 0).unwrap();
+    /// assert_eq!(editor.indent_options().shiftwidth, 2);
+    /// ```
+    pub fn detect_indent(&self) -> Option<DetectedIndent> {
+        detect_indent((0..self.line_count()).map(|l| self.line_buffer(l).unwrap()))
+    }
+
+    /// Returns the options of indentation.
+    pub fn indent_options(&self) -> &IndentOptions {
+        &self.indent
+    }
+
+    /// Sets the options of indentation.
+    pub fn set_indent_options(&mut self, options: IndentOptions) {
+        self.indent = options;
+    }
+
+    /// Sets the rules which decide indentation of lines.
+    pub fn set_indent_rules<R: IndentRules + Send + Sync + 'static>(&mut self, rules: R) {
+        self.indent_rules = Some(Arc::new(rules));
+    }
+
+    /// Removes the indentation rules, so that lines are indented like previous ones.
+    pub fn clear_indent_rules(&mut self) {
+        self.indent_rules = None;
+    }
+
+    /// Returns the width of indentation of a line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let editor = Editor::new("a\n\t  b", 0, 0).unwrap();
+    /// assert_eq!(editor.line_indent(0), Some(0));
+    /// assert_eq!(editor.line_indent(1), Some(10));
+    /// assert_eq!(editor.line_indent(2), None);
+    /// ```
+    pub fn line_indent(&self, line: usize) -> Option<usize> {
+        self.line_buffer(line)
+            .map(|l| indent_width(l, self.indent.tabstop))
+    }
+
+    /// Returns the display column of a position, where tabs advance to the next multiple of `tabstop`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// let editor = Editor::new("a\tb", 0, 0).unwrap();
+    /// assert_eq!(editor.display_column(Position::new(0, 2)), Some(8));
+    /// assert_eq!(editor.display_column(Position::new(0, 4)), None);
+    /// ```
+    pub fn display_column(&self, p: Position) -> Option<usize> {
+        let line = self.line_buffer(p.line)?;
+        if line.len() < p.column {
+            return None;
+        }
+        let tabstop = self.indent.tabstop;
+        Some(line[..p.column].iter().fold(0, |column, &ch| match ch {
+            '\t' if tabstop > 0 => (column / tabstop + 1) * tabstop,
+            _ => column + 1,
+        }))
+    }
+
+    /// Replaces indentation of a line with one of `width` columns.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("  ab", 0, 3).unwrap();
+    /// editor.set_indent(0, 4);
+    /// assert_eq!(editor.buffer_as_str(), "    ab");
+    /// assert_eq!(editor.column(), 5);
+    /// ```
+    pub fn set_indent(&mut self, line: usize, width: usize) {
+        let len = match self.line_buffer(line) {
+            Some(l) => indent_len(l),
+            None => return,
+        };
+        let s = self.indent.indent_string(width);
+        self.replace(&s, Position::new(line, 0)..Position::new(line, len));
+    }
+
+    /// Shifts lines in a range rightward by `count` levels of indentation.
+    ///
+    /// Empty lines are left as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("a\n\n  b", 0, 0).unwrap();
+    /// editor.shift_right(0..3, 1);
+    /// assert_eq!(editor.buffer_as_str(), "  a\n\n    b");
+    /// ```
+    pub fn shift_right(&mut self, range: Range<usize>, count: usize) {
+        self.shift(range, count as isize);
+    }
+
+    /// Shifts lines in a range leftward by `count` levels of indentation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::indent::IndentOptions;
+    /// let mut editor = Editor::new("      a\n  b", 0, 0).unwrap();
+    /// editor.set_indent_options(IndentOptions::default());
+    /// editor.shift_left(0..2, 1);
+    /// assert_eq!(editor.buffer_as_str(), "  a\nb");
+    ///
+    /// let mut editor = Editor::new("      a", 0, 0).unwrap();
+    /// editor.set_indent_options(IndentOptions {
+    ///     shiftround: true,
+    ///     ..IndentOptions::default()
+    /// });
+    /// editor.shift_left(0..1, 1);
+    /// assert_eq!(editor.buffer_as_str(), "    a");
+    /// ```
+    pub fn shift_left(&mut self, range: Range<usize>, count: usize) {
+        self.shift(range, -(count as isize));
+    }
+
+    fn shift(&mut self, range: Range<usize>, levels: isize) {
+        for l in range {
+            if self.line_width(l).is_none_or(|w| w == 0) {
+                continue;
+            }
+            let width = self.indent.shift(self.line_indent(l).unwrap(), levels);
+            self.set_indent(l, width);
+        }
+    }
+
+    /// Reindents lines in a range according to the indentation rules.
+    ///
+    /// Each non-blank line is indented like the previous non-blank line,
+    /// adjusted by the rules if any. Blank lines are left as they are.
     ///
     /// # Examples
     ///
     /// ```
     /// # use edit::editor::Editor;
-    /// let mut editor = Editor::new("a a\n\
-    ///                               c c\n\
-    ///                               b b b\n\
-    ///                               b a", 0, 3).unwrap();
-    /// editor.sort_line();
-    ///
-    /// assert_eq!(editor.buffer_as_str(),
-    ///     "a a\n\
-    ///      b a\n\
-    ///      b b b\n\
-    ///      c c\n"
+    /// use edit::editor::indent::BracketRules;
+    /// let mut editor = Editor::new("fn f() {\nif x {\ny();\n}\n\n}", 0, 0).unwrap();
+    /// editor.set_indent_rules(BracketRules);
+    /// editor.reindent_range(0..6);
+    /// assert_eq!(
+    ///     editor.buffer_as_str(),
+    ///     "fn f() {\n    if x {\n        y();\n    }\n\n}"
     /// );
-    /// assert_eq!(editor.line(), 0);
-    /// assert_eq!(editor.column(), 3);
     /// ```
-    pub fn sort_line(&mut self) {
-        let n = self.line_count();
-        self.sort_line_range(0..n);
+    pub fn reindent_range(&mut self, range: Range<usize>) {
+        for l in range {
+            if self.line_buffer(l).is_none_or(|b| indent_len(b) == b.len()) {
+                continue;
+            }
+            let width = self.expected_indent(l);
+            self.set_indent(l, width);
+        }
     }
 
-    /// Sort lines in a range.
+    /// Rewrites whitespace of lines in a range for a new tab width.
+    ///
+    /// Tabs are expanded with the current `tabstop`, and whitespace is
+    /// written back with tabs of `tabstop` unless `expandtab` is set, keeping
+    /// display columns. Only leading whitespace is rewritten unless `all` is
+    /// set. The new width becomes the `tabstop` of the editor.
+    ///
+    /// The whole change is applied and returned as one operation, whose
+    /// inverse undoes it.
     ///
     /// # Examples
     ///
     /// ```
     /// # use edit::editor::Editor;
-    /// let mut editor = Editor::new("a a\n\
-    ///                               c c\n\
-    ///                               b b b\n\
-    ///                               b a", 0, 3).unwrap();
-    /// editor.sort_line_range(0..2);
+    /// let mut editor = Editor::new("a\n\tb\tc\n\t\td", 2, 2).unwrap();
+    /// let old = editor.buffer_as_str();
+    /// let op = editor.retab(0..3, 4, false);
+    /// assert_eq!(editor.buffer_as_str(), "a\n\t\tb\tc\n\t\t\t\td");
+    /// assert_eq!(editor.column(), 4);
+    /// assert_eq!(editor.indent_options().tabstop, 4);
     ///
-    /// assert_eq!(editor.buffer_as_str(),
-    ///     "a a\n\
-    ///      c c\n\
-    ///      b b b\n\
-    ///      b a\n"
-    /// );
-    /// assert_eq!(editor.line(), 0);
-    /// assert_eq!(editor.column(), 3);
+    /// let inverse = op.invert(&old).unwrap();
+    /// editor.apply_operation(&inverse).unwrap();
+    /// assert_eq!(editor.buffer_as_str(), old);
     /// ```
-    pub fn sort_line_range(&mut self, range: Range<usize>) {
-        let l = self.line();
-        let nl: usize;
-        let mut buf = String::new();
-        {
-            let mut vec = Vec::new();
-            for l in 0..self.line_count() {
-                vec.push((l, self.line_buffer(l).unwrap()));
+    pub fn retab(&mut self, range: Range<usize>, tabstop: usize, all: bool) -> Operation {
+        let mut op = Operation::new();
+        let mut offset = 0;
+        for l in range {
+            let line = match self.line_buffer(l) {
+                Some(line) => line,
+                None => break,
+            };
+            let s: Vec<char> = retab_line(line, self.indent.tabstop, tabstop, self.indent.expandtab, all)
+                .chars()
+                .collect();
+            // Only the differing middle is replaced, so that the cursor stays on its character.
+            let prefix = s.iter().zip(line).take_while(|&(a, b)| a == b).count();
+            let suffix = s[prefix..]
+                .iter()
+                .rev()
+                .zip(line[prefix..].iter().rev())
+                .take_while(|&(a, b)| a == b)
+                .count();
+            if prefix == s.len() && prefix == line.len() {
+                continue;
+            }
+            let start = self.offset_position(Position::new(l, prefix)).unwrap();
+            op.retain(start - offset);
+            op.insert(&s[prefix..s.len() - suffix].iter().collect::<String>());
+            op.delete(line.len() - prefix - suffix);
+            offset = start + line.len() - prefix - suffix;
+        }
+        op.retain(self.buffer().len() - offset);
+        self.apply_operation(&op)
+            .expect("retab: the operation is made for the buffer");
+        self.indent.tabstop = tabstop;
+        op
+    }
+
+    /// Returns the width of indentation which a line should have.
+    fn expected_indent(&self, line: usize) -> usize {
+        let prev = (0..line).rev().find(|&l| {
+            let b = self.line_buffer(l).unwrap();
+            indent_len(b) < b.len()
+        });
+        let mut width = prev.map_or(0, |l| self.line_indent(l).unwrap());
+        if let Some(ref rules) = self.indent_rules {
+            if let Some(l) = prev {
+                let s: String = self.line_buffer(l).unwrap().iter().collect();
+                if rules.indents_after(&s) {
+                    width = self.indent.shift(width, 1);
+                }
             }
-            vec[range].sort_by_key(|&(_, v)| v);
-            nl = vec.iter().position(|&(i, _)| i == l).unwrap();
-            for (_, s) in vec.into_iter() {
-                let s: String = s.iter().collect();
-                buf += &s;
-                buf += "\n";
+            if self.dedents(line) {
+                width = self.indent.shift(width, -1);
             }
         }
-        let c = self.column();
-        self.core.reset(&buf, nl, c);
+        width
+    }
+
+    fn dedents(&self, line: usize) -> bool {
+        match (self.indent_rules.as_ref(), self.line_buffer(line)) {
+            (Some(rules), Some(b)) => rules.dedents(&b.iter().collect::<String>()),
+            _ => false,
+        }
     }
 
     /// Replaces the buffer with a string in a range.
@@ -1321,6 +3507,37 @@ impl Editor {
         self.insert_string_at(s, p.line, p.column);
     }
 
+    /// Replaces lines in a range with a string, where each line is counted
+    /// with its newline.
+    fn replace_lines(&mut self, range: Range<usize>, s: &str) {
+        let (start, end, s, unterminated) = {
+            let lines = Lines::new(self);
+            let len = self.buffer().len();
+            let mut s = s.to_string();
+            let mut unterminated = false;
+            // The hidden newline at the end of the buffer stays in place, so
+            // the replacement takes over the role of it, or it is deleted
+            // after a replacement without a newline at the end.
+            if self.position_at(len).is_none() && lines.offset(range.end) == len {
+                if lines.offset(range.start) == len {
+                    s.insert(0, '\n');
+                }
+                if s.ends_with('\n') {
+                    s.pop();
+                } else {
+                    unterminated = true;
+                }
+            }
+            (lines.position(range.start), lines.position(range.end), s, unterminated)
+        };
+        self.replace(&s, start..end);
+        if unterminated {
+            let last = self.line_count() - 1;
+            let width = self.line_width(last).unwrap();
+            self.delete_at(last, width);
+        }
+    }
+
     /// Replaces the buffer with a character at a position.
     ///
     /// # Examples
@@ -1344,6 +3561,377 @@ impl Editor {
         self.delete_at(line, column);
         self.insert_at(ch, line, column);
     }
+
+    /// Converts the case of the buffer in a range, moving the cursor to its start.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// use edit::editor::case::Case;
+    /// let mut editor = Editor::new("let fooBar = größe;", 0, 18).unwrap();
+    /// editor.convert_case(Position::new(0, 4)..Position::new(0, 10), Case::Screaming);
+    /// editor.convert_case(Position::new(0, 14)..Position::new(0, 19), Case::Upper);
+    /// assert_eq!(editor.buffer_as_str(), "let FOO_BAR = GRÖSSE;");
+    /// assert_eq!(editor.column(), 14);
+    /// ```
+    pub fn convert_case(&mut self, range: Range<Position>, case: Case) {
+        let s: String = self.buffer_range(range.clone()).unwrap().iter().collect();
+        let converted = convert_case(&s, case);
+        if converted != s {
+            self.replace(&converted, range.clone());
+        }
+        self.set_line(range.start.line);
+        self.set_column(range.start.column);
+    }
+
+    /// Converts the case of lines in a range, moving the cursor to the beginning of the first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::case::Case;
+    /// let mut editor = Editor::new("a\nb c\nd", 2, 0).unwrap();
+    /// editor.convert_case_lines(0..2, Case::Title);
+    /// assert_eq!(editor.buffer_as_str(), "A\nB C\nd");
+    /// assert_eq!(editor.line(), 0);
+    /// ```
+    pub fn convert_case_lines(&mut self, range: Range<usize>, case: Case) {
+        if range.start >= range.end || range.end > self.line_count() {
+            return;
+        }
+        let end = Position::new(range.end - 1, self.line_width(range.end - 1).unwrap());
+        self.convert_case(Position::new(range.start, 0)..end, case);
+    }
+
+    /// Adds `count` to the number or date at or after the cursor in the
+    /// current line, like vim's `Ctrl-A`, or subtracts with a negative `count`
+    /// like `Ctrl-X`. The cursor moves to the last character of the number.
+    ///
+    /// See the `number` module for recognized numbers. Returns `false` if
+    /// there is no number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("let x = 0x09;", 0, 0).unwrap();
+    /// assert!(editor.increment(1));
+    /// assert_eq!(editor.buffer_as_str(), "let x = 0x0a;");
+    /// assert_eq!(editor.column(), 11);
+    ///
+    /// let mut editor = Editor::new("due 2024-12-31", 0, 0).unwrap();
+    /// assert!(editor.increment(1));
+    /// assert_eq!(editor.buffer_as_str(), "due 2025-01-01");
+    /// editor.set_column(6);
+    /// assert!(editor.increment(-3));
+    /// assert_eq!(editor.buffer_as_str(), "due 2022-01-01");
+    /// ```
+    pub fn increment(&mut self, count: i64) -> bool {
+        let line = self.line();
+        match self.increment_line(line, self.column(), count) {
+            Some(end) => {
+                self.set_column(end - 1);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Increments the number at or after `column` in a line, returning the end of the new number.
+    fn increment_line(&mut self, line: usize, column: usize, count: i64) -> Option<usize> {
+        let (range, s) = increment_at(self.line_buffer(line)?, column, count)?;
+        let end = range.start + s.chars().count();
+        self.replace(&s, Position::new(line, range.start)..Position::new(line, range.end));
+        Some(end)
+    }
+
+    /// Increments the first number at or after `column` in each line of a
+    /// range, by `step` for the first one, twice `step` for the second and so
+    /// on, like vim's `g Ctrl-A` over a block. Lines without a number are skipped.
+    ///
+    /// Returns the number of incremented numbers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("0. a\n0. b\n\n0. c", 0, 0).unwrap();
+    /// assert_eq!(editor.increment_block(0..4, 0, 1), 3);
+    /// assert_eq!(editor.buffer_as_str(), "1. a\n2. b\n\n3. c");
+    /// ```
+    pub fn increment_block(&mut self, lines: Range<usize>, column: usize, step: i64) -> usize {
+        let mut n = 0;
+        for line in lines {
+            let count = step.saturating_mul(n as i64 + 1);
+            if self.increment_line(line, column, count).is_some() {
+                n += 1;
+            }
+        }
+        n
+    }
+
+    /// Computes line-level differences from the buffer to the buffer of `other`,
+    /// with three lines of context.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// use edit::editor::diff::Algorithm;
+    /// let old = Editor::new("aa\nbb\ncc", 0, 0).unwrap();
+    /// let new = Editor::new("aa\nbx\ncc", 0, 0).unwrap();
+    /// let diff = old.diff(&new, Algorithm::Patience);
+    ///
+    /// let hunk = &diff.hunks()[0];
+    /// assert_eq!(hunk.old_lines, 0..3);
+    /// assert_eq!(hunk.inline[0].new, Position::new(1, 1)..Position::new(1, 2));
+    /// ```
+    pub fn diff(&self, other: &Editor, algorithm: Algorithm) -> Diff {
+        Diff::new(self, other, algorithm, 3)
+    }
+
+    /// Applies a patch to the buffer, returning a result per hunk.
+    ///
+    /// A hunk is searched for from where its header says, moving away from
+    /// it line by line. If it is not found, up to `fuzz` context lines at each
+    /// end of the hunk are ignored. Each hunk is applied by one `replace`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::patch::{HunkResult, Patch};
+    /// let patch = Patch::parse("@@ -1,3 +1,3 @@\n a\n-b\n+x\n c\n").unwrap();
+    /// let mut editor = Editor::new("a\nb\nC", 0, 0).unwrap();
+    /// assert_eq!(editor.apply_patch(&patch, 0), vec![HunkResult::Rejected]);
+    /// assert_eq!(editor.apply_patch(&patch, 1), vec![HunkResult::Applied { fuzz: 1 }]);
+    /// assert_eq!(editor.buffer_as_str(), "a\nx\nC");
+    /// ```
+    pub fn apply_patch(&mut self, patch: &Patch, fuzz: usize) -> Vec<HunkResult> {
+        let mut applier = Applier::new();
+        let mut results = Vec::new();
+        for hunk in patch.hunks() {
+            let found = applier.locate(&Lines::new(self).lines, hunk, fuzz);
+            results.push(match found {
+                Some((s, e, new, result)) => {
+                    self.replace_lines(s..e, &new);
+                    result
+                }
+                None => HunkResult::Rejected,
+            });
+        }
+        results
+    }
+
+    /// Merges the buffer with a text on disk, where both are derived from `base`.
+    ///
+    /// Changes on disk which do not conflict with the buffer are applied, and
+    /// conflicting regions are replaced with conflict markers. Returns the
+    /// number of conflicts. Lines which are not changed keep the cursor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let mut editor = Editor::new("a\nb\nC\n", 2, 0).unwrap();
+    /// assert_eq!(editor.merge_with("a\nb\nc\n", "A\nb\nc\n"), 0);
+    /// assert_eq!(editor.buffer_as_str(), "A\nb\nC\n");
+    /// assert_eq!(editor.line(), 2);
+    ///
+    /// assert_eq!(editor.merge_with("A\nb\nC\n", "A\nX\nC\n"), 0);
+    /// assert_eq!(editor.merge_with("A\nb\nC\n", "A\nY\nC\n"), 1);
+    /// assert_eq!(
+    ///     editor.buffer_as_str(),
+    ///     "A\n<<<<<<< buffer\nX\n=======\nY\n>>>>>>> disk\nC\n"
+    /// );
+    /// ```
+    pub fn merge_with(&mut self, base: &str, disk: &str) -> usize {
+        let merged = merge(base, &self.buffer_as_str(), disk, "buffer", "disk");
+        self.set_lines(&merged.text);
+        merged.conflicts
+    }
+
+    /// Replaces the buffer with a text, editing only lines which differ.
+    fn set_lines(&mut self, text: &str) {
+        let old = Lines::new(self).lines;
+        let new: Vec<String> = text.split_inclusive('\n').map(|l| l.to_string()).collect();
+        let mut changes: Vec<(Range<usize>, String)> = Vec::new();
+        for op in diff_slices(&old, &new, Algorithm::Myers) {
+            if op.tag == Tag::Equal {
+                continue;
+            }
+            let s = new[op.new].concat();
+            match changes.last_mut() {
+                Some(&mut (ref mut range, ref mut t)) if range.end == op.old.start => {
+                    range.end = op.old.end;
+                    t.push_str(&s);
+                }
+                _ => changes.push((op.old, s)),
+            }
+        }
+        for (range, s) in changes.into_iter().rev() {
+            self.replace_lines(range, &s);
+        }
+    }
+
+    /// Returns conflicts in the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// let editor = Editor::new("a\n<<<<<<< x\nb\n=======\nc\n>>>>>>> y", 0, 0).unwrap();
+    /// let conflicts = editor.conflicts();
+    /// assert_eq!(conflicts.len(), 1);
+    /// assert_eq!(conflicts[0].start, 1);
+    /// assert_eq!(conflicts[0].end, 5);
+    /// ```
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        find_conflicts((0..self.line_count()).map(|l| self.line_buffer(l).unwrap()))
+    }
+
+    /// Returns a position at the beginning of a next conflict.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// let editor = Editor::new("<<<<<<<\n=======\n>>>>>>>\n<<<<<<<\n=======\n>>>>>>>", 0, 0).unwrap();
+    /// assert_eq!(editor.next_conflict_position(), Some(Position::new(3, 0)));
+    /// ```
+    pub fn next_conflict_position(&self) -> Option<Position> {
+        let line = self.line();
+        self.conflicts()
+            .into_iter()
+            .find(|c| c.start > line)
+            .map(|c| Position::new(c.start, 0))
+    }
+
+    /// Returns a position at the beginning of a previous conflict.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::Position;
+    /// let editor = Editor::new("<<<<<<<\n=======\n>>>>>>>\n<<<<<<<\n=======\n>>>>>>>", 3, 0).unwrap();
+    /// assert_eq!(editor.previous_conflict_position(), Some(Position::new(0, 0)));
+    /// ```
+    pub fn previous_conflict_position(&self) -> Option<Position> {
+        let line = self.line();
+        self.conflicts()
+            .into_iter()
+            .rev()
+            .find(|c| c.start < line)
+            .map(|c| Position::new(c.start, 0))
+    }
+
+    /// Moves a cursor to the beginning of a next conflict.
+    pub fn move_to_next_conflict(&mut self) {
+        if let Some(pos) = self.next_conflict_position() {
+            self.set_line(pos.line);
+            self.set_column(pos.column);
+        }
+    }
+
+    /// Moves a cursor to the beginning of a previous conflict.
+    pub fn move_to_previous_conflict(&mut self) {
+        if let Some(pos) = self.previous_conflict_position() {
+            self.set_line(pos.line);
+            self.set_column(pos.column);
+        }
+    }
+
+    /// Resolves the conflict at the cursor, returning `false` if there is none.
+    ///
+    /// The cursor is moved to the beginning of the resolved lines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::merge::Resolution;
+    /// let mut editor = Editor::new("a\n<<<<<<< x\nb\n=======\nc\n>>>>>>> y\nd", 3, 0).unwrap();
+    /// assert!(editor.resolve_conflict(Resolution::Theirs));
+    /// assert_eq!(editor.buffer_as_str(), "a\nc\nd");
+    /// assert_eq!(editor.line(), 1);
+    /// assert!(!editor.resolve_conflict(Resolution::Ours));
+    /// ```
+    pub fn resolve_conflict(&mut self, resolution: Resolution) -> bool {
+        let line = self.line();
+        let c = match self.conflicts().into_iter().find(|c| c.start <= line && line <= c.end) {
+            Some(c) => c,
+            None => return false,
+        };
+        let s = {
+            let lines = Lines::new(self).lines;
+            let mut s = match resolution {
+                Resolution::Ours => lines[c.ours()].concat(),
+                Resolution::Theirs => lines[c.theirs()].concat(),
+                Resolution::Both => lines[c.ours()].concat() + &lines[c.theirs()].concat(),
+            };
+            // The end marker may be the last line without a newline.
+            if !lines[c.end].ends_with('\n') && s.ends_with('\n') {
+                s.pop();
+            }
+            s
+        };
+        self.replace_lines(c.start..c.end + 1, &s);
+        self.set_line(c.start);
+        self.set_column(0);
+        true
+    }
+
+    /// Applies an operation to the buffer, keeping the cursor on the same character.
+    ///
+    /// Returns an error if the base length of the operation differs from the
+    /// length of the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use edit::editor::Editor;
+    /// use edit::editor::ot::Operation;
+    /// let mut editor = Editor::new("abc\ndef", 1, 1).unwrap();
+    /// let mut op = Operation::new();
+    /// op.retain(1);
+    /// op.delete(3);
+    /// op.insert("x");
+    /// op.retain(3);
+    /// editor.apply_operation(&op).unwrap();
+    ///
+    /// assert_eq!(editor.buffer_as_str(), "axdef");
+    /// assert_eq!(editor.line(), 0);
+    /// assert_eq!(editor.column(), 3);
+    /// ```
+    pub fn apply_operation(&mut self, op: &Operation) -> Result<(), OperationError> {
+        let len = self.buffer().len();
+        if op.base_len() != len {
+            return Err(OperationError::BaseLength(len, op.base_len()));
+        }
+        let mut offset = 0;
+        for c in op.components() {
+            match *c {
+                Component::Retain(n) => offset += n,
+                Component::Insert(ref s) => {
+                    let p = self.position_at(offset).unwrap();
+                    self.insert_string_at(s, p.line, p.column);
+                    offset += s.chars().count();
+                }
+                Component::Delete(n) => {
+                    let s = self.position_at(offset).unwrap();
+                    let e = self.position_at(offset + n).unwrap();
+                    self.delete_range(s..e);
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -1474,6 +4062,21 @@ mod tests {
         assert_eq!(editor.column(), 3);
     }
 
+    #[test]
+    fn test_keyword_class() {
+        let mut editor = Editor::new("snake_case + kebab-case x", 0, 0).unwrap();
+        assert_eq!(editor.next_word_position(), Some(Position::new(0, 11)));
+        assert_eq!(editor.next_keyword_end_position(), Some(Position::new(0, 9)));
+
+        editor.set_keyword_class(KeywordClass::parse("@,-").unwrap());
+        assert_eq!(editor.next_word_position(), Some(Position::new(0, 5)));
+        editor.set_column(13);
+        assert_eq!(editor.next_keyword_end_position(), Some(Position::new(0, 22)));
+        editor.set_column(8);
+        assert_eq!(editor.previous_keyword_position(), Some(Position::new(0, 6)));
+        assert_eq!(editor.previous_symbol_position(), Some(Position::new(0, 5)));
+    }
+
     #[test]
     fn test_move_to_beginning_of_non_blank() {
         let buffer = "  aaa  ";
@@ -1526,6 +4129,30 @@ mod tests {
         assert_eq!(editor.column(), 0);
     }
 
+    #[test]
+    fn test_move_over_closed_fold() {
+        let buffer = "a\nb\nc\nd\ne\nf";
+        let mut editor = Editor::new(buffer, 5, 1).unwrap();
+        editor.create_fold(1..5);
+        editor.create_fold(2..4);
+        editor.close_fold(2);
+        editor.move_to_beginning_of_lower_line();
+        assert_eq!(editor.line(), 4);
+        editor.move_to_beginning_of_lower_line();
+        assert_eq!(editor.line(), 2);
+        editor.move_to_beginning_of_lower_line();
+        assert_eq!(editor.line(), 1);
+
+        editor.close_fold(1);
+        editor.move_down(1);
+        assert_eq!(editor.line(), 5);
+        editor.move_up(1000);
+        assert_eq!(editor.line(), 0);
+        editor.move_to_beginning_of_upper_line();
+        editor.move_to_beginning_of_upper_line();
+        assert_eq!(editor.line(), 5);
+    }
+
     #[test]
     fn test_line_buffer() {
         let test = |editor: Editor| {
@@ -1567,9 +4194,123 @@ mod tests {
             "a a\n\
              b b b\n\
              c a\n\
-             c c\n"
+             c c"
         );
         assert_eq!(editor.line(), 1);
         assert_eq!(editor.column(), 4);
     }
+
+    #[test]
+    fn test_line_transforms_keep_buffer_end() {
+        let mut editor = Editor::new("a\nb", 0, 0).unwrap();
+        editor.insert_string_at("\n", 1, 1);
+        assert!(editor.move_lines_down(0..1, 1));
+        assert_eq!(editor.buffer_as_str(), "b\na\n");
+        assert_eq!(editor.line(), 1);
+        editor.reverse_lines(0..3);
+        assert_eq!(editor.buffer_as_str(), "\na\nb");
+        assert_eq!(editor.line(), 1);
+        assert_eq!(editor.remove_blank_lines(0..3), 1);
+        assert_eq!(editor.buffer_as_str(), "a\nb");
+        assert_eq!(editor.line(), 0);
+        editor.duplicate_lines(1..2, 1);
+        assert_eq!(editor.buffer_as_str(), "a\nb\nb");
+        assert_eq!(editor.line(), 2);
+    }
+
+    #[test]
+    fn test_join_keeps_text_like_comment_leaders() {
+        let joined = |text: &str, grammar: Option<Grammar>| {
+            let mut editor = Editor::new(text, 0, 0).unwrap();
+            if let Some(grammar) = grammar {
+                editor.set_grammar(grammar);
+            }
+            editor.join(0);
+            editor.buffer_as_str()
+        };
+        let text = "#include <a>\n#include <b>";
+        assert_eq!(joined(text, None), "#include <a> #include <b>");
+        assert_eq!(joined(text, Some(highlight::rust())), "#include <a> #include <b>");
+        assert_eq!(joined("  --foo \\\n  --bar", None), "  --foo \\ --bar");
+        assert_eq!(joined("> quote\n> more", None), "> quote > more");
+        assert_eq!(joined("// a\n// b", Some(highlight::rust())), "// a b");
+    }
+
+    #[test]
+    fn test_reflow_keeps_leaders() {
+        let reflowed = |text: &str, width: usize| {
+            let mut editor = Editor::new(text, 0, 0).unwrap();
+            editor.reflow_lines(0..editor.line_count(), width);
+            editor.buffer_as_str()
+        };
+        assert_eq!(reflowed("> one two\n> three four", 80), "> one two three four");
+        assert_eq!(reflowed("> one two\n> three four", 10), "> one two\n> three\n> four");
+        assert_eq!(reflowed("# one two\n# three four", 80), "# one two three four");
+        assert_eq!(reflowed(" * one two\n * three", 80), " * one two three");
+    }
+
+    #[test]
+    fn test_comment_syntax_leaders() {
+        let mut editor = Editor::new(" * a\n * b", 0, 0).unwrap();
+        editor.join(0);
+        assert_eq!(editor.buffer_as_str(), " * a * b");
+        let mut editor = Editor::new(" * a\n * b", 0, 0).unwrap();
+        editor.set_grammar(highlight::rust());
+        editor.join(0);
+        assert_eq!(editor.buffer_as_str(), " * a b");
+
+        let mut editor = Editor::new("-- a\n-- b\n--   c", 0, 0).unwrap();
+        editor.set_comment_syntax(CommentSyntax::for_language("lua").unwrap());
+        editor.reflow_lines(0..3, 80);
+        assert_eq!(editor.buffer_as_str(), "-- a b c");
+
+        let text = "#!/usr/bin/env python\n# a\n#!/usr/bin/env python";
+        let mut editor = Editor::new(text, 0, 0).unwrap();
+        editor.set_comment_syntax(CommentSyntax::for_language("python").unwrap());
+        editor.join(0);
+        editor.join(0);
+        assert_eq!(editor.buffer_as_str(), "#!/usr/bin/env python # a #!/usr/bin/env python");
+    }
+
+    #[test]
+    fn test_highlight_escape_at_buffer_end() {
+        let mut editor = Editor::new("let s = \"abc\\", 0, 0).unwrap();
+        editor.set_grammar(highlight::rust());
+        let escape = Span { start: 12, end: 13, style: Style::Escape };
+        assert_eq!(editor.highlights(0).unwrap().last(), Some(&escape));
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+        let mut editor = Editor::new("fn f() {}", 0, 0).unwrap();
+        editor.set_grammar(highlight::rust());
+        editor.set_indent_rules(indent::BracketRules);
+        assert_send_sync(&editor);
+    }
+
+    #[test]
+    fn test_bracket_index_follows_edits() {
+        let mut editor = Editor::new("fn f(a: [u8]) {\n    g(a)\n}", 0, 0).unwrap();
+        editor.insert_string_at("xy", 1, 4);
+        editor.delete_at(0, 0);
+        editor.insert_at('\n', 1, 3);
+        editor.delete_range(Position::new(0, 8)..Position::new(0, 10));
+        editor.insert_string_at("(", 2, 0);
+        let fresh = BracketIndex::new(editor.buffer(), |_| false);
+        assert_eq!(editor.brackets, fresh);
+        assert_eq!(editor.buffer_as_str(), "n f(a: []) {\n   \n( xyg(a)\n}");
+        assert_eq!(editor.unmatched_brackets(), vec![Position::new(2, 0)]);
+
+        let mut editor = Editor::new("f(a) {\n    g(\")\")\n}", 0, 0).unwrap();
+        editor.set_grammar(highlight::rust());
+        editor.insert_string_at("/* (", 0, 6);
+        editor.insert_string_at("*/", 1, 9);
+        editor.delete_at(1, 6);
+        let mut fresh = Editor::new(&editor.buffer_as_str(), 0, 0).unwrap();
+        fresh.set_grammar(highlight::rust());
+        assert_eq!(editor.brackets, fresh.brackets);
+        assert_eq!(editor.buffer_as_str(), "f(a) {/* (\n    g()\"*/)\n}");
+        assert_eq!(editor.unmatched_brackets(), vec![Position::new(1, 10)]);
+    }
 }
@@ -0,0 +1,217 @@
+//! Incrementing numbers and dates in text, like vim's `Ctrl-A` and `Ctrl-X`.
+//!
+//! Numbers are decimal, optionally negative, or hexadecimal, binary and
+//! octal with a `0x`, `0b` or `0o` prefix. A `-` is a sign unless it
+//! follows an alphanumeric character, so `x-1` has the number `1`. Dates are
+//! written as `YYYY-MM-DD`.
+//!
+//! Zero-padded numbers keep their width, and hexadecimal digits keep their
+//! case. Hexadecimal, binary and octal numbers wrap around as 64-bit
+//! unsigned integers.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::number::increment_at;
+//!
+//! let line: Vec<char> = "x = 0x0F, y = -1".chars().collect();
+//! assert_eq!(increment_at(&line, 0, 1), Some((4..8, "0x10".to_string())));
+//! assert_eq!(increment_at(&line, 9, 3), Some((14..16, "2".to_string())));
+//! ```
+
+use std::ops::Range;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Decimal,
+    Radix(u32),
+    Date,
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Returns the number of days from 1970-01-01.
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Returns the date of a number of days from 1970-01-01.
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = (mp + 2) % 12 + 1;
+    (yoe + era * 400 + i64::from(m <= 2), m, d)
+}
+
+/// Parses a date at `i`, returning its year, month and day.
+fn date_at(s: &[char], i: usize) -> Option<(i64, i64, i64)> {
+    let t = s.get(i..i + 10)?;
+    let digits = [0, 1, 2, 3, 5, 6, 8, 9];
+    if t[4] != '-' || t[7] != '-' || !digits.iter().all(|&j| t[j].is_ascii_digit()) {
+        return None;
+    }
+    let digit = |ch: Option<&char>| ch.is_some_and(|ch| ch.is_ascii_digit());
+    if (i > 0 && digit(s.get(i - 1))) || digit(s.get(i + 10)) {
+        return None;
+    }
+    let n = |r: Range<usize>| t[r].iter().collect::<String>().parse::<i64>().unwrap();
+    let (y, m, d) = (n(0..4), n(5..7), n(8..10));
+    ((1..=12).contains(&m) && 1 <= d && d <= days_in_month(y, m)).then_some((y, m, d))
+}
+
+/// Splits a line into numbers and dates.
+fn tokens(s: &[char]) -> Vec<(Range<usize>, Kind)> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < s.len() {
+        if date_at(s, i).is_some() {
+            tokens.push((i..i + 10, Kind::Date));
+            i += 10;
+            continue;
+        }
+        let radix = match s.get(i + 1) {
+            Some('x') | Some('X') => 16,
+            Some('b') | Some('B') => 2,
+            Some('o') | Some('O') => 8,
+            _ => 0,
+        };
+        if s[i] == '0' && radix > 0 {
+            let n = s[i + 2..].iter().take_while(|ch| ch.is_digit(radix)).count();
+            if n > 0 {
+                tokens.push((i..i + 2 + n, Kind::Radix(radix)));
+                i += 2 + n;
+                continue;
+            }
+        }
+        if s[i].is_ascii_digit() {
+            let n = s[i..].iter().take_while(|ch| ch.is_ascii_digit()).count();
+            let signed = i > 0 && s[i - 1] == '-' && (i < 2 || !s[i - 2].is_alphanumeric());
+            let start = if signed { i - 1 } else { i };
+            tokens.push((start..i + n, Kind::Decimal));
+            i += n;
+            continue;
+        }
+        i += 1;
+    }
+    tokens
+}
+
+fn increment_decimal(s: &str, count: i64) -> Option<String> {
+    let digits = s.trim_start_matches('-');
+    let value: i128 = s.parse().ok()?;
+    let value = value.checked_add(i128::from(count))?;
+    let width = if digits.len() > 1 && digits.starts_with('0') {
+        digits.len()
+    } else {
+        0
+    };
+    let sign = if value < 0 { "-" } else { "" };
+    Some(format!("{}{:0w$}", sign, value.unsigned_abs(), w = width))
+}
+
+fn increment_radix(s: &str, radix: u32, count: i64) -> Option<String> {
+    let digits = &s[2..];
+    let value = u64::from_str_radix(digits, radix).ok()?.wrapping_add(count as u64);
+    let w = digits.len();
+    let formatted = match radix {
+        16 if digits.chars().any(|ch| ch.is_ascii_uppercase()) => format!("{:0w$X}", value, w = w),
+        16 => format!("{:0w$x}", value, w = w),
+        2 => format!("{:0w$b}", value, w = w),
+        _ => format!("{:0w$o}", value, w = w),
+    };
+    Some(format!("{}{}", &s[..2], formatted))
+}
+
+/// Increments the year, the month or the day of a date, selected by `offset` in it.
+fn increment_date(date: (i64, i64, i64), offset: usize, count: i64) -> Option<String> {
+    let (y, m, d) = date;
+    let (y, m, d) = if offset < 5 {
+        let y = y.checked_add(count)?;
+        (y, m, d.min(days_in_month(y, m)))
+    } else if offset < 8 {
+        let months = (y * 12 + m - 1).checked_add(count)?;
+        let (y, m) = (months.div_euclid(12), months.rem_euclid(12) + 1);
+        (y, m, d.min(days_in_month(y, m)))
+    } else {
+        civil_from_days(days_from_civil(y, m, d).checked_add(count)?)
+    };
+    (0..=9999).contains(&y).then(|| format!("{:04}-{:02}-{:02}", y, m, d))
+}
+
+/// Finds the number or date at or after `column` in a line and adds `count` to it.
+///
+/// A date is changed in the component under `column`, or in its day if it
+/// is after `column`. Returns the range of the number and its replacement.
+pub fn increment_at(line: &[char], column: usize, count: i64) -> Option<(Range<usize>, String)> {
+    let (range, kind) = tokens(line).into_iter().find(|t| t.0.end > column)?;
+    let s: String = line[range.clone()].iter().collect();
+    let replacement = match kind {
+        Kind::Decimal => increment_decimal(&s, count)?,
+        Kind::Radix(radix) => increment_radix(&s, radix, count)?,
+        Kind::Date => {
+            let offset = if column < range.start { 8 } else { column - range.start };
+            increment_date(date_at(line, range.start)?, offset, count)?
+        }
+    };
+    Some((range, replacement))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inc(s: &str, column: usize, count: i64) -> Option<String> {
+        let line: Vec<char> = s.chars().collect();
+        increment_at(&line, column, count).map(|(r, rep)| {
+            let mut line = line.clone();
+            line.splice(r, rep.chars());
+            line.into_iter().collect()
+        })
+    }
+
+    #[test]
+    fn test_decimal() {
+        assert_eq!(inc("a 9 b", 0, 1), Some("a 10 b".to_string()));
+        assert_eq!(inc("a 9 b", 4, 1), None);
+        assert_eq!(inc("x 007", 3, 5), Some("x 012".to_string()));
+        assert_eq!(inc("-1", 1, 3), Some("2".to_string()));
+        assert_eq!(inc("0", 0, -2), Some("-2".to_string()));
+        assert_eq!(inc("x-1", 0, 1), Some("x-2".to_string()));
+        assert_eq!(inc("12 34", 3, 1), Some("12 35".to_string()));
+    }
+
+    #[test]
+    fn test_radix() {
+        assert_eq!(inc("0xff", 0, 1), Some("0x100".to_string()));
+        assert_eq!(inc("0x0A", 2, 6), Some("0x10".to_string()));
+        assert_eq!(inc("0b0111", 0, 1), Some("0b1000".to_string()));
+        assert_eq!(inc("0o17", 0, 1), Some("0o20".to_string()));
+        assert_eq!(inc("0x0", 0, -1), Some("0xffffffffffffffff".to_string()));
+    }
+
+    #[test]
+    fn test_date() {
+        assert_eq!(inc("on 2024-02-28", 0, 2), Some("on 2024-03-01".to_string()));
+        assert_eq!(inc("2024-01-31", 6, 1), Some("2024-02-29".to_string()));
+        assert_eq!(inc("2024-02-29", 1, 1), Some("2025-02-28".to_string()));
+        assert_eq!(inc("2024-01-05", 9, -5), Some("2023-12-31".to_string()));
+        assert_eq!(inc("2024-13-05", 0, 1), Some("2025-13-05".to_string()));
+    }
+}
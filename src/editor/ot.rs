@@ -0,0 +1,743 @@
+//! Operational transformation for collaborative editing.
+//!
+//! An `Operation` describes an edit of a whole buffer as a sequence of
+//! retained, inserted and deleted characters, where lengths are counted in
+//! characters like the offsets of `Editor::offset_position`. Two operations
+//! made concurrently against the same buffer can be transformed so that
+//! applying them in either order yields the same text.
+//!
+//! `Client` and `Server` implement the client/server protocol of Jupiter
+//! (as used by ot.js): every client has at most one operation in flight,
+//! and the server serializes all operations into a single history.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::ot::Operation;
+//!
+//! let mut a = Operation::new();
+//! a.retain(3);
+//! a.insert("x");
+//!
+//! let mut b = Operation::new();
+//! b.delete(1);
+//! b.retain(2);
+//!
+//! let (a1, b1) = Operation::transform(&a, &b).unwrap();
+//! let left = b1.apply(&a.apply("abc").unwrap()).unwrap();
+//! let right = a1.apply(&b.apply("abc").unwrap()).unwrap();
+//! assert_eq!(left, "bcx");
+//! assert_eq!(left, right);
+//! ```
+
+use std::error;
+use std::fmt;
+use std::mem;
+
+/// A component of an `Operation`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Component {
+    /// Skips over a number of characters.
+    Retain(usize),
+    /// Inserts a string.
+    Insert(String),
+    /// Deletes a number of characters.
+    Delete(usize),
+}
+
+/// An error which can be returned when applying or combining operations.
+#[derive(PartialEq, Debug)]
+pub enum OperationError {
+    /// The base length of an operation is not the expected one.
+    ///
+    /// The first value is the expected length and the second is the actual one.
+    BaseLength(usize, usize),
+    /// A revision is newer than the history of a server.
+    Revision(usize),
+    /// An acknowledgement arrived when no operation was awaiting it.
+    UnexpectedAck,
+}
+
+impl fmt::Display for OperationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            OperationError::BaseLength(expected, found) => {
+                write!(f, "base length {} does not match {}", found, expected)
+            }
+            OperationError::Revision(n) => write!(f, "revision {} is out of range", n),
+            OperationError::UnexpectedAck => write!(f, "no operation is awaiting an acknowledgement"),
+        }
+    }
+}
+
+impl error::Error for OperationError {
+    fn description(&self) -> &str {
+        match *self {
+            OperationError::BaseLength(..) => "base length does not match",
+            OperationError::Revision(..) => "revision is out of range",
+            OperationError::UnexpectedAck => "unexpected acknowledgement",
+        }
+    }
+}
+
+/// An edit of a whole buffer.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Operation {
+    components: Vec<Component>,
+    base_len: usize,
+    target_len: usize,
+}
+
+impl Operation {
+    /// Creates an empty `Operation`.
+    pub fn new() -> Operation {
+        Operation::default()
+    }
+
+    /// Returns the components of the operation.
+    pub fn components(&self) -> &[Component] {
+        &self.components
+    }
+
+    /// Returns the length of a buffer which the operation can be applied to.
+    pub fn base_len(&self) -> usize {
+        self.base_len
+    }
+
+    /// Returns the length of a buffer after the operation is applied.
+    pub fn target_len(&self) -> usize {
+        self.target_len
+    }
+
+    /// Returns `true` if the operation leaves any buffer unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use edit::editor::ot::Operation;
+    /// let mut op = Operation::new();
+    /// op.retain(4);
+    /// assert!(op.is_noop());
+    /// ```
+    pub fn is_noop(&self) -> bool {
+        self.components.iter().all(|c| matches!(*c, Component::Retain(_)))
+    }
+
+    /// Appends a component which skips over `n` characters.
+    pub fn retain(&mut self, n: usize) {
+        if n == 0 {
+            return;
+        }
+        self.base_len += n;
+        self.target_len += n;
+        if let Some(&mut Component::Retain(ref mut m)) = self.components.last_mut() {
+            *m += n;
+            return;
+        }
+        self.components.push(Component::Retain(n));
+    }
+
+    /// Appends a component which inserts `s`.
+    ///
+    /// Insertions are always kept before an adjacent deletion so that equal
+    /// edits have equal representations.
+    pub fn insert(&mut self, s: &str) {
+        if s.is_empty() {
+            return;
+        }
+        self.target_len += s.chars().count();
+        let n = self.components.len();
+        if let Some(&mut Component::Insert(ref mut t)) = self.components.last_mut() {
+            t.push_str(s);
+            return;
+        }
+        if let Some(&Component::Delete(_)) = self.components.last() {
+            if n >= 2 {
+                if let Component::Insert(ref mut t) = self.components[n - 2] {
+                    t.push_str(s);
+                    return;
+                }
+            }
+            self.components.insert(n - 1, Component::Insert(s.to_string()));
+            return;
+        }
+        self.components.push(Component::Insert(s.to_string()));
+    }
+
+    /// Appends a component which deletes `n` characters.
+    pub fn delete(&mut self, n: usize) {
+        if n == 0 {
+            return;
+        }
+        self.base_len += n;
+        if let Some(&mut Component::Delete(ref mut m)) = self.components.last_mut() {
+            *m += n;
+            return;
+        }
+        self.components.push(Component::Delete(n));
+    }
+
+    /// Applies the operation to a string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use edit::editor::ot::Operation;
+    /// let mut op = Operation::new();
+    /// op.retain(1);
+    /// op.delete(1);
+    /// op.insert("ee");
+    /// op.retain(1);
+    /// assert_eq!(op.apply("abc"), Ok(String::from("aeec")));
+    /// assert!(op.apply("ab").is_err());
+    /// ```
+    pub fn apply(&self, s: &str) -> Result<String, OperationError> {
+        let chars: Vec<char> = s.chars().collect();
+        if chars.len() != self.base_len {
+            return Err(OperationError::BaseLength(chars.len(), self.base_len));
+        }
+        let mut result = String::new();
+        let mut i = 0;
+        for c in &self.components {
+            match *c {
+                Component::Retain(n) => {
+                    result.extend(&chars[i..i + n]);
+                    i += n;
+                }
+                Component::Insert(ref t) => result.push_str(t),
+                Component::Delete(n) => i += n,
+            }
+        }
+        Ok(result)
+    }
+
+    /// Composes two consecutive operations into one which has the same effect.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use edit::editor::ot::Operation;
+    /// let mut a = Operation::new();
+    /// a.retain(2);
+    /// a.insert("c");
+    /// let mut b = Operation::new();
+    /// b.delete(1);
+    /// b.retain(2);
+    /// let ab = a.compose(&b).unwrap();
+    /// assert_eq!(ab.apply("ab"), Ok(String::from("bc")));
+    /// ```
+    pub fn compose(&self, other: &Operation) -> Result<Operation, OperationError> {
+        if self.target_len != other.base_len {
+            return Err(OperationError::BaseLength(self.target_len, other.base_len));
+        }
+        let mut result = Operation::new();
+        let mut ops1 = self.components.iter().cloned();
+        let mut ops2 = other.components.iter().cloned();
+        let mut op1 = ops1.next();
+        let mut op2 = ops2.next();
+        loop {
+            match (op1.take(), op2.take()) {
+                (None, None) => break,
+                (Some(Component::Delete(n)), b) => {
+                    result.delete(n);
+                    op1 = ops1.next();
+                    op2 = b;
+                }
+                (a, Some(Component::Insert(s))) => {
+                    result.insert(&s);
+                    op1 = a;
+                    op2 = ops2.next();
+                }
+                (Some(Component::Retain(n)), Some(Component::Retain(m))) => {
+                    result.retain(n.min(m));
+                    op1 = shorten(Component::Retain(n), m).or_else(|| ops1.next());
+                    op2 = shorten(Component::Retain(m), n).or_else(|| ops2.next());
+                }
+                (Some(Component::Insert(s)), Some(Component::Delete(m))) => {
+                    let n = s.chars().count();
+                    op1 = shorten(Component::Insert(s), m).or_else(|| ops1.next());
+                    op2 = shorten(Component::Delete(m), n).or_else(|| ops2.next());
+                }
+                (Some(Component::Insert(s)), Some(Component::Retain(m))) => {
+                    let n = s.chars().count();
+                    result.insert(&s.chars().take(m).collect::<String>());
+                    op1 = shorten(Component::Insert(s), m).or_else(|| ops1.next());
+                    op2 = shorten(Component::Retain(m), n).or_else(|| ops2.next());
+                }
+                (Some(Component::Retain(n)), Some(Component::Delete(m))) => {
+                    result.delete(n.min(m));
+                    op1 = shorten(Component::Retain(n), m).or_else(|| ops1.next());
+                    op2 = shorten(Component::Delete(m), n).or_else(|| ops2.next());
+                }
+                _ => unreachable!("compose: lengths are checked in advance"),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Returns an operation which undoes the operation, given the string it was applied to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use edit::editor::ot::Operation;
+    /// let mut op = Operation::new();
+    /// op.retain(1);
+    /// op.insert("x");
+    /// op.delete(1);
+    /// op.retain(1);
+    /// let inverse = op.invert("abc").unwrap();
+    /// assert_eq!(inverse.apply("axc"), Ok(String::from("abc")));
+    /// ```
+    pub fn invert(&self, s: &str) -> Result<Operation, OperationError> {
+        let chars: Vec<char> = s.chars().collect();
+        if chars.len() != self.base_len {
+            return Err(OperationError::BaseLength(chars.len(), self.base_len));
+        }
+        let mut inverse = Operation::new();
+        let mut i = 0;
+        for c in &self.components {
+            match *c {
+                Component::Retain(n) => {
+                    inverse.retain(n);
+                    i += n;
+                }
+                Component::Insert(ref t) => inverse.delete(t.chars().count()),
+                Component::Delete(n) => {
+                    inverse.insert(&chars[i..i + n].iter().collect::<String>());
+                    i += n;
+                }
+            }
+        }
+        Ok(inverse)
+    }
+
+    /// Transforms two concurrent operations `a` and `b` into `(a', b')` such
+    /// that `a` followed by `b'` equals `b` followed by `a'`.
+    ///
+    /// When both operations insert at the same position, the insertion of
+    /// `a` comes first.
+    pub fn transform(a: &Operation, b: &Operation) -> Result<(Operation, Operation), OperationError> {
+        if a.base_len != b.base_len {
+            return Err(OperationError::BaseLength(a.base_len, b.base_len));
+        }
+        let mut a1 = Operation::new();
+        let mut b1 = Operation::new();
+        let mut ops1 = a.components.iter().cloned();
+        let mut ops2 = b.components.iter().cloned();
+        let mut op1 = ops1.next();
+        let mut op2 = ops2.next();
+        loop {
+            match (op1.take(), op2.take()) {
+                (None, None) => break,
+                (Some(Component::Insert(s)), b) => {
+                    a1.insert(&s);
+                    b1.retain(s.chars().count());
+                    op1 = ops1.next();
+                    op2 = b;
+                }
+                (a, Some(Component::Insert(s))) => {
+                    a1.retain(s.chars().count());
+                    b1.insert(&s);
+                    op1 = a;
+                    op2 = ops2.next();
+                }
+                (Some(x), Some(y)) => {
+                    let n = component_len(&x);
+                    let m = component_len(&y);
+                    match (&x, &y) {
+                        (&Component::Retain(_), &Component::Retain(_)) => {
+                            a1.retain(n.min(m));
+                            b1.retain(n.min(m));
+                        }
+                        (&Component::Delete(_), &Component::Retain(_)) => a1.delete(n.min(m)),
+                        (&Component::Retain(_), &Component::Delete(_)) => b1.delete(n.min(m)),
+                        _ => (),
+                    }
+                    op1 = shorten(x, m).or_else(|| ops1.next());
+                    op2 = shorten(y, n).or_else(|| ops2.next());
+                }
+                _ => unreachable!("transform: lengths are checked in advance"),
+            }
+        }
+        Ok((a1, b1))
+    }
+}
+
+fn component_len(c: &Component) -> usize {
+    match *c {
+        Component::Retain(n) | Component::Delete(n) => n,
+        Component::Insert(ref s) => s.chars().count(),
+    }
+}
+
+/// Drops the first `n` characters of a component, returning `None` if nothing remains.
+fn shorten(c: Component, n: usize) -> Option<Component> {
+    if component_len(&c) <= n {
+        return None;
+    }
+    Some(match c {
+        Component::Retain(m) => Component::Retain(m - n),
+        Component::Delete(m) => Component::Delete(m - n),
+        Component::Insert(s) => Component::Insert(s.chars().skip(n).collect()),
+    })
+}
+
+/// The synchronization state of a `Client`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum State {
+    /// No operation is awaiting an acknowledgement.
+    Synchronized,
+    /// An operation has been sent and awaits an acknowledgement.
+    AwaitingConfirm(Operation),
+    /// An operation awaits an acknowledgement, and local operations made in
+    /// the meantime are buffered.
+    AwaitingWithBuffer(Operation, Operation),
+}
+
+/// The client side of the collaboration protocol.
+///
+/// A `Client` does not communicate by itself; each method returns what has to
+/// be sent to the server or applied to the local buffer.
+#[derive(Clone, Debug)]
+pub struct Client {
+    revision: usize,
+    state: State,
+}
+
+impl Client {
+    /// Creates a new `Client` which is synchronized with a server at `revision`.
+    pub fn new(revision: usize) -> Client {
+        Client {
+            revision,
+            state: State::Synchronized,
+        }
+    }
+
+    /// Returns the last revision of the server which the client knows.
+    pub fn revision(&self) -> usize {
+        self.revision
+    }
+
+    /// Returns the current state.
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// Handles an operation which has been applied locally.
+    ///
+    /// Returns an operation to be sent to the server with the current revision, if any.
+    pub fn apply_client(&mut self, op: Operation) -> Result<Option<Operation>, OperationError> {
+        let (state, send) = match mem::replace(&mut self.state, State::Synchronized) {
+            State::Synchronized => (State::AwaitingConfirm(op.clone()), Some(op)),
+            State::AwaitingConfirm(o) => (State::AwaitingWithBuffer(o, op), None),
+            State::AwaitingWithBuffer(o, b) => (State::AwaitingWithBuffer(o, b.compose(&op)?), None),
+        };
+        self.state = state;
+        Ok(send)
+    }
+
+    /// Handles an operation which has been received from the server.
+    ///
+    /// Returns an operation to be applied to the local buffer.
+    pub fn apply_server(&mut self, op: Operation) -> Result<Operation, OperationError> {
+        let (state, op) = match mem::replace(&mut self.state, State::Synchronized) {
+            State::Synchronized => (State::Synchronized, op),
+            State::AwaitingConfirm(o) => {
+                let (o, op) = Operation::transform(&o, &op)?;
+                (State::AwaitingConfirm(o), op)
+            }
+            State::AwaitingWithBuffer(o, b) => {
+                let (o, op) = Operation::transform(&o, &op)?;
+                let (b, op) = Operation::transform(&b, &op)?;
+                (State::AwaitingWithBuffer(o, b), op)
+            }
+        };
+        self.state = state;
+        self.revision += 1;
+        Ok(op)
+    }
+
+    /// Handles an acknowledgement of the operation in flight.
+    ///
+    /// Returns a buffered operation to be sent to the server with the current revision, if any.
+    pub fn server_ack(&mut self) -> Result<Option<Operation>, OperationError> {
+        let (state, send) = match mem::replace(&mut self.state, State::Synchronized) {
+            State::Synchronized => return Err(OperationError::UnexpectedAck),
+            State::AwaitingConfirm(_) => (State::Synchronized, None),
+            State::AwaitingWithBuffer(_, b) => (State::AwaitingConfirm(b.clone()), Some(b)),
+        };
+        self.state = state;
+        self.revision += 1;
+        Ok(send)
+    }
+}
+
+/// The server side of the collaboration protocol, which holds the document
+/// and its history.
+#[derive(Clone, Debug)]
+pub struct Server {
+    document: String,
+    operations: Vec<Operation>,
+}
+
+impl Server {
+    /// Creates a new `Server` which has a document.
+    pub fn new(document: &str) -> Server {
+        Server {
+            document: document.to_string(),
+            operations: Vec::new(),
+        }
+    }
+
+    /// Returns the document.
+    pub fn document(&self) -> &str {
+        &self.document
+    }
+
+    /// Returns the number of operations which have been applied.
+    pub fn revision(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Handles an operation which a client made at `revision`.
+    ///
+    /// Returns the transformed operation, which is to be acknowledged to the
+    /// sender and broadcast to the other clients.
+    pub fn receive(&mut self, revision: usize, op: Operation) -> Result<Operation, OperationError> {
+        if revision > self.operations.len() {
+            return Err(OperationError::Revision(revision));
+        }
+        let mut op = op;
+        for concurrent in &self.operations[revision..] {
+            op = Operation::transform(&op, concurrent)?.0;
+        }
+        self.document = op.apply(&self.document)?;
+        self.operations.push(op.clone());
+        Ok(op)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use editor::testing::{assert_converged, simulate, Rng};
+    use editor::Editor;
+
+    use std::collections::VecDeque;
+
+    #[test]
+    fn test_insert() {
+        let mut op = Operation::new();
+        op.retain(1);
+        op.delete(2);
+        op.insert("a");
+        op.insert("b");
+        assert_eq!(
+            op.components(),
+            &[
+                Component::Retain(1),
+                Component::Insert(String::from("ab")),
+                Component::Delete(2),
+            ]
+        );
+        assert_eq!(op.base_len(), 3);
+        assert_eq!(op.target_len(), 3);
+    }
+
+    #[test]
+    fn test_compose() {
+        let mut a = Operation::new();
+        a.retain(1);
+        a.insert("世界");
+        a.retain(2);
+        let mut b = Operation::new();
+        b.retain(2);
+        b.delete(2);
+        b.insert("x");
+        b.retain(1);
+        let ab = a.compose(&b).unwrap();
+        let s = "abc";
+        assert_eq!(ab.apply(s), b.apply(&a.apply(s).unwrap()));
+        assert_eq!(ab.apply(s), Ok(String::from("a世xc")));
+
+        assert_eq!(b.compose(&a), Err(OperationError::BaseLength(4, 3)));
+    }
+
+    #[test]
+    fn test_invert() {
+        let mut op = Operation::new();
+        op.delete(2);
+        op.retain(1);
+        op.insert("世界");
+        let s = "abc";
+        let t = op.apply(s).unwrap();
+        let inverse = op.invert(s).unwrap();
+        assert_eq!(inverse.apply(&t), Ok(s.to_string()));
+        assert_eq!(op.invert("ab"), Err(OperationError::BaseLength(2, 3)));
+    }
+
+    #[test]
+    fn test_transform() {
+        let mut a = Operation::new();
+        a.retain(1);
+        a.insert("a");
+        a.retain(1);
+        let mut b = Operation::new();
+        b.retain(1);
+        b.insert("b");
+        b.delete(1);
+        let (a1, b1) = Operation::transform(&a, &b).unwrap();
+        let s = "xy";
+        let left = b1.apply(&a.apply(s).unwrap()).unwrap();
+        let right = a1.apply(&b.apply(s).unwrap()).unwrap();
+        assert_eq!(left, "xab");
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn test_client() {
+        let mut client = Client::new(0);
+        let mut op = Operation::new();
+        op.insert("a");
+        assert_eq!(client.apply_client(op.clone()), Ok(Some(op)));
+        let mut op = Operation::new();
+        op.insert("a");
+        op.retain(1);
+        assert_eq!(client.apply_client(op), Ok(None));
+        let mut op = Operation::new();
+        op.insert("a");
+        op.retain(2);
+        assert_eq!(client.apply_client(op), Ok(None));
+        assert_eq!(client.revision(), 0);
+
+        let mut buffered = Operation::new();
+        buffered.insert("aa");
+        buffered.retain(1);
+        assert_eq!(client.server_ack(), Ok(Some(buffered.clone())));
+        assert_eq!(client.state(), &State::AwaitingConfirm(buffered));
+        assert_eq!(client.server_ack(), Ok(None));
+        assert_eq!(client.server_ack(), Err(OperationError::UnexpectedAck));
+        assert_eq!(client.revision(), 2);
+    }
+
+    #[test]
+    fn test_server() {
+        let mut server = Server::new("abc");
+        let mut a = Operation::new();
+        a.insert("x");
+        a.retain(3);
+        let mut b = Operation::new();
+        b.retain(3);
+        b.insert("y");
+        assert_eq!(server.receive(0, a.clone()), Ok(a));
+        let mut b1 = Operation::new();
+        b1.retain(4);
+        b1.insert("y");
+        assert_eq!(server.receive(0, b), Ok(b1));
+        assert_eq!(server.document(), "xabcy");
+        assert_eq!(server.revision(), 2);
+        assert_eq!(
+            server.receive(3, Operation::new()),
+            Err(OperationError::Revision(3))
+        );
+    }
+
+    fn random_operation(rng: &mut Rng, len: usize) -> Operation {
+        let mut op = Operation::new();
+        let at = rng.below(len + 1);
+        op.retain(at);
+        if len > at && rng.below(2) == 0 {
+            let n = 1 + rng.below((len - at).min(3));
+            op.delete(n);
+            op.retain(len - at - n);
+        } else {
+            op.insert(rng.insertion());
+            op.retain(len - at);
+        }
+        op
+    }
+
+    enum Message {
+        Ack,
+        Op(Operation),
+    }
+
+    struct Peer {
+        editor: Editor,
+        client: Client,
+        inbox: VecDeque<Message>,
+    }
+
+    #[test]
+    fn test_convergence() {
+        simulate(|rng| {
+            let initial = "fn main() {\n    edit();\n}";
+            let mut server = Server::new(initial);
+            let mut outbox: VecDeque<(usize, usize, Operation)> = VecDeque::new();
+            let mut peers: Vec<Peer> = (0..3)
+                .map(|_| {
+                    Peer {
+                        editor: Editor::new(initial, 0, 0).unwrap(),
+                        client: Client::new(0),
+                        inbox: VecDeque::new(),
+                    }
+                })
+                .collect();
+
+            let mut edits = 0;
+            loop {
+                let pending = !outbox.is_empty() || peers.iter().any(|p| !p.inbox.is_empty());
+                if edits >= 40 && !pending {
+                    break;
+                }
+                match rng.below(3) {
+                    0 if edits < 40 => {
+                        edits += 1;
+                        let i = rng.below(peers.len());
+                        let peer = &mut peers[i];
+                        let op = random_operation(rng, peer.editor.buffer().len());
+                        peer.editor.apply_operation(&op).unwrap();
+                        if let Some(op) = peer.client.apply_client(op).unwrap() {
+                            outbox.push_back((i, peer.client.revision(), op));
+                        }
+                    }
+                    1 => {
+                        if let Some((from, revision, op)) = outbox.pop_front() {
+                            let op = server.receive(revision, op).unwrap();
+                            for (i, peer) in peers.iter_mut().enumerate() {
+                                if i == from {
+                                    peer.inbox.push_back(Message::Ack);
+                                } else {
+                                    peer.inbox.push_back(Message::Op(op.clone()));
+                                }
+                            }
+                        }
+                    }
+                    _ => {
+                        let i = rng.below(peers.len());
+                        let peer = &mut peers[i];
+                        match peer.inbox.pop_front() {
+                            Some(Message::Ack) => {
+                                if let Some(op) = peer.client.server_ack().unwrap() {
+                                    outbox.push_back((i, peer.client.revision(), op));
+                                }
+                            }
+                            Some(Message::Op(op)) => {
+                                let op = peer.client.apply_server(op).unwrap();
+                                peer.editor.apply_operation(&op).unwrap();
+                            }
+                            None => (),
+                        }
+                    }
+                }
+            }
+
+            let texts = peers.iter().map(|p| p.editor.buffer_as_str());
+            assert_converged(Some(server.document().to_string()).into_iter().chain(texts));
+            for peer in &peers {
+                assert_eq!(peer.client.state(), &State::Synchronized);
+            }
+        });
+    }
+}
@@ -0,0 +1,444 @@
+//! Parsing and applying patches in the unified format.
+//!
+//! A `Patch` is applied to an `Editor` hunk by hunk. A hunk which is not
+//! found at the line its header says is searched for nearby, and with a fuzz
+//! factor, up to that many context lines at either end of the hunk may be
+//! ignored. Each hunk is applied as a single `Editor::replace`.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::Editor;
+//! use edit::editor::patch::{HunkResult, Patch};
+//!
+//! let patch = Patch::parse(
+//!     "--- a\n\
+//!      +++ b\n\
+//!      @@ -1,2 +1,2 @@\n \
+//!      x\n\
+//!      -y\n\
+//!      +z\n",
+//! ).unwrap();
+//!
+//! let mut editor = Editor::new("w\nx\ny\n", 0, 0).unwrap();
+//! let results = editor.apply_patch(&patch, 0);
+//! assert_eq!(results, vec![HunkResult::AppliedWithOffset { offset: 1, fuzz: 0 }]);
+//! assert_eq!(editor.buffer_as_str(), "w\nx\nz\n");
+//! ```
+
+use std::error;
+use std::fmt;
+
+use editor::diff::DiffLine;
+
+/// An error which can be returned when parsing a patch.
+///
+/// Each variant has the 1-based number of the offending line.
+#[derive(PartialEq, Debug)]
+pub enum ParseError {
+    /// A hunk header is malformed.
+    Header(usize),
+    /// A line inside a hunk does not start with ` `, `-`, `+` or `\`.
+    Line(usize),
+    /// A hunk has fewer lines than its header says.
+    Count(usize),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::Header(n) => write!(f, "line {}: malformed hunk header", n),
+            ParseError::Line(n) => write!(f, "line {}: unexpected line in hunk", n),
+            ParseError::Count(n) => write!(f, "line {}: hunk ends prematurely", n),
+        }
+    }
+}
+
+impl error::Error for ParseError {
+    fn description(&self) -> &str {
+        match *self {
+            ParseError::Header(..) => "malformed hunk header",
+            ParseError::Line(..) => "unexpected line in hunk",
+            ParseError::Count(..) => "hunk ends prematurely",
+        }
+    }
+}
+
+/// A hunk of a patch.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatchHunk {
+    /// The 1-based first line in the old text, as written in the header.
+    pub old_start: usize,
+    /// The number of lines in the old text.
+    pub old_len: usize,
+    /// The 1-based first line in the new text, as written in the header.
+    pub new_start: usize,
+    /// The number of lines in the new text.
+    pub new_len: usize,
+    /// The lines of the hunk, including their newlines.
+    pub lines: Vec<DiffLine>,
+}
+
+/// A result of applying a hunk.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HunkResult {
+    /// The hunk was applied where its header says.
+    Applied {
+        /// The number of context lines ignored at each end.
+        fuzz: usize,
+    },
+    /// The hunk was applied some lines away from where its header says.
+    AppliedWithOffset {
+        /// The number of lines the hunk was moved by.
+        offset: isize,
+        /// The number of context lines ignored at each end.
+        fuzz: usize,
+    },
+    /// The hunk was not found in the buffer.
+    Rejected,
+}
+
+/// A patch in the unified format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Patch {
+    hunks: Vec<PatchHunk>,
+}
+
+impl Patch {
+    /// Parses a patch.
+    ///
+    /// Lines outside hunks, such as file headers, are ignored, so the hunks
+    /// of all files in the patch are collected in order.
+    pub fn parse(s: &str) -> Result<Patch, ParseError> {
+        let mut hunks = Vec::new();
+        let lines: Vec<&str> = s.split_terminator('\n').collect();
+        let mut i = 0;
+        while i < lines.len() {
+            if !lines[i].starts_with("@@") {
+                i += 1;
+                continue;
+            }
+            let (old_start, old_len, new_start, new_len) =
+                parse_header(lines[i]).ok_or(ParseError::Header(i + 1))?;
+            i += 1;
+            let mut hunk = PatchHunk {
+                old_start,
+                old_len,
+                new_start,
+                new_len,
+                lines: Vec::new(),
+            };
+            let (mut old, mut new) = (0, 0);
+            while old < old_len || new < new_len || lines.get(i).is_some_and(|l| l.starts_with('\\')) {
+                let line = *lines.get(i).ok_or(ParseError::Count(i))?;
+                let prefix = line.chars().next();
+                let text = format!("{}\n", &line[prefix.map_or(0, char::len_utf8)..]);
+                match prefix {
+                    // Some tools strip the space of an empty context line.
+                    Some(' ') | None => {
+                        old += 1;
+                        new += 1;
+                        hunk.lines.push(DiffLine::Context(text));
+                    }
+                    Some('-') => {
+                        old += 1;
+                        hunk.lines.push(DiffLine::Delete(text));
+                    }
+                    Some('+') => {
+                        new += 1;
+                        hunk.lines.push(DiffLine::Insert(text));
+                    }
+                    Some('\\') => {
+                        match hunk.lines.last_mut() {
+                            Some(&mut DiffLine::Context(ref mut t)) |
+                            Some(&mut DiffLine::Delete(ref mut t)) |
+                            Some(&mut DiffLine::Insert(ref mut t)) => {
+                                t.pop();
+                            }
+                            None => return Err(ParseError::Line(i + 1)),
+                        }
+                    }
+                    _ => return Err(ParseError::Line(i + 1)),
+                }
+                if old > old_len || new > new_len {
+                    return Err(ParseError::Line(i + 1));
+                }
+                i += 1;
+            }
+            hunks.push(hunk);
+        }
+        Ok(Patch { hunks })
+    }
+
+    /// Returns the hunks.
+    pub fn hunks(&self) -> &[PatchHunk] {
+        &self.hunks
+    }
+}
+
+/// Parses a header like `@@ -1,2 +1,3 @@`.
+fn parse_header(line: &str) -> Option<(usize, usize, usize, usize)> {
+    let mut words = line.split(' ');
+    if words.next() != Some("@@") {
+        return None;
+    }
+    let old = parse_range(words.next()?, '-')?;
+    let new = parse_range(words.next()?, '+')?;
+    if words.next() != Some("@@") {
+        return None;
+    }
+    Some((old.0, old.1, new.0, new.1))
+}
+
+fn parse_range(s: &str, prefix: char) -> Option<(usize, usize)> {
+    if !s.starts_with(prefix) {
+        return None;
+    }
+    let mut it = s[1..].splitn(2, ',');
+    let start = it.next()?.parse().ok()?;
+    let len = match it.next() {
+        Some(n) => n.parse().ok()?,
+        None => 1,
+    };
+    Some((start, len))
+}
+
+impl PatchHunk {
+    /// Returns the hunk without `fuzz` context lines at each end, and the
+    /// number of lines removed from the beginning.
+    fn trim(&self, fuzz: usize) -> (&[DiffLine], usize) {
+        let is_context = |l: &&DiffLine| matches!(**l, DiffLine::Context(_));
+        let lead = self.lines.iter().take_while(is_context).count().min(fuzz);
+        let trail = self.lines[lead..]
+            .iter()
+            .rev()
+            .take_while(is_context)
+            .count()
+            .min(fuzz);
+        (&self.lines[lead..self.lines.len() - trail], lead)
+    }
+
+    /// Returns the 0-based index of the first old line.
+    fn old_index(&self) -> usize {
+        if self.old_len == 0 {
+            self.old_start
+        } else {
+            self.old_start.saturating_sub(1)
+        }
+    }
+}
+
+/// Returns the old and the new text of lines of a hunk.
+fn sides(lines: &[DiffLine]) -> (Vec<&str>, String) {
+    let mut old = Vec::new();
+    let mut new = String::new();
+    for l in lines {
+        match *l {
+            DiffLine::Context(ref t) => {
+                old.push(t.as_str());
+                new += t;
+            }
+            DiffLine::Delete(ref t) => old.push(t.as_str()),
+            DiffLine::Insert(ref t) => new += t,
+        }
+    }
+    (old, new)
+}
+
+/// Searches for `pattern` in `lines` starting from `expected` and moving away from it.
+fn find(lines: &[String], pattern: &[&str], expected: isize) -> Option<usize> {
+    if pattern.len() > lines.len() {
+        return None;
+    }
+    let last = (lines.len() - pattern.len()) as isize;
+    let matches = |at: isize| {
+        at >= 0 && at <= last &&
+            pattern.iter().enumerate().all(|(k, p)| {
+                lines[at as usize + k].trim_end_matches('\n') == p.trim_end_matches('\n')
+            })
+    };
+    let expected = expected.max(0).min(last);
+    for d in 0..last + 1 {
+        if matches(expected - d) {
+            return Some((expected - d) as usize);
+        }
+        if matches(expected + d) {
+            return Some((expected + d) as usize);
+        }
+    }
+    None
+}
+
+/// A state of applying hunks to a buffer in order.
+pub(crate) struct Applier {
+    line_delta: isize,
+    last_offset: isize,
+}
+
+impl Applier {
+    pub(crate) fn new() -> Applier {
+        Applier {
+            line_delta: 0,
+            last_offset: 0,
+        }
+    }
+
+    /// Locates a hunk in `lines`, returning the range of lines to replace,
+    /// the replacement and the result.
+    pub(crate) fn locate(
+        &mut self,
+        lines: &[String],
+        hunk: &PatchHunk,
+        max_fuzz: usize,
+    ) -> Option<(usize, usize, String, HunkResult)> {
+        let header = hunk.old_index() as isize + self.line_delta;
+        for fuzz in 0..max_fuzz + 1 {
+            let (trimmed, lead) = hunk.trim(fuzz);
+            let (old, new) = sides(trimmed);
+            let expected = header + self.last_offset + lead as isize;
+            if let Some(at) = find(lines, &old, expected) {
+                let offset = at as isize - lead as isize - header;
+                self.last_offset = offset;
+                self.line_delta += hunk.new_len as isize - hunk.old_len as isize;
+                let result = if offset == 0 {
+                    HunkResult::Applied { fuzz }
+                } else {
+                    HunkResult::AppliedWithOffset { offset, fuzz }
+                };
+                return Some((at, at + old.len(), new, result));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use editor::Editor;
+    use editor::diff::Algorithm;
+
+    #[test]
+    fn test_parse() {
+        let patch = Patch::parse(
+            "diff --git a/x b/x\n\
+             --- a/x\n\
+             +++ b/x\n\
+             @@ -1,2 +1 @@ fn main\n \
+             a\n\
+             -b\n\
+             \\ No newline at end of file\n\
+             @@ -5,0 +5,1 @@\n\
+             +c\n",
+        ).unwrap();
+        assert_eq!(
+            patch.hunks(),
+            &[
+                PatchHunk {
+                    old_start: 1,
+                    old_len: 2,
+                    new_start: 1,
+                    new_len: 1,
+                    lines: vec![
+                        DiffLine::Context(String::from("a\n")),
+                        DiffLine::Delete(String::from("b")),
+                    ],
+                },
+                PatchHunk {
+                    old_start: 5,
+                    old_len: 0,
+                    new_start: 5,
+                    new_len: 1,
+                    lines: vec![DiffLine::Insert(String::from("c\n"))],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(Patch::parse("@@ -1 +1\n"), Err(ParseError::Header(1)));
+        assert_eq!(Patch::parse("@@ -1,2 +1,2 @@\n a\n"), Err(ParseError::Count(2)));
+        assert_eq!(Patch::parse("@@ -1 +1 @@\n*a\n"), Err(ParseError::Line(2)));
+        assert_eq!(Patch::parse("@@ -1 +1 @@\n世\n"), Err(ParseError::Line(2)));
+        assert_eq!(Patch::parse("@@ -1 +1 @@\n-a\n"), Err(ParseError::Count(2)));
+        assert_eq!(Patch::parse("@@ -1 +1 @@\n-a\n-b\n"), Err(ParseError::Line(3)));
+    }
+
+    #[test]
+    fn test_apply_patch() {
+        let patch = Patch::parse(
+            "@@ -2,3 +2,3 @@\n \
+             b\n\
+             -c\n\
+             +C\n \
+             d\n\
+             @@ -8,3 +8,4 @@\n \
+             h\n \
+             i\n\
+             +I\n \
+             j\n\
+             @@ -20,3 +21,3 @@\n \
+             x\n\
+             -y\n\
+             +Y\n \
+             z\n",
+        ).unwrap();
+        let mut editor = Editor::new("0\na\nb\nc\nd\ne\nf\ng\nH\ni\nj\nk\n", 11, 0).unwrap();
+        let results = editor.apply_patch(&patch, 0);
+        assert_eq!(
+            results,
+            vec![
+                HunkResult::AppliedWithOffset { offset: 1, fuzz: 0 },
+                HunkResult::Rejected,
+                HunkResult::Rejected,
+            ]
+        );
+        assert_eq!(editor.buffer_as_str(), "0\na\nb\nC\nd\ne\nf\ng\nH\ni\nj\nk\n");
+        assert_eq!(editor.line(), 11);
+
+        let results = editor.apply_patch(&patch, 1);
+        assert_eq!(results[1], HunkResult::AppliedWithOffset { offset: 1, fuzz: 1 });
+        assert_eq!(editor.buffer_as_str(), "0\na\nb\nC\nd\ne\nf\ng\nH\ni\nI\nj\nk\n");
+        assert_eq!(editor.line(), 12);
+    }
+
+    #[test]
+    fn test_apply_patch_at_end() {
+        let patch = Patch::parse("@@ -2 +2,2 @@\n-b\n+c\n+d\n").unwrap();
+        let mut editor = Editor::new("a\nb\n", 0, 0).unwrap();
+        assert_eq!(editor.apply_patch(&patch, 0), vec![HunkResult::Applied { fuzz: 0 }]);
+        assert_eq!(editor.buffer_as_str(), "a\nc\nd\n");
+
+        let patch = Patch::parse("@@ -3,0 +4 @@\n+e\n").unwrap();
+        assert_eq!(editor.apply_patch(&patch, 0), vec![HunkResult::Applied { fuzz: 0 }]);
+        assert_eq!(editor.buffer_as_str(), "a\nc\nd\ne\n");
+    }
+
+    #[test]
+    fn test_apply_diff_at_end() {
+        let texts = ["", "\n", "a", "a\n", "a\nb", "a\nb\n", "x\nz", "b\n"];
+        for old in &texts {
+            for new in &texts {
+                let a = Editor::new(old, 0, 0).unwrap();
+                let b = Editor::new(new, 0, 0).unwrap();
+                let unified = a.diff(&b, Algorithm::Myers).unified("a", "b");
+                let mut editor = a.clone();
+                if !unified.is_empty() {
+                    let patch = Patch::parse(&unified).unwrap();
+                    let results = editor.apply_patch(&patch, 0);
+                    assert!(results.iter().all(|r| *r == HunkResult::Applied { fuzz: 0 }));
+                }
+                assert_eq!(editor.buffer_as_str(), *new, "{:?} to {:?}", old, new);
+            }
+        }
+    }
+
+    #[test]
+    fn test_apply_patch_to_empty() {
+        let patch = Patch::parse("@@ -0,0 +1,2 @@\n+a\n+b\n").unwrap();
+        let mut editor = Editor::new("", 0, 0).unwrap();
+        assert_eq!(editor.apply_patch(&patch, 0), vec![HunkResult::Applied { fuzz: 0 }]);
+        assert_eq!(editor.buffer_as_str(), "a\nb\n");
+    }
+}
@@ -0,0 +1,106 @@
+//! Sentences and paragraphs of prose.
+//!
+//! A sentence ends with `.`, `!` or `?`, optionally followed by closing
+//! `)`, `]`, `"` or `'`, and then by whitespace or the end of the buffer. A
+//! `.` after one of the given abbreviations does not end a sentence. A
+//! paragraph is a run of non-blank lines, so a blank line also ends a
+//! sentence.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::prose::sentence_starts;
+//!
+//! let s: Vec<char> = "Hi, Mr. Smith. (Yes!) Go.\n\nNext".chars().collect();
+//! assert_eq!(sentence_starts(&s, &[]), vec![0, 8, 15, 22, 27]);
+//! assert_eq!(sentence_starts(&s, &["Mr".to_string()]), vec![0, 15, 22, 27]);
+//! ```
+
+/// Returns `true` if a line has only whitespace.
+pub fn is_blank(line: &[char]) -> bool {
+    line.iter().all(|ch| ch.is_whitespace())
+}
+
+/// Returns the offset just after the sentence end whose punctuation is at `i`, if any.
+fn sentence_end(s: &[char], i: usize, abbreviations: &[String]) -> Option<usize> {
+    if !matches!(s[i], '.' | '!' | '?') {
+        return None;
+    }
+    let j = i + 1 + s[i + 1..]
+        .iter()
+        .take_while(|&&ch| matches!(ch, ')' | ']' | '"' | '\''))
+        .count();
+    if s.get(j).is_some_and(|ch| !ch.is_whitespace()) {
+        return None;
+    }
+    if s[i] == '.' {
+        let n = s[..i].iter().rev().take_while(|ch| !ch.is_whitespace()).count();
+        let word: String = s[i - n..i]
+            .iter()
+            .skip_while(|&&ch| matches!(ch, '(' | '[' | '"' | '\''))
+            .collect();
+        if abbreviations.contains(&word) {
+            return None;
+        }
+    }
+    Some(j)
+}
+
+/// Returns offsets where sentences start.
+///
+/// A sentence starts at the first non-whitespace character of the buffer,
+/// after a sentence end, or after a blank line.
+pub fn sentence_starts(s: &[char], abbreviations: &[String]) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let mut expect = true;
+    let mut blank = true;
+    let mut i = 0;
+    while i < s.len() {
+        let ch = s[i];
+        if ch == '\n' {
+            expect |= blank;
+            blank = true;
+        } else if !ch.is_whitespace() {
+            if expect {
+                starts.push(i);
+                expect = false;
+            }
+            blank = false;
+            if let Some(j) = sentence_end(s, i, abbreviations) {
+                expect = true;
+                i = j;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    starts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn starts(s: &str, abbreviations: &[&str]) -> Vec<usize> {
+        let chars: Vec<char> = s.chars().collect();
+        let abbreviations: Vec<String> = abbreviations.iter().map(|a| a.to_string()).collect();
+        sentence_starts(&chars, &abbreviations)
+    }
+
+    #[test]
+    fn test_sentence_starts() {
+        assert_eq!(starts("  One. Two?  Three!", &[]), vec![2, 7, 13]);
+        assert_eq!(starts("a.b c.\nd", &[]), vec![0, 7]);
+        assert_eq!(starts("\"Quote.\" Next", &[]), vec![0, 9]);
+        assert_eq!(starts("See e.g. this. (And e.g. that.)", &["e.g"]), vec![0, 15]);
+        assert_eq!(starts("para\n  \n\nnext one", &[]), vec![0, 9]);
+        assert!(starts(" \n ", &[]).is_empty());
+    }
+
+    #[test]
+    fn test_is_blank() {
+        assert!(is_blank(&[]));
+        assert!(is_blank(&[' ', '\t']));
+        assert!(!is_blank(&[' ', 'a']));
+    }
+}
@@ -0,0 +1,101 @@
+//! Pairing of quotes.
+//!
+//! Quotes are paired from the beginning of a scope, so whether a quote
+//! opens or closes a string is decided by the quotes before it. A quote
+//! after a backslash is escaped, and for double quotes, raw strings like
+//! `r#"..."#` are closed only by a quote followed by as many `#`s.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::quote::quote_pairs;
+//!
+//! let s: Vec<char> = r##"a "b \" c" r#"d " e"# "f""##.chars().collect();
+//! assert_eq!(quote_pairs(&s, '"'), vec![(2, 9), (13, 19), (22, 24)]);
+//! ```
+
+/// Where quotes are paired from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QuoteScope {
+    /// Quotes are paired in each line, so a string cannot span lines.
+    #[default]
+    Line,
+    /// Quotes are paired from the beginning of the buffer.
+    Buffer,
+}
+
+/// Returns the number of `#`s of a raw string whose opening quote is at `i`.
+fn raw_hashes(s: &[char], i: usize) -> Option<usize> {
+    let hashes = s[..i].iter().rev().take_while(|&&ch| ch == '#').count();
+    let r = i.checked_sub(hashes + 1)?;
+    let prefix = if r > 0 && s[r - 1] == 'b' { r - 1 } else { r };
+    let word = |ch: char| ch.is_alphanumeric() || ch == '_';
+    if s[r] != 'r' || prefix > 0 && word(s[prefix - 1]) {
+        return None;
+    }
+    Some(hashes)
+}
+
+/// Returns offsets of paired quotes of `q`, each pair as its opening and closing quote.
+///
+/// An unclosed quote is not paired.
+pub fn quote_pairs(s: &[char], q: char) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    let mut i = 0;
+    while i < s.len() {
+        if s[i] == '\\' {
+            i += 2;
+            continue;
+        }
+        if s[i] != q {
+            i += 1;
+            continue;
+        }
+        let close = match raw_hashes(s, i).filter(|_| q == '"') {
+            Some(n) => (i + 1..s.len()).find(|&j| {
+                s[j] == q && s.len() - j > n && s[j + 1..j + 1 + n].iter().all(|&ch| ch == '#')
+            }),
+            None => {
+                let mut j = i + 1;
+                loop {
+                    match s.get(j) {
+                        Some(&'\\') => j += 2,
+                        Some(&ch) if ch == q => break Some(j),
+                        Some(_) => j += 1,
+                        None => break None,
+                    }
+                }
+            }
+        };
+        match close {
+            Some(j) => {
+                pairs.push((i, j));
+                i = j + 1;
+            }
+            None => break,
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(s: &str, q: char) -> Vec<(usize, usize)> {
+        let chars: Vec<char> = s.chars().collect();
+        quote_pairs(&chars, q)
+    }
+
+    #[test]
+    fn test_quote_pairs() {
+        assert_eq!(pairs(r#""a" "b"#, '"'), vec![(0, 2)]);
+        assert_eq!(pairs(r#"\"a" "b""#, '"'), vec![(3, 5)]);
+        assert_eq!(pairs(r#""\\" "b""#, '"'), vec![(0, 3), (5, 7)]);
+        assert_eq!(pairs("'a' 'b\\''", '\''), vec![(0, 2), (4, 8)]);
+        assert_eq!(pairs(r##"br"a\" "##, '"'), vec![(2, 5)]);
+        assert_eq!(pairs(r###"r##"a"#b"##"###, '"'), vec![(3, 8)]);
+        // Not a raw string.
+        assert_eq!(pairs(r#"for"a\"""#, '"'), vec![(3, 7)]);
+    }
+}
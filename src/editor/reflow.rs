@@ -0,0 +1,181 @@
+//! Reflowing text to a width, like vim's `gq`.
+//!
+//! Lines are grouped into paragraphs, which are separated by blank lines
+//! and by changes of comment leader. The words of each paragraph are filled
+//! into lines no wider than the width, except for words which are wider by
+//! themselves. The first line keeps its indentation and comment leader, and
+//! the other lines take those of the second line of the paragraph, so a
+//! hanging indentation is kept.
+//!
+//! Widths are display widths, where wide characters take two columns.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::reflow::{default_leaders, reflow};
+//!
+//! let text = ["  # one two", "  # three", "", "four five six"];
+//! let lines: Vec<Vec<char>> = text.iter().map(|l| l.chars().collect()).collect();
+//! let lines: Vec<&[char]> = lines.iter().map(|l| &l[..]).collect();
+//! assert_eq!(
+//!     reflow(&lines, 10, 4, &default_leaders()),
+//!     vec!["  # one", "  # two", "  # three", "", "four five", "six"]
+//! );
+//! ```
+
+use std::ops::Range;
+
+use editor::comment::comment_leader;
+
+/// Comment leaders kept by reflowing by default, those of `//`, `#` and
+/// `/* */` comments and of quotes in mail and Markdown.
+pub const DEFAULT_LEADERS: &[&str] = &["///", "//!", "//", "#", "*", ">"];
+
+/// Returns `DEFAULT_LEADERS` as strings.
+pub fn default_leaders() -> Vec<String> {
+    DEFAULT_LEADERS.iter().map(|s| s.to_string()).collect()
+}
+
+/// Returns the number of columns a character takes on display.
+///
+/// Combining marks and zero-width characters take none, and wide East
+/// Asian characters and emoji take two.
+pub fn char_width(ch: char) -> usize {
+    match ch as u32 {
+        0x0300..=0x036F | 0x200B..=0x200F | 0x20D0..=0x20FF | 0xFE00..=0xFE0F | 0xFE20..=0xFE2F => 0,
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+/// Returns the display width of text, where tabs advance to the next
+/// multiple of `tabstop`.
+pub fn display_width(s: &[char], tabstop: usize) -> usize {
+    s.iter().fold(0, |column, &ch| match ch {
+        '\t' if tabstop > 0 => (column / tabstop + 1) * tabstop,
+        _ => column + char_width(ch),
+    })
+}
+
+/// Returns the length of the prefix of a line, which is its indentation or
+/// its comment leader with whitespace around it, and the comment leader.
+fn prefix<'a>(line: &'a [char], leaders: &[String]) -> (usize, Option<&'a [char]>) {
+    match comment_leader(line, leaders) {
+        Some(r) => {
+            let n = line[r.end..].iter().take_while(|ch| ch.is_whitespace()).count();
+            (r.end + n, Some(&line[r]))
+        }
+        None => (line.iter().take_while(|ch| ch.is_whitespace()).count(), None),
+    }
+}
+
+/// Returns the words of lines after their prefixes, as the index of their
+/// line and their range in it.
+pub fn words(lines: &[&[char]], leaders: &[String]) -> Vec<(usize, Range<usize>)> {
+    let mut words = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let mut j = prefix(line, leaders).0;
+        while j < line.len() {
+            let n = line[j..].iter().take_while(|ch| !ch.is_whitespace()).count();
+            if n > 0 {
+                words.push((i, j..j + n));
+            }
+            j += n.max(1);
+        }
+    }
+    words
+}
+
+/// Reflows lines to `width` columns, returning the new lines.
+pub fn reflow(lines: &[&[char]], width: usize, tabstop: usize, leaders: &[String]) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let (n, leader) = prefix(lines[i], leaders);
+        if n == lines[i].len() {
+            result.push(lines[i].iter().collect());
+            i += 1;
+            continue;
+        }
+        let mut j = i + 1;
+        while j < lines.len() {
+            let (m, l) = prefix(lines[j], leaders);
+            if m == lines[j].len() || l != leader {
+                break;
+            }
+            j += 1;
+        }
+        let first = &lines[i][..n];
+        let rest = if j > i + 1 {
+            &lines[i + 1][..prefix(lines[i + 1], leaders).0]
+        } else {
+            first
+        };
+        let mut line = first.to_vec();
+        let mut empty = true;
+        for (k, r) in words(&lines[i..j], leaders) {
+            let word = &lines[i + k][r];
+            let w = display_width(&line, tabstop) + 1 + display_width(word, tabstop);
+            if !empty && w > width {
+                result.push(line.iter().collect());
+                line = rest.to_vec();
+                empty = true;
+            }
+            if !empty {
+                line.push(' ');
+            }
+            line.extend(word);
+            empty = false;
+        }
+        result.push(line.into_iter().collect());
+        i = j;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reflowed(text: &[&str], width: usize) -> Vec<String> {
+        let lines: Vec<Vec<char>> = text.iter().map(|l| l.chars().collect()).collect();
+        let lines: Vec<&[char]> = lines.iter().map(|l| &l[..]).collect();
+        reflow(&lines, width, 4, &default_leaders())
+    }
+
+    #[test]
+    fn test_reflow() {
+        assert_eq!(
+            reflowed(&["- a b c d", "  e", "//", "// f g", "# h"], 6),
+            ["- a b", "  c d", "  e", "//", "// f g", "# h"]
+        );
+        assert_eq!(reflowed(&["a", "b", "c"], 80), ["a b c"]);
+        assert_eq!(reflowed(&["\tx y"], 6), ["\tx", "\ty"]);
+        assert_eq!(reflowed(&["abcdefgh ij"], 4), ["abcdefgh", "ij"]);
+        assert_eq!(reflowed(&[" // ab", " // cd ef"], 10), [" // ab cd", " // ef"]);
+        assert_eq!(reflowed(&[" * ab", " * cd ef"], 9), [" * ab cd", " * ef"]);
+        assert_eq!(reflowed(&["#a", "#b"], 80), ["#a b"]);
+        assert_eq!(reflowed(&["# ab", "# cd ef"], 8), ["# ab cd", "# ef"]);
+        assert_eq!(reflowed(&["> ab cd", "> ef"], 6), ["> ab", "> cd", "> ef"]);
+        assert_eq!(reflowed(&["/// ab", "/// cd ef"], 10), ["/// ab cd", "/// ef"]);
+    }
+
+    #[test]
+    fn test_wide_characters() {
+        assert_eq!(display_width(&['漢', '字', 'e', '\u{301}'], 4), 5);
+        assert_eq!(reflowed(&["漢字 漢字 漢字"], 10), ["漢字 漢字", "漢字"]);
+    }
+}
@@ -0,0 +1,426 @@
+//! Snippets with tabstops, in the syntax of LSP and TextMate.
+//!
+//! A snippet is text with tabstops: `$1` or `${1}` is an empty tabstop,
+//! `${1:default}` one with a placeholder, which may have tabstops inside,
+//! and `${1|one,two|}` one with choices, the first of which is inserted.
+//! `$0` is where the cursor ends, at the end of the snippet by default.
+//! Tabstops of the same number mirror the first of them. `\` escapes `$`,
+//! `}` and `\`, and also `,` and `|` in choices.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::snippet::Snippet;
+//!
+//! let snippet = Snippet::parse("for ${1:i} in ${2|a,b|} { $1 }$0").unwrap();
+//! assert_eq!(snippet.text(), "for i in a { i }");
+//! let t = &snippet.tabstops()[0];
+//! assert_eq!((t.number, t.ranges.clone()), (1, vec![4..5, 13..14]));
+//! assert_eq!(snippet.tabstops()[1].choices, vec!["a", "b"]);
+//! assert_eq!(snippet.tabstops()[2].ranges, vec![16..16]);
+//! ```
+
+// Tabstops often have a single range, which is not meant to be a list of its offsets.
+#![allow(clippy::single_range_in_vec_init)]
+
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::ops::Range;
+
+/// An error which can be returned when parsing a snippet, with the offset
+/// of the offending character.
+#[derive(PartialEq, Debug)]
+pub struct ParseError(pub usize);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "offset {}: malformed tabstop", self.0)
+    }
+}
+
+impl error::Error for ParseError {
+    fn description(&self) -> &str {
+        "malformed tabstop"
+    }
+}
+
+/// A tabstop of a snippet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tabstop {
+    /// The number of the tabstop.
+    pub number: usize,
+    /// Character ranges of the tabstop, the first one followed by its mirrors.
+    pub ranges: Vec<Range<usize>>,
+    /// Choices of the tabstop, if any.
+    pub choices: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Text(String),
+    Tabstop(usize, Vec<Node>),
+    Choice(usize, Vec<String>),
+}
+
+struct Parser {
+    chars: Vec<char>,
+    i: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.i).cloned()
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        let found = self.peek() == Some(ch);
+        if found {
+            self.i += 1;
+        }
+        found
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let n = self.chars[self.i..]
+            .iter()
+            .take_while(|ch| ch.is_ascii_digit())
+            .count();
+        let s: String = self.chars[self.i..self.i + n].iter().collect();
+        self.i += n;
+        s.parse().ok()
+    }
+
+    /// Reads a character, unescaping one of `escaped` after `\`.
+    fn text_char(&mut self, escaped: &[char]) -> char {
+        let ch = self.chars[self.i];
+        self.i += 1;
+        match self.peek() {
+            Some(next) if ch == '\\' && escaped.contains(&next) => {
+                self.i += 1;
+                next
+            }
+            _ => ch,
+        }
+    }
+
+    /// Parses nodes up to the end, or up to `}` if `nested` is set.
+    fn nodes(&mut self, nested: bool) -> Result<Vec<Node>, ParseError> {
+        let mut nodes = Vec::new();
+        let mut text = String::new();
+        while let Some(ch) = self.peek() {
+            if nested && ch == '}' {
+                break;
+            }
+            let start = self.i;
+            if ch == '$' {
+                self.i += 1;
+                if let Some(node) = self.tabstop(start)? {
+                    if !text.is_empty() {
+                        nodes.push(Node::Text(::std::mem::take(&mut text)));
+                    }
+                    nodes.push(node);
+                    continue;
+                }
+                self.i = start + 1;
+                text.push('$');
+                continue;
+            }
+            text.push(self.text_char(&['$', '}', '\\']));
+        }
+        if !text.is_empty() {
+            nodes.push(Node::Text(text));
+        }
+        Ok(nodes)
+    }
+
+    /// Parses a tabstop after `$` at `start`, or returns `None` if it is not one.
+    fn tabstop(&mut self, start: usize) -> Result<Option<Node>, ParseError> {
+        if !self.eat('{') {
+            return Ok(self.number().map(|n| Node::Tabstop(n, Vec::new())));
+        }
+        let n = match self.number() {
+            Some(n) => n,
+            None => return Ok(None),
+        };
+        let node = if self.eat(':') {
+            Node::Tabstop(n, self.nodes(true)?)
+        } else if self.eat('|') {
+            let mut choices = vec![String::new()];
+            loop {
+                match self.peek() {
+                    Some('|') => break,
+                    Some(',') => choices.push(String::new()),
+                    Some(_) => {
+                        let ch = self.text_char(&['$', '}', '\\', ',', '|']);
+                        choices.last_mut().unwrap().push(ch);
+                        continue;
+                    }
+                    None => return Err(ParseError(start)),
+                }
+                self.i += 1;
+            }
+            self.i += 1;
+            Node::Choice(n, choices)
+        } else {
+            Node::Tabstop(n, Vec::new())
+        };
+        if !self.eat('}') {
+            return Err(ParseError(start));
+        }
+        Ok(Some(node))
+    }
+}
+
+/// A parsed snippet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snippet {
+    text: String,
+    /// Tabstops in the order of visiting, `$0` last.
+    tabstops: Vec<Tabstop>,
+}
+
+struct Renderer {
+    text: Vec<char>,
+    /// The first placeholder of each tabstop.
+    defaults: HashMap<usize, Node>,
+    /// Text of tabstops rendered so far.
+    rendered: HashMap<usize, String>,
+    tabstops: Vec<Tabstop>,
+}
+
+impl Renderer {
+    fn collect_defaults(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            match *node {
+                Node::Tabstop(n, ref children) => {
+                    if !children.is_empty() && !self.defaults.contains_key(&n) {
+                        self.defaults.insert(n, node.clone());
+                    }
+                    self.collect_defaults(children);
+                }
+                Node::Choice(n, _) => {
+                    self.defaults.entry(n).or_insert_with(|| node.clone());
+                }
+                Node::Text(_) => {}
+            }
+        }
+    }
+
+    fn render(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            let n = match *node {
+                Node::Text(ref s) => {
+                    self.text.extend(s.chars());
+                    continue;
+                }
+                Node::Tabstop(n, _) | Node::Choice(n, _) => n,
+            };
+            let start = self.text.len();
+            let mut choices = Vec::new();
+            if let Some(s) = self.rendered.get(&n) {
+                self.text.extend(s.chars());
+            } else {
+                // A tabstop inside its own placeholder is an empty mirror.
+                self.rendered.insert(n, String::new());
+                match self.defaults.get(&n).cloned() {
+                    Some(Node::Tabstop(_, children)) => self.render(&children),
+                    Some(Node::Choice(_, c)) => {
+                        self.text.extend(c[0].chars());
+                        choices = c;
+                    }
+                    _ => {}
+                }
+                let s = self.text[start..].iter().collect();
+                self.rendered.insert(n, s);
+            }
+            match self.tabstops.iter_mut().find(|t| t.number == n) {
+                Some(t) => t.ranges.push(start..self.text.len()),
+                None => self.tabstops.push(Tabstop {
+                    number: n,
+                    ranges: vec![start..self.text.len()],
+                    choices,
+                }),
+            }
+        }
+    }
+}
+
+impl Snippet {
+    /// Parses a snippet.
+    pub fn parse(s: &str) -> Result<Snippet, ParseError> {
+        let mut parser = Parser {
+            chars: s.chars().collect(),
+            i: 0,
+        };
+        let nodes = parser.nodes(false)?;
+        let mut renderer = Renderer {
+            text: Vec::new(),
+            defaults: HashMap::new(),
+            rendered: HashMap::new(),
+            tabstops: Vec::new(),
+        };
+        renderer.collect_defaults(&nodes);
+        renderer.render(&nodes);
+        let mut tabstops = renderer.tabstops;
+        let end = renderer.text.len();
+        if tabstops.iter().all(|t| t.number != 0) {
+            tabstops.push(Tabstop {
+                number: 0,
+                ranges: vec![end..end],
+                choices: Vec::new(),
+            });
+        }
+        tabstops.sort_by_key(|t| t.number.wrapping_sub(1));
+        Ok(Snippet {
+            text: renderer.text.into_iter().collect(),
+            tabstops,
+        })
+    }
+
+    /// Returns the text of the snippet.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the tabstops in the order of visiting, `$0` last.
+    pub fn tabstops(&self) -> &[Tabstop] {
+        &self.tabstops
+    }
+
+    /// Returns the snippet with `indent` after each newline, for inserting
+    /// it into an indented line.
+    pub fn indented(&self, indent: &str) -> Snippet {
+        let width = indent.chars().count();
+        let newlines: Vec<usize> = self.text
+            .chars()
+            .enumerate()
+            .filter(|&(_, ch)| ch == '\n')
+            .map(|(i, _)| i)
+            .collect();
+        let map = |i: usize| i + width * newlines.iter().filter(|&&n| n < i).count();
+        let mut tabstops = self.tabstops.clone();
+        for t in &mut tabstops {
+            for r in &mut t.ranges {
+                *r = map(r.start)..map(r.end);
+            }
+        }
+        Snippet {
+            text: self.text.replace('\n', &format!("\n{}", indent)),
+            tabstops,
+        }
+    }
+}
+
+/// Tabstops of an inserted snippet, tracked through edits of the buffer.
+#[derive(Clone, Debug)]
+pub(crate) struct SnippetSession {
+    /// Tabstops with ranges of buffer offsets.
+    pub tabstops: Vec<Tabstop>,
+    /// The index of the current tabstop.
+    pub current: usize,
+    /// The range being replaced to mirror the current tabstop, if any.
+    pub mirroring: Option<(usize, usize)>,
+}
+
+impl SnippetSession {
+    /// Starts tracking a snippet inserted at `offset`.
+    pub fn new(snippet: &Snippet, offset: usize) -> SnippetSession {
+        let mut tabstops = snippet.tabstops.clone();
+        for t in &mut tabstops {
+            for r in &mut t.ranges {
+                *r = r.start + offset..r.end + offset;
+            }
+        }
+        SnippetSession {
+            tabstops,
+            current: 0,
+            mirroring: None,
+        }
+    }
+
+    /// Shifts ranges for `removed` characters at `offset` replaced with `inserted` ones.
+    ///
+    /// Ranges of the current tabstop, or the one being mirrored, grow by
+    /// characters inserted at their boundaries, and others do not.
+    pub fn edit(&mut self, offset: usize, removed: usize, inserted: usize) {
+        let map = |p: usize, right: bool| if p < offset || (p == offset && !right) {
+            p
+        } else if p >= offset + removed {
+            p - removed + inserted
+        } else if right {
+            offset + inserted
+        } else {
+            offset
+        };
+        for (t, tabstop) in self.tabstops.iter_mut().enumerate() {
+            for (r, range) in tabstop.ranges.iter_mut().enumerate() {
+                let grow = match self.mirroring {
+                    Some(m) => m == (t, r),
+                    None => t == self.current,
+                };
+                let start = map(range.start, !grow);
+                *range = start..map(range.end, grow).max(start);
+            }
+        }
+    }
+
+    /// Returns the current tabstop.
+    pub fn tabstop(&self) -> &Tabstop {
+        &self.tabstops[self.current]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(snippet: &Snippet) -> Vec<(usize, Vec<Range<usize>>)> {
+        snippet
+            .tabstops()
+            .iter()
+            .map(|t| (t.number, t.ranges.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let snippet = Snippet::parse("${1:a ${2:b}} $2 $$ \\$1 ${x}").unwrap();
+        assert_eq!(snippet.text(), "a b b $$ $1 ${x}");
+        assert_eq!(
+            ranges(&snippet),
+            vec![(1, vec![0..3]), (2, vec![2..3, 4..5]), (0, vec![16..16])]
+        );
+
+        // A placeholder of a later occurrence is used for all.
+        let snippet = Snippet::parse("$1 ${1:x} ${0:end}").unwrap();
+        assert_eq!(snippet.text(), "x x end");
+        assert_eq!(ranges(&snippet), vec![(1, vec![0..1, 2..3]), (0, vec![4..7])]);
+
+        let snippet = Snippet::parse("${1|a\\,b,c|}").unwrap();
+        assert_eq!(snippet.tabstops()[0].choices, vec!["a,b", "c"]);
+
+        assert_eq!(Snippet::parse("a ${1:b"), Err(ParseError(2)));
+        assert_eq!(Snippet::parse("${1|a"), Err(ParseError(0)));
+    }
+
+    #[test]
+    fn test_indented() {
+        let snippet = Snippet::parse("{\n\t$1\n}").unwrap().indented("  ");
+        assert_eq!(snippet.text(), "{\n  \t\n  }");
+        assert_eq!(ranges(&snippet), vec![(1, vec![5..5]), (0, vec![9..9])]);
+    }
+
+    #[test]
+    fn test_session_edit() {
+        let snippet = Snippet::parse("$1$2 $1").unwrap();
+        let mut session = SnippetSession::new(&snippet, 10);
+        session.edit(10, 0, 3);
+        assert_eq!(session.tabstops[0].ranges, vec![10..13, 14..14]);
+        assert_eq!(session.tabstops[1].ranges, vec![13..13]);
+        session.edit(11, 2, 0);
+        assert_eq!(session.tabstops[0].ranges, vec![10..11, 12..12]);
+        session.edit(0, 0, 1);
+        assert_eq!(session.tabstops[0].ranges, vec![11..12, 13..13]);
+    }
+}
@@ -0,0 +1,271 @@
+//! Sorting lines, like vim's `:sort` and the `sort` command.
+//!
+//! Lines are compared by a key, which is the whole line, a field, the text
+//! from a column or the match of a pattern, in an order chosen by
+//! `SortOptions`. Sorting is stable, so lines with equal keys keep their
+//! order, also when sorting in reverse.
+//!
+//! # Examples
+//!
+//! ```
+//! use edit::editor::sort::{sort_lines, SortKey, SortOptions, SortOrder};
+//!
+//! let text = ["b 10", "a 9", "c 10"];
+//! let lines: Vec<Vec<char>> = text.iter().map(|l| l.chars().collect()).collect();
+//! let lines: Vec<&[char]> = lines.iter().map(|l| &l[..]).collect();
+//! let options = SortOptions {
+//!     order: SortOrder::Numeric,
+//!     key: SortKey::Field(1),
+//!     ..SortOptions::default()
+//! };
+//! assert_eq!(sort_lines(&lines, &options), vec![1, 0, 2]);
+//! ```
+
+use std::cmp::Ordering;
+use std::ops::Range;
+
+/// How keys are ordered.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SortOrder {
+    /// By characters.
+    #[default]
+    Lexical,
+    /// By the first decimal number, which may be negative or have a
+    /// fraction. Keys without a number come first.
+    Numeric,
+    /// By characters, except that runs of digits compare as numbers, so
+    /// `a2` comes before `a10`.
+    Natural,
+    /// By the first version number, like `1.2.10`, comparing its components
+    /// as numbers. Keys without a version come first, and keys with equal
+    /// versions are ordered naturally.
+    Version,
+}
+
+/// Which part of a line is compared.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum SortKey {
+    /// The whole line.
+    #[default]
+    Line,
+    /// A whitespace-separated field, counted from 0.
+    Field(usize),
+    /// The text from a column to the end of the line.
+    Column(usize),
+    /// The range a function matches in the line, like a pattern.
+    Match(fn(&[char]) -> Option<Range<usize>>),
+}
+
+/// Options for sorting lines.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SortOptions {
+    /// How keys are ordered.
+    pub order: SortOrder,
+    /// Which part of a line is compared.
+    pub key: SortKey,
+    /// Whether to sort in descending order.
+    pub reverse: bool,
+    /// Whether to compare letters regardless of their case.
+    pub ignore_case: bool,
+    /// Whether to drop lines whose key compares equal to the previous line.
+    pub unique: bool,
+}
+
+/// Returns the key of a line. Lines without the key have an empty one.
+fn key<'a>(line: &'a [char], key: &SortKey) -> &'a [char] {
+    match *key {
+        SortKey::Line => line,
+        SortKey::Field(n) => {
+            let mut i = 0;
+            let mut field = 0;
+            loop {
+                i += line[i..].iter().take_while(|ch| ch.is_whitespace()).count();
+                let len = line[i..].iter().take_while(|ch| !ch.is_whitespace()).count();
+                if len == 0 {
+                    return &[];
+                }
+                if field == n {
+                    return &line[i..i + len];
+                }
+                field += 1;
+                i += len;
+            }
+        }
+        SortKey::Column(n) => &line[n.min(line.len())..],
+        SortKey::Match(f) => f(line).map_or(&[], |r| &line[r]),
+    }
+}
+
+/// Compares runs of digits as numbers.
+fn compare_digits(a: &[char], b: &[char]) -> Ordering {
+    let trim = |s: &[char]| s.len() - s.iter().take_while(|&&ch| ch == '0').count();
+    let (na, nb) = (trim(a), trim(b));
+    na.cmp(&nb).then_with(|| a[a.len() - na..].cmp(&b[b.len() - nb..]))
+}
+
+fn compare_natural(a: &[char], b: &[char]) -> Ordering {
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i].is_ascii_digit() && b[j].is_ascii_digit() {
+            let n = a[i..].iter().take_while(|ch| ch.is_ascii_digit()).count();
+            let m = b[j..].iter().take_while(|ch| ch.is_ascii_digit()).count();
+            match compare_digits(&a[i..i + n], &b[j..j + m]) {
+                Ordering::Equal => {}
+                o => return o,
+            }
+            i += n;
+            j += m;
+        } else {
+            match a[i].cmp(&b[j]) {
+                Ordering::Equal => {}
+                o => return o,
+            }
+            i += 1;
+            j += 1;
+        }
+    }
+    (a.len() - i).cmp(&(b.len() - j))
+}
+
+/// Returns the first decimal number of a key.
+fn number(s: &[char]) -> Option<f64> {
+    let i = s.iter().position(|ch| ch.is_ascii_digit())?;
+    let start = if i > 0 && s[i - 1] == '-' { i - 1 } else { i };
+    let mut end = i + s[i..].iter().take_while(|ch| ch.is_ascii_digit()).count();
+    if s.get(end) == Some(&'.') && s.get(end + 1).is_some_and(|ch| ch.is_ascii_digit()) {
+        end += 1 + s[end + 1..].iter().take_while(|ch| ch.is_ascii_digit()).count();
+    }
+    s[start..end].iter().collect::<String>().parse().ok()
+}
+
+/// Returns the components of the first version number of a key.
+fn version(s: &[char]) -> Option<Vec<&[char]>> {
+    let mut i = s.iter().position(|ch| ch.is_ascii_digit())?;
+    let mut components = Vec::new();
+    loop {
+        let n = s[i..].iter().take_while(|ch| ch.is_ascii_digit()).count();
+        components.push(&s[i..i + n]);
+        i += n;
+        if s.get(i) != Some(&'.') || !s.get(i + 1).is_some_and(|ch| ch.is_ascii_digit()) {
+            return Some(components);
+        }
+        i += 1;
+    }
+}
+
+/// Compares two lines by `options`, ignoring `reverse` and `unique`.
+pub fn compare_lines(a: &[char], b: &[char], options: &SortOptions) -> Ordering {
+    let lower = |s: &[char]| -> Vec<char> { s.iter().flat_map(|ch| ch.to_lowercase()).collect() };
+    let (a, b) = (key(a, &options.key), key(b, &options.key));
+    let (a, b) = if options.ignore_case {
+        (lower(a), lower(b))
+    } else {
+        (a.to_vec(), b.to_vec())
+    };
+    match options.order {
+        SortOrder::Lexical => a.cmp(&b),
+        SortOrder::Numeric => match (number(&a), number(&b)) {
+            (Some(x), Some(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+            (x, y) => x.is_some().cmp(&y.is_some()),
+        },
+        SortOrder::Natural => compare_natural(&a, &b),
+        SortOrder::Version => {
+            let ordering = match (version(&a), version(&b)) {
+                (Some(x), Some(y)) => {
+                    let n = x.len().min(y.len());
+                    (0..n)
+                        .map(|i| compare_digits(x[i], y[i]))
+                        .find(|&o| o != Ordering::Equal)
+                        .unwrap_or_else(|| x.len().cmp(&y.len()))
+                }
+                (x, y) => x.is_some().cmp(&y.is_some()),
+            };
+            ordering.then_with(|| compare_natural(&a, &b))
+        }
+    }
+}
+
+/// Sorts lines by `options`, returning the indices of the lines in their
+/// new order. With `unique`, only the first of lines which compare equal is
+/// kept.
+pub fn sort_lines(lines: &[&[char]], options: &SortOptions) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..lines.len()).collect();
+    indices.sort_by(|&i, &j| {
+        let o = compare_lines(lines[i], lines[j], options);
+        if options.reverse { o.reverse() } else { o }
+    });
+    if options.unique {
+        indices.dedup_by(|&mut j, &mut i| {
+            compare_lines(lines[i], lines[j], options) == Ordering::Equal
+        });
+    }
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(text: &[&str], options: &SortOptions) -> Vec<String> {
+        let lines: Vec<Vec<char>> = text.iter().map(|l| l.chars().collect()).collect();
+        let lines: Vec<&[char]> = lines.iter().map(|l| &l[..]).collect();
+        sort_lines(&lines, options).into_iter().map(|i| text[i].to_string()).collect()
+    }
+
+    #[test]
+    fn test_orders() {
+        let options = |order| SortOptions {
+            order,
+            ..SortOptions::default()
+        };
+        let text = ["x10", "x9", "x-2", "y", "x09"];
+        assert_eq!(
+            sorted(&text, &options(SortOrder::Lexical)),
+            ["x-2", "x09", "x10", "x9", "y"]
+        );
+        assert_eq!(
+            sorted(&text, &options(SortOrder::Numeric)),
+            ["y", "x-2", "x9", "x09", "x10"]
+        );
+        assert_eq!(
+            sorted(&text, &options(SortOrder::Natural)),
+            ["x-2", "x9", "x09", "x10", "y"]
+        );
+        let text = ["v1.10", "v1.9.2", "1.9", "none", "v1.9"];
+        assert_eq!(
+            sorted(&text, &options(SortOrder::Version)),
+            ["none", "1.9", "v1.9", "v1.9.2", "v1.10"]
+        );
+    }
+
+    #[test]
+    fn test_options() {
+        let text = ["b", "A", "a", "B", "a"];
+        let options = SortOptions {
+            ignore_case: true,
+            reverse: true,
+            ..SortOptions::default()
+        };
+        assert_eq!(sorted(&text, &options), ["b", "B", "A", "a", "a"]);
+        let options = SortOptions {
+            unique: true,
+            ..options
+        };
+        assert_eq!(sorted(&text, &options), ["b", "A"]);
+
+        fn after_colon(line: &[char]) -> Option<Range<usize>> {
+            line.iter().position(|&ch| ch == ':').map(|i| i + 1..line.len())
+        }
+        let text = ["x:2", "y", "z:1", "w : 3"];
+        let options = SortOptions {
+            key: SortKey::Match(after_colon),
+            ..SortOptions::default()
+        };
+        assert_eq!(sorted(&text, &options), ["y", "w : 3", "z:1", "x:2"]);
+        let options = SortOptions {
+            key: SortKey::Column(2),
+            ..SortOptions::default()
+        };
+        assert_eq!(sorted(&text, &options), ["y", "z:1", "x:2", "w : 3"]);
+    }
+}
@@ -0,0 +1,40 @@
+//! Helpers for randomized tests.
+
+/// A xorshift generator, which is enough to shuffle simulated events.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns a number less than `n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// Returns a text to insert, which may have newlines and multibyte characters.
+    pub fn insertion(&mut self) -> &'static str {
+        ["a", "bc", "\n", "世", "d\ne"][self.below(5)]
+    }
+}
+
+/// Runs a simulation once for each of many seeds.
+pub fn simulate<F: FnMut(&mut Rng)>(mut f: F) {
+    for seed in 1..60u64 {
+        f(&mut Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15)));
+    }
+}
+
+/// Asserts that every replica has the same text.
+pub fn assert_converged<I: IntoIterator<Item = String>>(texts: I) {
+    let mut texts = texts.into_iter();
+    if let Some(first) = texts.next() {
+        for text in texts {
+            assert_eq!(text, first);
+        }
+    }
+}